@@ -300,7 +300,9 @@ class MultiParentCasperImpl[F[_]: Sync: Log: Time: SafetyOracle: BlockStore: Blo
                        merged,
                        remaining,
                        justifications,
-                       protocolVersion
+                       protocolVersion,
+                       number,
+                       ByteString.copyFrom(publicKey)
                      )
                    } else {
                      CreateBlockStatus.noNewDeploys.pure[F]
@@ -348,7 +350,9 @@ class MultiParentCasperImpl[F[_]: Sync: Log: Time: SafetyOracle: BlockStore: Blo
       merged: ExecEngineUtil.MergeResult[ExecEngineUtil.TransformMap, Block],
       deploys: Seq[Deploy],
       justifications: Seq[Justification],
-      protocolVersion: ProtocolVersion
+      protocolVersion: ProtocolVersion,
+      height: Long,
+      proposer: ByteString
   ): F[CreateBlockStatus] =
     (for {
       now <- Time[F].currentMillis
@@ -357,7 +361,8 @@ class MultiParentCasperImpl[F[_]: Sync: Log: Time: SafetyOracle: BlockStore: Blo
                       .computeDeploysCheckpoint[F](
                         merged,
                         deploys,
-                        protocolVersion
+                        protocolVersion,
+                        ipc.BlockContext(now, height, proposer)
                       )
       DeploysCheckpoint(preStateHash, postStateHash, deploysForBlock, number, protocolVersion) = stateResult
       //TODO: compute bonds properly
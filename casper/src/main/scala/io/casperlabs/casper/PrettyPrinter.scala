@@ -26,6 +26,8 @@ object PrettyPrinter {
       s"AddBigInt(${value.get.value})"
     case ipc.Transform.TransformInstance.AddKeys(ipc.TransformAddKeys(ks)) =>
       s"Insert(${ks.map(buildString).mkString(",")})"
+    case ipc.Transform.TransformInstance.RemoveKeys(ipc.TransformRemoveKeys(removed, added)) =>
+      s"Remove(${removed.mkString(",")})Insert(${added.map(buildString).mkString(",")})"
     case ipc.Transform.TransformInstance.Failure(_)  => "TransformFailure"
     case ipc.Transform.TransformInstance.Identity(_) => "Read"
     case ipc.Transform.TransformInstance.Write(ipc.TransformWrite(mv)) =>
@@ -47,10 +49,10 @@ object PrettyPrinter {
 
   def buildString(v: ipc.Value): String = v.valueInstance match {
     case ipc.Value.ValueInstance.Empty => "ValueEmpty"
-    case ipc.Value.ValueInstance.Account(ipc.Account(pk, nonce, urefs, associatedKeys)) =>
+    case ipc.Value.ValueInstance.Account(ipc.Account(pk, nonce, urefs, associatedKeys, _, _)) =>
       s"Account(${buildString(pk)}, $nonce, {${urefs.map(buildString).mkString(",")}}, {${associatedKeys.map(buildString).mkString(",")})"
     case ipc.Value.ValueInstance.ByteArr(bytes) => s"ByteArray(${buildString(bytes)})"
-    case ipc.Value.ValueInstance.Contract(ipc.Contract(body, urefs, protocolVersion)) =>
+    case ipc.Value.ValueInstance.Contract(ipc.Contract(body, urefs, protocolVersion, _)) =>
       s"Contract(${buildString(body)}, {${urefs.map(buildString).mkString(",")}}, ${buildString(protocolVersion)})"
     case ipc.Value.ValueInstance.IntList(ipc.IntList(list))       => s"List(${list.mkString(",")})"
     case ipc.Value.ValueInstance.Integer(i)                       => s"Int32($i)"
@@ -59,6 +61,22 @@ object PrettyPrinter {
     case ipc.Value.ValueInstance.StringVal(s)                     => s"String($s)"
     case ipc.Value.ValueInstance.BigInt(v)                        => s"BigInt(${v.value})"
     case ipc.Value.ValueInstance.Key(key)                         => buildString(key)
+    case ipc.Value.ValueInstance.BoolVal(b)                       => s"Bool($b)"
+    case ipc.Value.ValueInstance.Unit(_)                          => "Unit"
+    case ipc.Value.ValueInstance.OptionVal(ipc.OptionValue(value)) =>
+      value.fold("None")(v => s"Some(${buildString(v)})")
+    case ipc.Value.ValueInstance.Tuple(ipc.Tuple(values)) =>
+      s"Tuple(${values.map(buildString).mkString(",")})"
+    case ipc.Value.ValueInstance.KeyList(ipc.KeyList(keys)) =>
+      s"List(${keys.map(buildString).mkString(",")})"
+    case ipc.Value.ValueInstance.List(ipc.ValueList(values)) =>
+      s"List(${values.map(buildString).mkString(",")})"
+    case ipc.Value.ValueInstance.Map(ipc.ValueMap(entries)) =>
+      val printed = entries.map {
+        case ipc.ValueMap.Entry(key, value) =>
+          s"${key.fold("ValueEmpty")(k => buildString(k))} -> ${value.fold("ValueEmpty")(v => buildString(v))}"
+      }
+      s"Map(${printed.mkString(",")})"
   }
 
   def buildString(b: BlockMessage): String =
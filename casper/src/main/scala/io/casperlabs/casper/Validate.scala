@@ -12,7 +12,7 @@ import io.casperlabs.casper.consensus.{Block, Bond}, Block.Justification
 import io.casperlabs.casper.util.ProtoUtil.bonds
 import io.casperlabs.casper.util.execengine.ExecEngineUtil
 import io.casperlabs.casper.util.execengine.ExecEngineUtil.StateHash
-import io.casperlabs.casper.util.{DagOperations, ProtoUtil}
+import io.casperlabs.casper.util.{CasperLabsProtocolVersions, DagOperations, ProtoUtil}
 import io.casperlabs.catscontrib.MonadThrowable
 import io.casperlabs.crypto.Keys.{PublicKey, PublicKeyBS, Signature}
 import io.casperlabs.crypto.hash.Blake2b256
@@ -710,7 +710,8 @@ object Validate {
       for {
         possiblePostState <- ExecutionEngineService[F].commit(
                               preStateHash,
-                              effects
+                              effects,
+                              CasperLabsProtocolVersions.thresholdsVersionMap.fromBlock(block)
                             )
         //TODO: distinguish "internal errors" and "user errors"
         _ <- possiblePostState match {
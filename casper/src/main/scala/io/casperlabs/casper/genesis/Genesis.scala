@@ -64,7 +64,8 @@ object Genesis {
                                blessedTerms.map(deployDataToEEDeploy),
                                CasperLabsProtocolVersions.thresholdsVersionMap.fromBlock(
                                  initial
-                               )
+                               ),
+                               ExecEngineUtil.blockContext(initial)
                              )
                          )
       // TODO: We shouldn't need to do any commutivity checking for the genesis block.
@@ -80,7 +81,11 @@ object Genesis {
             s"Commiting blessed deploy effects onto starting hash ${Base16.encode(startHash.toByteArray)}..."
           )
       postStateHash <- MonadError[F, Throwable].rethrow(
-                        ExecutionEngineService[F].commit(startHash, transforms)
+                        ExecutionEngineService[F].commit(
+                          startHash,
+                          transforms,
+                          CasperLabsProtocolVersions.thresholdsVersionMap.fromBlock(initial)
+                        )
                       )
       stateWithContracts = initial.getHeader.getState
         .withPreStateHash(ExecutionEngineService[F].emptyStateHash)
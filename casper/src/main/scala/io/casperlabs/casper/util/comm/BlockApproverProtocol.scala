@@ -21,6 +21,7 @@ import io.casperlabs.comm.transport
 import io.casperlabs.comm.transport.{Blob, TransportLayer}
 import io.casperlabs.crypto.Keys.PublicKey
 import io.casperlabs.crypto.hash.Blake2b256
+import io.casperlabs.ipc.BlockContext
 import io.casperlabs.models.InternalProcessedDeploy
 import io.casperlabs.shared._
 import io.casperlabs.smartcontracts.ExecutionEngineService
@@ -155,7 +156,8 @@ object BlockApproverProtocol {
                              ExecutionEngineService[F].emptyStateHash,
                              deploys
                                .map(ProtoUtil.deployDataToEEDeploy),
-                             protocolVersion
+                             protocolVersion,
+                             BlockContext(time = timestamp, height = postState.blockNumber)
                            )
                          ).leftMap(_.getMessage)
       deployEffects = ExecEngineUtil.findCommutingEffects(
@@ -164,7 +166,11 @@ object BlockApproverProtocol {
       transforms = ExecEngineUtil.extractTransforms(deployEffects)
       postStateHash <- EitherT(
                         ExecutionEngineService[F]
-                          .commit(ExecutionEngineService[F].emptyStateHash, transforms)
+                          .commit(
+                            ExecutionEngineService[F].emptyStateHash,
+                            transforms,
+                            protocolVersion
+                          )
                       ).leftMap(_.getMessage)
       _ <- EitherT(
             (postStateHash == postState.postStateHash)
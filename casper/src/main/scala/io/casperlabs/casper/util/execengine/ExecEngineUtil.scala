@@ -33,23 +33,29 @@ case class DeploysCheckpoint(
 object ExecEngineUtil {
   type StateHash = ByteString
 
+  /** A deploy with its cost and its effects. Failed deploys are `Left` and carry the effects
+    * which have to be committed even though they failed (i.e. the nonce increment). */
+  type DeployEffects = (Deploy, Long, Either[ExecutionEffect, ExecutionEffect])
+
   def computeDeploysCheckpoint[F[_]: MonadError[?[_], Throwable]: BlockStore: Log: ExecutionEngineService](
       merged: MergeResult[TransformMap, Block],
       deploys: Seq[Deploy],
-      protocolVersion: ProtocolVersion
+      protocolVersion: ProtocolVersion,
+      blockContext: BlockContext
   ): F[DeploysCheckpoint] =
     for {
       preStateHash <- computePrestate[F](merged)
       processedDeploys <- processDeploys[F](
                            preStateHash,
                            deploys,
-                           protocolVersion
+                           protocolVersion,
+                           blockContext
                          )
       deployEffects   = findCommutingEffects(processedDeployEffects(deploys zip processedDeploys))
       deploysForBlock = extractProcessedDepoys(deployEffects)
       transforms      = extractTransforms(deployEffects)
       postStateHash <- MonadError[F, Throwable].rethrow(
-                        ExecutionEngineService[F].commit(preStateHash, transforms)
+                        ExecutionEngineService[F].commit(preStateHash, transforms, protocolVersion)
                       )
       maxBlockNumber = merged.parents.foldl(-1L) {
         case (acc, b) => math.max(acc, blockNumber(b))
@@ -69,71 +75,73 @@ object ExecEngineUtil {
   def processDeploys[F[_]: MonadError[?[_], Throwable]: BlockStore: ExecutionEngineService](
       prestate: StateHash,
       deploys: Seq[Deploy],
-      protocolVersion: ProtocolVersion
+      protocolVersion: ProtocolVersion,
+      blockContext: BlockContext
   ): F[Seq[DeployResult]] =
     ExecutionEngineService[F]
-      .exec(prestate, deploys.map(ProtoUtil.deployDataToEEDeploy), protocolVersion)
+      .exec(prestate, deploys.map(ProtoUtil.deployDataToEEDeploy), protocolVersion, blockContext)
       .rethrow
 
+  /** Context of the deploys executed in `block`, as seen by the contracts they call. */
+  def blockContext(block: Block): BlockContext =
+    BlockContext(
+      time = block.getHeader.timestamp,
+      height = block.getHeader.rank,
+      proposer = block.getHeader.validatorPublicKey
+    )
+
   /** Produce effects for each processed deploy. */
   def processedDeployEffects(
       deployResults: Seq[(Deploy, DeployResult)]
-  ): Seq[(Deploy, Long, Option[ExecutionEffect])] =
+  ): Seq[DeployEffects] =
     deployResults.map {
-      case (deploy, DeployResult(_, DeployResult.Result.Empty)) =>
-        (deploy, 0L, None) //This should never happen either
-      case (deploy, DeployResult(cost, DeployResult.Result.Error(_))) =>
-        (deploy, cost, None)
-      case (deploy, DeployResult(cost, DeployResult.Result.Effects(eff))) =>
-        (deploy, cost, Some(eff))
+      case (deploy, deployResult) =>
+        deployResult.result match {
+          case DeployResult.Result.Empty =>
+            (deploy, 0L, Left(ExecutionEffect())) //This should never happen either
+          case DeployResult.Result.Error(_) =>
+            (deploy, deployResult.cost, Left(deployResult.getErrorEffects))
+          case DeployResult.Result.Effects(eff) =>
+            (deploy, deployResult.cost, Right(eff))
+        }
     }
 
   //TODO: Logic for picking the commuting group? Prioritize highest revenue? Try to include as many deploys as possible?
   def findCommutingEffects(
-      deployEffects: Seq[(Deploy, Long, Option[ExecutionEffect])]
-  ): Seq[(Deploy, Long, Option[ExecutionEffect])] = {
-    val (errors, errorFree) = deployEffects.span(_._3.isEmpty)
-
-    val nonConflicting = errorFree.toList match {
-      case Nil                           => Nil
-      case (head @ (_, _, eff0)) :: tail =>
-        // the `eff0.get` call is safe because of the `span` above which separates into Some and None cases
-        val (result, _) = tail.foldLeft(Vector(head) -> Op.fromIpcEntry(eff0.get.opMap)) {
-          case (unchanged @ (acc, totalOps), next @ (_, _, Some(eff))) =>
-            val ops = Op.fromIpcEntry(eff.opMap)
-            if (totalOps ~ ops)
-              (acc :+ next, totalOps + ops)
-            else
-              unchanged
-        }
-
-        result
-    }
+      deployEffects: Seq[DeployEffects]
+  ): Seq[DeployEffects] = {
+    // Failed deploys are checked as well: the effects they leave behind
+    // (the nonce increment) conflict with other deploys of the same account.
+    val (nonConflicting, _) =
+      deployEffects.foldLeft(Vector.empty[DeployEffects] -> Op.fromIpcEntry(Nil)) {
+        case (unchanged @ (acc, totalOps), next @ (_, _, eff)) =>
+          val ops = Op.fromIpcEntry(eff.merge.opMap)
+          if (totalOps ~ ops)
+            (acc :+ next, totalOps + ops)
+          else
+            unchanged
+      }
 
-    // We include errors because we define them as
-    // commuting with everything since we will never
-    // re-run them (this is a policy decision we have made) and
-    // they touch no keys because we rolled back the changes
-    nonConflicting ++ errors
+    nonConflicting
   }
 
   def extractProcessedDepoys(
-      commutingEffects: Seq[(Deploy, Long, Option[ExecutionEffect])]
+      commutingEffects: Seq[DeployEffects]
   ): Seq[Block.ProcessedDeploy] =
     commutingEffects.map {
-      case (deploy, cost, maybeEffect) => {
+      case (deploy, cost, eff) => {
         Block.ProcessedDeploy(
           Some(deploy),
           cost,
-          maybeEffect.isEmpty // `None` means there was an error
+          eff.isLeft // `Left` means there was an error
         )
       }
     }
 
   def extractTransforms(
-      commutingEffects: Seq[(Deploy, Long, Option[ExecutionEffect])]
+      commutingEffects: Seq[DeployEffects]
   ): Seq[TransformEntry] =
-    commutingEffects.collect { case (_, _, Some(eff)) => eff.transformMap }.flatten
+    commutingEffects.flatMap { case (_, _, eff) => eff.merge.transformMap }
 
   def effectsForBlock[F[_]: Sync: BlockStore: ExecutionEngineService](
       block: Block,
@@ -147,7 +155,8 @@ object ExecEngineUtil {
       processedDeploys <- processDeploys[F](
                            prestate,
                            deploys.flatMap(_.deploy),
-                           protocolVersion
+                           protocolVersion,
+                           blockContext(block)
                          )
       deployEffects = processedDeployEffects(deploys.map(_.getDeploy) zip processedDeploys)
       transformMap  = (findCommutingEffects _ andThen extractTransforms)(deployEffects)
@@ -161,9 +170,10 @@ object ExecEngineUtil {
     case MergeResult.Result(soleParent, _, others) if others.isEmpty =>
       ProtoUtil.postStateHash(soleParent).pure[F] //single parent
     case MergeResult.Result(initParent, nonFirstParentsCombinedEffect, _) => //multiple parents
-      val prestate = ProtoUtil.postStateHash(initParent)
+      val prestate        = ProtoUtil.postStateHash(initParent)
+      val protocolVersion = CasperLabsProtocolVersions.thresholdsVersionMap.fromBlock(initParent)
       MonadError[F, Throwable].rethrow(
-        ExecutionEngineService[F].commit(prestate, nonFirstParentsCombinedEffect)
+        ExecutionEngineService[F].commit(prestate, nonFirstParentsCombinedEffect, protocolVersion)
       )
   }
 
@@ -24,7 +24,7 @@ import io.casperlabs.casper.util.execengine.{
 import io.casperlabs.crypto.Keys.PrivateKey
 import io.casperlabs.crypto.codec.Base16
 import io.casperlabs.crypto.signatures.SignatureAlgorithm.Ed25519
-import io.casperlabs.ipc.ProtocolVersion
+import io.casperlabs.ipc.{BlockContext, ProtocolVersion}
 import io.casperlabs.p2p.EffectsTestInstances.LogStub
 import io.casperlabs.shared.Time
 import io.casperlabs.smartcontracts.ExecutionEngineService
@@ -885,7 +885,8 @@ class ValidateTest
         deploysCheckpoint <- ExecEngineUtil.computeDeploysCheckpoint[Task](
                               ExecEngineUtil.MergeResult.empty,
                               deploys,
-                              ProtocolVersion(1)
+                              ProtocolVersion(1),
+                              BlockContext()
                             )
         DeploysCheckpoint(preStateHash, computedPostStateHash, processedDeploys, _, _) = deploysCheckpoint
         block <- createBlock[Task](
@@ -89,7 +89,8 @@ object BlockGenerator {
       result <- computeDeploysCheckpoint[F](
                  merged,
                  deploys,
-                 ProtocolVersion(1)
+                 ProtocolVersion(1),
+                 ExecEngineUtil.blockContext(b)
                )
     } yield result
 
@@ -265,19 +265,21 @@ object HashSetCasperTestNode {
       override def exec(
           prestate: ByteString,
           deploys: Seq[Deploy],
-          protocolVersion: ipc.ProtocolVersion
+          protocolVersion: ipc.ProtocolVersion,
+          blockContext: ipc.BlockContext
       ): F[Either[Throwable, Seq[DeployResult]]] =
         //This function returns the same `DeployResult` for all deploys,
         //regardless of their wasm code. It pretends to have run all the deploys,
         //but it doesn't really; it just returns the same result no matter what.
         deploys
-          .map(d => DeployResult(10, DeployResult.Result.Effects(getExecutionEffect(d))))
+          .map(d => DeployResult(cost = 10, result = DeployResult.Result.Effects(getExecutionEffect(d))))
           .asRight[Throwable]
           .pure[F]
 
       override def commit(
           prestate: ByteString,
-          effects: Seq[TransformEntry]
+          effects: Seq[TransformEntry],
+          protocolVersion: ipc.ProtocolVersion
       ): F[Either[Throwable, ByteString]] = {
         //This function increments the prestate by interpreting as an integer and adding 1.
         //The purpose of this is simply to have the output post-state be different
@@ -119,7 +119,8 @@ class ExecEngineUtilTest
                         .computeDeploysCheckpoint[Task](
                           ExecEngineUtil.MergeResult.empty,
                           deploy,
-                          protocolVersion
+                          protocolVersion,
+                          BlockContext()
                         )
       DeploysCheckpoint(_, _, result, _, _) = computeResult
     } yield result
@@ -174,7 +175,8 @@ class ExecEngineUtilTest
           checkpoint <- ExecEngineUtil.computeDeploysCheckpoint[Task](
                          merged = ExecEngineUtil.MergeResult.empty,
                          deploys = deploys,
-                         ProtocolVersion(1)
+                         ProtocolVersion(1),
+                         BlockContext()
                        )
         } yield {
           val processedDeploys = checkpoint.deploysForBlock.map(_.getDeploy)
@@ -208,7 +210,7 @@ class ExecEngineUtilTest
                 ExecutionEffect(Seq(opEntry), Seq(transforEntry))
               }
               deploys
-                .map(d => DeployResult(10, DeployResult.Result.Effects(getExecutionEffect(d))))
+                .map(d => DeployResult(cost = 10, result = DeployResult.Result.Effects(getExecutionEffect(d))))
                 .asRight[Throwable]
             },
           (_, _) => new Throwable("failed when commit transform").asLeft.pure[Task],
@@ -277,6 +279,84 @@ class ExecEngineUtilTest
       } yield ()
   }
 
+  "computeDeploysCheckpoint" should "commit the nonce increment of failed deploys" in withStorage {
+    implicit blockStore => implicit blockDagStorage =>
+      val accountKey =
+        Key(Key.KeyInstance.Account(KeyAddress(ByteString.copyFrom(Array.fill(32)(1.toByte)))))
+      val bumpedAccount = Value(Value.ValueInstance.Account(ipc.Account(nonce = 1)))
+      val nonceIncrement = ExecutionEffect(
+        Seq(OpEntry(Some(accountKey), Some(ipc.Op(ipc.Op.OpInstance.Write(WriteOp()))))),
+        Seq(
+          TransformEntry(
+            Some(accountKey),
+            Some(Transform(Transform.TransformInstance.Write(TransformWrite(Some(bumpedAccount)))))
+          )
+        )
+      )
+      // Keeps the values written by the committed effects, so the post-state can be queried.
+      var postState = Map.empty[Key, Value]
+
+      implicit val executionEngineService: ExecutionEngineService[Task] =
+        mock[Task](
+          (_, deploys, _) =>
+            Task.now {
+              deploys
+                .map(
+                  _ =>
+                    DeployResult(
+                      cost = 10,
+                      result = DeployResult.Result.Error(
+                        DeployError(DeployError.DeployErrors.RevertErr(RevertError(1)))
+                      ),
+                      errorEffects = Some(nonceIncrement)
+                    )
+                )
+                .asRight[Throwable]
+            },
+          (_, effects) =>
+            Task.now {
+              postState ++= effects.collect {
+                case TransformEntry(
+                    Some(key),
+                    Some(Transform(Transform.TransformInstance.Write(TransformWrite(Some(value)))))
+                    ) =>
+                  key -> value
+              }
+              ByteString.copyFromUtf8("post-state").asRight[Throwable]
+            },
+          (_, key, _) =>
+            Task.now(
+              postState
+                .get(key)
+                .toRight[Throwable](new SmartContractEngineError("key not found"))
+            ),
+          _ => Seq.empty[Bond].pure[Task],
+          _ => Task.unit,
+          _ => ().asRight[String].pure[Task]
+        )
+
+      val deploy = ProtoUtil.sourceDeploy(
+        ByteString.copyFromUtf8("revert"),
+        System.currentTimeMillis(),
+        Integer.MAX_VALUE
+      )
+
+      for {
+        checkpoint <- ExecEngineUtil.computeDeploysCheckpoint[Task](
+                       ExecEngineUtil.MergeResult.empty,
+                       Seq(deploy),
+                       ProtocolVersion(1),
+                       BlockContext()
+                     )
+        account <- ExecutionEngineService[Task]
+                    .query(checkpoint.postStateHash, accountKey, Seq.empty)
+                    .rethrow
+      } yield {
+        checkpoint.deploysForBlock.map(_.isError) shouldBe Seq(true)
+        account.getAccount.nonce shouldBe 1
+      }
+  }
+
   "abstractMerge" should "do nothing in the case of zero or one candidates" in {
     val genesis = OpDagNode.genesis(Map(1     -> Op.Read))
     val tip     = OpDagNode.withParents(Map(2 -> Op.Write), List(genesis))
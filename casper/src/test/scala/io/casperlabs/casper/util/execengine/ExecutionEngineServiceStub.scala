@@ -50,12 +50,14 @@ object ExecutionEngineServiceStub {
     override def exec(
         prestate: ByteString,
         deploys: Seq[Deploy],
-        protocolVersion: ProtocolVersion
+        protocolVersion: ProtocolVersion,
+        blockContext: BlockContext
     ): F[Either[Throwable, Seq[DeployResult]]] =
       execFunc(prestate, deploys, protocolVersion)
     override def commit(
         prestate: ByteString,
-        effects: Seq[TransformEntry]
+        effects: Seq[TransformEntry],
+        protocolVersion: ProtocolVersion
     ): F[Either[Throwable, ByteString]] = commitFunc(prestate, effects)
     override def computeBonds(hash: ByteString)(implicit log: Log[F]): F[Seq[Bond]] =
       computeBondsFunc(hash)
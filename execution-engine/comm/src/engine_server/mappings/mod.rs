@@ -1,16 +1,20 @@
 mod uint;
 
 use protobuf::ProtobufEnum;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::{TryFrom, TryInto};
 
-use common::value::account::{AssociatedKeys, PublicKey, Weight};
+use common::value::account::{ActionThresholds, AssociatedKeys, PublicKey, PurseId, Weight};
 use engine_server::ipc::KeyURef_AccessRights;
+use execution_engine::engine_state::block_context::BlockContext;
 use execution_engine::engine_state::error::{Error as EngineError, RootNotFound};
+use execution_engine::engine_state::event::Event;
 use execution_engine::engine_state::execution_effect::ExecutionEffect;
-use execution_engine::engine_state::execution_result::ExecutionResult;
+use execution_engine::engine_state::execution_result::{ExecutionResult, ViewResult};
+use execution_engine::engine_state::merge::MergeResult;
 use execution_engine::engine_state::op::Op;
 use execution_engine::execution::Error as ExecutionError;
+use execution_engine::trace::HostCall;
 use ipc;
 use shared::logging;
 use shared::logging::log_level;
@@ -20,6 +24,8 @@ use storage::global_state::{CommitResult, History};
 
 /// Helper method for turning instances of Value into Transform::Write.
 fn transform_write(v: common::value::Value) -> Result<transform::Transform, ParsingError> {
+    v.check_limits()
+        .map_err(|error| ParsingError(format!("Invalid value to write: {}", error)))?;
     Ok(transform::Transform::Write(v))
 }
 
@@ -47,99 +53,190 @@ impl TryFrom<&super::ipc::Transform> for transform::Transform {
                 })
                 .collect::<Result<BTreeMap<String, common::key::Key>, ParsingError>>()?;
             Ok(transform::Transform::AddKeys(keys_map))
+        } else if tr.has_remove_keys() {
+            let remove_keys = tr.get_remove_keys();
+            let removed = remove_keys.get_removed().iter().cloned().collect();
+            let added: URefMap = remove_keys.get_added().try_into()?;
+            Ok(transform::Transform::RemoveKeys {
+                removed,
+                added: added.0,
+            })
         } else if tr.has_add_i32() {
             Ok(transform::Transform::AddInt32(tr.get_add_i32().value))
         } else if tr.has_add_big_int() {
             let b = tr.get_add_big_int().get_value();
             let v = b.try_into()?;
             match v {
+                common::value::Value::UInt64(u) => Ok(u.into()),
                 common::value::Value::UInt128(u) => Ok(u.into()),
                 common::value::Value::UInt256(u) => Ok(u.into()),
                 common::value::Value::UInt512(u) => Ok(u.into()),
                 other => parse_error(format!("Through some impossibility a RustBigInt was turned into a non-uint value type: ${:?}", other))
             }
         } else if tr.has_write() {
-            let v = tr.get_write().get_value();
-            if v.has_integer() {
-                transform_write(common::value::Value::Int32(v.get_integer()))
-            } else if v.has_big_int() {
-                let b = v.get_big_int();
-                let v = b.try_into()?;
-                transform_write(v)
-            } else if v.has_byte_arr() {
-                let v: Vec<u8> = Vec::from(v.get_byte_arr());
-                transform_write(common::value::Value::ByteArray(v))
-            } else if v.has_int_list() {
-                let list = v.get_int_list().list.clone();
-                transform_write(common::value::Value::ListInt32(list))
-            } else if v.has_string_val() {
-                transform_write(common::value::Value::String(v.get_string_val().to_string()))
-            } else if v.has_account() {
-                let pub_key = {
-                    let mut tmp = [0u8; 32];
-                    tmp.clone_from_slice(&v.get_account().pub_key);
-                    tmp
-                };
-                let uref_map: URefMap = v.get_account().get_known_urefs().try_into()?;
-                let associated_keys: AssociatedKeys = {
-                    let mut keys = AssociatedKeys::empty();
-                    v.get_account()
-                        .get_associated_keys()
-                        .iter()
-                        .try_for_each(|k| {
-                            k.try_into().and_then(|(pub_key, weight)| {
-                                match keys.add_key(pub_key, weight) {
-                                    Err(add_key_failure) => parse_error(format!(
-                                        "Error when parsing associated keys: {:?}",
-                                        add_key_failure
-                                    )),
-                                    Ok(_) => Ok(()),
-                                }
-                            })
-                        })?;
-                    keys
-                };
-                let account = common::value::Account::new(
-                    pub_key,
-                    v.get_account().nonce,
-                    uref_map.0,
-                    associated_keys,
-                );
-                transform_write(common::value::Value::Account(account))
-            } else if v.has_contract() {
-                let ipc_contr = v.get_contract();
-                let contr_body = ipc_contr.get_body().to_vec();
-                let known_urefs: URefMap = ipc_contr.get_known_urefs().try_into()?;
-                let protocol_version = if ipc_contr.has_protocol_version() {
-                    ipc_contr.get_protocol_version()
+            transform_write(tr.get_write().get_value().try_into()?)
+        } else {
+            parse_error("TransformEntry couldn't be parsed to known Transform.".to_owned())
+        }
+    }
+}
+
+impl TryFrom<&super::ipc::Value> for common::value::Value {
+    type Error = ParsingError;
+    fn try_from(v: &super::ipc::Value) -> Result<common::value::Value, ParsingError> {
+        if v.has_integer() {
+            Ok(common::value::Value::Int32(v.get_integer()))
+        } else if v.has_big_int() {
+            v.get_big_int().try_into()
+        } else if v.has_byte_arr() {
+            let v: Vec<u8> = Vec::from(v.get_byte_arr());
+            Ok(common::value::Value::ByteArray(v))
+        } else if v.has_int_list() {
+            let list = v.get_int_list().list.clone();
+            Ok(common::value::Value::ListInt32(list))
+        } else if v.has_string_val() {
+            Ok(common::value::Value::String(v.get_string_val().to_string()))
+        } else if v.has_account() {
+            let pub_key = {
+                let mut tmp = [0u8; 32];
+                tmp.clone_from_slice(&v.get_account().pub_key);
+                tmp
+            };
+            let uref_map: URefMap = v.get_account().get_known_urefs().try_into()?;
+            let associated_keys: AssociatedKeys = {
+                let mut keys = AssociatedKeys::empty();
+                v.get_account()
+                    .get_associated_keys()
+                    .iter()
+                    .try_for_each(|k| {
+                        k.try_into().and_then(|(pub_key, weight)| {
+                            match keys.add_key(pub_key, weight) {
+                                Err(add_key_failure) => parse_error(format!(
+                                    "Error when parsing associated keys: {:?}",
+                                    add_key_failure
+                                )),
+                                Ok(_) => Ok(()),
+                            }
+                        })
+                    })?;
+                keys
+            };
+            let purse_id = {
+                let ipc_purse_id = v.get_account().get_purse_id();
+                if ipc_purse_id.len() != 32 {
+                    return parse_error("Purse id should have 32 bytes.".to_string());
+                }
+                let mut tmp = [0u8; 32];
+                tmp.copy_from_slice(ipc_purse_id);
+                PurseId::new(tmp)
+            };
+            let action_thresholds: ActionThresholds = {
+                let ipc_account = v.get_account();
+                if ipc_account.has_action_thresholds() {
+                    ipc_account.get_action_thresholds().try_into()?
                 } else {
-                    return parse_error("Protocol version is not specified".to_string());
-                };
-                let contract = common::value::Contract::new(
+                    return parse_error("Action thresholds are not specified".to_string());
+                }
+            };
+            let account = common::value::Account::new(
+                pub_key,
+                v.get_account().nonce,
+                uref_map.0,
+                associated_keys,
+                purse_id,
+                action_thresholds,
+            );
+            Ok(common::value::Value::Account(account))
+        } else if v.has_contract() {
+            let ipc_contr = v.get_contract();
+            let contr_body = ipc_contr.get_body().to_vec();
+            let known_urefs: URefMap = ipc_contr.get_known_urefs().try_into()?;
+            let protocol_version = if ipc_contr.has_protocol_version() {
+                ipc_contr.get_protocol_version()
+            } else {
+                return parse_error("Protocol version is not specified".to_string());
+            };
+            let contract = match ipc_contr.get_admin_uref() {
+                [] => common::value::Contract::new(
                     contr_body,
                     known_urefs.0,
                     protocol_version.version,
-                );
-                transform_write(contract.into())
-            } else if v.has_string_list() {
-                let list = v.get_string_list().list.to_vec();
-                transform_write(common::value::Value::ListString(list))
-            } else if v.has_named_key() {
-                let nk = v.get_named_key();
-                let name = nk.get_name().to_string();
-                let key = nk.get_key().try_into()?;
-                transform_write(common::value::Value::NamedKey(name, key))
-            } else if v.has_key() {
-                let key = v.get_key().try_into()?;
-                transform_write(common::value::Value::Key(key))
+                ),
+                admin_uref if admin_uref.len() == 32 => {
+                    let mut admin = [0u8; 32];
+                    admin.copy_from_slice(admin_uref);
+                    common::value::Contract::upgradable(
+                        contr_body,
+                        known_urefs.0,
+                        protocol_version.version,
+                        admin,
+                    )
+                }
+                _ => return parse_error("Admin URef should have 32 bytes.".to_string()),
+            };
+            Ok(contract.into())
+        } else if v.has_string_list() {
+            let list = v.get_string_list().list.to_vec();
+            Ok(common::value::Value::ListString(list))
+        } else if v.has_named_key() {
+            let nk = v.get_named_key();
+            let name = nk.get_name().to_string();
+            let key = nk.get_key().try_into()?;
+            Ok(common::value::Value::NamedKey(name, key))
+        } else if v.has_key() {
+            let key = v.get_key().try_into()?;
+            Ok(common::value::Value::Key(key))
+        } else if v.has_bool_val() {
+            Ok(common::value::Value::Bool(v.get_bool_val()))
+        } else if v.has_unit() {
+            Ok(common::value::Value::Unit)
+        } else if v.has_option_val() {
+            let option_val = v.get_option_val();
+            if option_val.has_value() {
+                let value: common::value::Value = option_val.get_value().try_into()?;
+                Ok(common::value::Value::Option(Some(Box::new(value))))
             } else {
-                parse_error(format!(
-                    "TransformEntry write contained unknown value: {:?}",
-                    v
-                ))
+                Ok(common::value::Value::Option(None))
             }
+        } else if v.has_tuple() {
+            let values = v
+                .get_tuple()
+                .get_values()
+                .iter()
+                .map(TryInto::try_into)
+                .collect::<Result<Vec<common::value::Value>, ParsingError>>()?;
+            Ok(common::value::Value::Tuple(values))
+        } else if v.has_key_list() {
+            let keys = v
+                .get_key_list()
+                .get_keys()
+                .iter()
+                .map(TryInto::try_into)
+                .collect::<Result<Vec<common::key::Key>, ParsingError>>()?;
+            Ok(common::value::Value::ListKey(keys))
+        } else if v.has_list() {
+            let values = v
+                .get_list()
+                .get_values()
+                .iter()
+                .map(TryInto::try_into)
+                .collect::<Result<Vec<common::value::Value>, ParsingError>>()?;
+            Ok(common::value::Value::List(values))
+        } else if v.has_map() {
+            let mut map = BTreeMap::new();
+            for entry in v.get_map().get_entries() {
+                let key: common::value::Value = entry.get_key().try_into()?;
+                let value: common::value::Value = entry.get_value().try_into()?;
+                if map.insert(key, value).is_some() {
+                    return parse_error(format!(
+                        "Map contained duplicate key: {:?}",
+                        entry.get_key()
+                    ));
+                }
+            }
+            Ok(common::value::Value::Map(map))
         } else {
-            parse_error("TransformEntry couldn't be parsed to known Transform.".to_owned())
+            parse_error(format!("Value contained unknown instance: {:?}", v))
         }
     }
 }
@@ -151,6 +248,7 @@ impl From<common::value::Value> for super::ipc::Value {
             common::value::Value::Int32(i) => {
                 tv.set_integer(i);
             }
+            common::value::Value::UInt64(u) => tv.set_big_int(u.into()),
             common::value::Value::UInt128(u) => tv.set_big_int(u.into()),
             common::value::Value::UInt256(u) => tv.set_big_int(u.into()),
             common::value::Value::UInt512(u) => tv.set_big_int(u.into()),
@@ -186,13 +284,19 @@ impl From<common::value::Value> for super::ipc::Value {
                 let mut acc = super::ipc::Account::new();
                 acc.set_pub_key(account.pub_key().to_vec());
                 acc.set_nonce(account.nonce());
+                acc.set_purse_id(account.purse_id().value().to_vec());
+                acc.set_action_thresholds(account.action_thresholds().into());
                 let urefs = URefMap(account.get_urefs_lookup()).into();
                 acc.set_known_urefs(protobuf::RepeatedField::from_vec(urefs));
                 tv.set_account(acc);
             }
             common::value::Value::Contract(contract) => {
+                let admin = contract.admin();
                 let (bytes, known_urefs, protocol_version) = contract.destructure();
                 let mut contr = super::ipc::Contract::new();
+                if let Some(admin) = admin {
+                    contr.set_admin_uref(admin.to_vec());
+                }
                 let urefs = URefMap(known_urefs).into();
                 contr.set_body(bytes);
                 contr.set_known_urefs(protobuf::RepeatedField::from_vec(urefs));
@@ -201,6 +305,51 @@ impl From<common::value::Value> for super::ipc::Value {
                 contr.set_protocol_version(protocol);
                 tv.set_contract(contr);
             }
+            common::value::Value::Bool(b) => {
+                tv.set_bool_val(b);
+            }
+            common::value::Value::Unit => {
+                tv.set_unit(super::ipc::Unit::new());
+            }
+            common::value::Value::Option(option) => {
+                let mut option_val = super::ipc::OptionValue::new();
+                if let Some(value) = option {
+                    option_val.set_value((*value).into());
+                }
+                tv.set_option_val(option_val);
+            }
+            common::value::Value::Tuple(values) => {
+                let mut tuple = super::ipc::Tuple::new();
+                let values = values.into_iter().map(Into::into).collect();
+                tuple.set_values(protobuf::RepeatedField::from_vec(values));
+                tv.set_tuple(tuple);
+            }
+            common::value::Value::ListKey(keys) => {
+                let mut key_list = super::ipc::KeyList::new();
+                let keys = keys.iter().map(Into::into).collect();
+                key_list.set_keys(protobuf::RepeatedField::from_vec(keys));
+                tv.set_key_list(key_list);
+            }
+            common::value::Value::List(values) => {
+                let mut list = super::ipc::ValueList::new();
+                let values = values.into_iter().map(Into::into).collect();
+                list.set_values(protobuf::RepeatedField::from_vec(values));
+                tv.set_list(list);
+            }
+            common::value::Value::Map(map) => {
+                let mut value_map = super::ipc::ValueMap::new();
+                let entries = map
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let mut entry = super::ipc::ValueMap_Entry::new();
+                        entry.set_key(key.into());
+                        entry.set_value(value.into());
+                        entry
+                    })
+                    .collect();
+                value_map.set_entries(protobuf::RepeatedField::from_vec(entries));
+                tv.set_map(value_map);
+            }
         };
         tv
     }
@@ -229,6 +378,9 @@ impl From<transform::Transform> for super::ipc::Transform {
                 add.set_value(i);
                 t.set_add_i32(add);
             }
+            transform::Transform::AddUInt64(u) => {
+                add_big_int_transform(&mut t, u);
+            }
             transform::Transform::AddUInt128(u) => {
                 add_big_int_transform(&mut t, u);
             }
@@ -244,6 +396,15 @@ impl From<transform::Transform> for super::ipc::Transform {
                 add.set_value(protobuf::RepeatedField::from_vec(keys));
                 t.set_add_keys(add);
             }
+            transform::Transform::RemoveKeys { removed, added } => {
+                let mut remove = super::ipc::TransformRemoveKeys::new();
+                let keys = URefMap(added).into();
+                remove.set_removed(protobuf::RepeatedField::from_vec(
+                    removed.into_iter().collect(),
+                ));
+                remove.set_added(protobuf::RepeatedField::from_vec(keys));
+                t.set_remove_keys(remove);
+            }
             transform::Transform::Failure(transform::Error::TypeMismatch(
                 transform::TypeMismatch { expected, found },
             )) => {
@@ -254,6 +415,11 @@ impl From<transform::Transform> for super::ipc::Transform {
                 fail.set_type_mismatch(typemismatch_err);
                 t.set_failure(fail);
             }
+            transform::Transform::Failure(transform::Error::Overflow) => {
+                let mut fail = super::ipc::TransformFailure::new();
+                fail.set_overflow(super::ipc::OverflowError::new());
+                t.set_failure(fail);
+            }
         };
         t
     }
@@ -309,6 +475,57 @@ impl TryFrom<&ipc::Account_AssociatedKey> for (PublicKey, Weight) {
     }
 }
 
+impl TryFrom<&ipc::BlockContext> for BlockContext {
+    type Error = ParsingError;
+
+    fn try_from(value: &ipc::BlockContext) -> Result<Self, Self::Error> {
+        let mut proposer = [0u8; 32];
+        match value.get_proposer().len() {
+            // The block context isn't set by clients which don't know about it.
+            0 => (),
+            32 => proposer.copy_from_slice(value.get_proposer()),
+            other => {
+                return parse_error(format!(
+                    "Proposer has to be 32 bytes long, got {} bytes.",
+                    other
+                ))
+            }
+        }
+        Ok(BlockContext::new(
+            value.get_time(),
+            value.get_height(),
+            proposer,
+        ))
+    }
+}
+
+impl TryFrom<&ipc::Account_ActionThresholds> for ActionThresholds {
+    type Error = ParsingError;
+
+    fn try_from(value: &ipc::Account_ActionThresholds) -> Result<Self, Self::Error> {
+        let deployment = value.get_deployment_threshold();
+        let key_management = value.get_key_management_threshold();
+        // Weight is a newtype wrapper around u8 type.
+        if deployment > u8::max_value().into() || key_management > u8::max_value().into() {
+            parse_error("Action threshold cannot be bigger 256.".to_string())
+        } else {
+            Ok(ActionThresholds::new(
+                Weight::new(deployment as u8),
+                Weight::new(key_management as u8),
+            ))
+        }
+    }
+}
+
+impl From<&ActionThresholds> for ipc::Account_ActionThresholds {
+    fn from(action_thresholds: &ActionThresholds) -> Self {
+        let mut result = ipc::Account_ActionThresholds::new();
+        result.set_deployment_threshold(action_thresholds.deployment().value().into());
+        result.set_key_management_threshold(action_thresholds.key_management().value().into());
+        result
+    }
+}
+
 impl From<&common::key::Key> for super::ipc::Key {
     fn from(key: &common::key::Key) -> super::ipc::Key {
         let mut k = super::ipc::Key::new();
@@ -384,6 +601,24 @@ impl From<Op> for super::ipc::Op {
     }
 }
 
+impl TryFrom<&super::ipc::Op> for Op {
+    type Error = ParsingError;
+
+    fn try_from(ipc_op: &super::ipc::Op) -> Result<Self, ParsingError> {
+        if ipc_op.has_read() {
+            Ok(Op::Read)
+        } else if ipc_op.has_write() {
+            Ok(Op::Write)
+        } else if ipc_op.has_add() {
+            Ok(Op::Add)
+        } else if ipc_op.has_noop() {
+            Ok(Op::NoOp)
+        } else {
+            parse_error(format!("ipc Op couldn't be parsed to any Op: {:?}", ipc_op))
+        }
+    }
+}
+
 /// Transforms gRPC TransformEntry into domain tuple of (Key, Transform).
 impl TryFrom<&super::ipc::TransformEntry> for (common::key::Key, transform::Transform) {
     type Error = ParsingError;
@@ -433,6 +668,72 @@ impl From<ExecutionEffect> for super::ipc::ExecutionEffect {
     }
 }
 
+impl TryFrom<&super::ipc::ExecutionEffect> for ExecutionEffect {
+    type Error = ParsingError;
+
+    fn try_from(ipc_effect: &super::ipc::ExecutionEffect) -> Result<Self, ParsingError> {
+        let ops: HashMap<common::key::Key, Op> = ipc_effect
+            .get_op_map()
+            .iter()
+            .map(|op_entry| {
+                if !op_entry.has_key() {
+                    return parse_error("No key field in OpEntry".to_owned());
+                }
+                if !op_entry.has_operation() {
+                    return parse_error("No operation field in OpEntry".to_owned());
+                }
+                let key: common::key::Key = op_entry.get_key().try_into()?;
+                let op: Op = op_entry.get_operation().try_into()?;
+                Ok((key, op))
+            })
+            .collect::<Result<_, ParsingError>>()?;
+        let transforms: HashMap<common::key::Key, transform::Transform> = ipc_effect
+            .get_transform_map()
+            .iter()
+            .map(TryInto::try_into)
+            .collect::<Result<_, ParsingError>>()?;
+        Ok(ExecutionEffect(ops, transforms))
+    }
+}
+
+impl From<MergeResult> for ipc::MergeResult {
+    fn from(merge_result: MergeResult) -> ipc::MergeResult {
+        let conflicts: Vec<ipc::EffectConflict> = merge_result
+            .conflicts
+            .into_iter()
+            .map(|conflict| {
+                let keys: Vec<ipc::KeyConflict> = conflict
+                    .keys
+                    .into_iter()
+                    .map(|key_conflict| {
+                        let mut ipc_key_conflict = ipc::KeyConflict::new();
+                        ipc_key_conflict.set_key((&key_conflict.key).into());
+                        ipc_key_conflict.set_first_op(key_conflict.op.into());
+                        ipc_key_conflict.set_second_op(key_conflict.other_op.into());
+                        ipc_key_conflict
+                    })
+                    .collect();
+                let mut ipc_conflict = ipc::EffectConflict::new();
+                ipc_conflict.set_first(conflict.first as u32);
+                ipc_conflict.set_second(conflict.second as u32);
+                ipc_conflict.set_keys(protobuf::RepeatedField::from_vec(keys));
+                ipc_conflict
+            })
+            .collect();
+        let mut ipc_merge_result = ipc::MergeResult::new();
+        ipc_merge_result.set_conflicts(protobuf::RepeatedField::from_vec(conflicts));
+        ipc_merge_result.set_merged(
+            merge_result
+                .merged
+                .into_iter()
+                .map(|index| index as u32)
+                .collect(),
+        );
+        ipc_merge_result.set_effect(merge_result.effect.into());
+        ipc_merge_result
+    }
+}
+
 impl From<RootNotFound> for ipc::RootNotFound {
     fn from(err: RootNotFound) -> ipc::RootNotFound {
         let RootNotFound(missing_root_hash) = err;
@@ -452,24 +753,53 @@ impl From<TypeMismatch> for ipc::TypeMismatch {
     }
 }
 
+impl From<Event> for ipc::Event {
+    fn from(event: Event) -> ipc::Event {
+        let mut ipc_event = ipc::Event::new();
+        ipc_event.set_topic(event.topic);
+        ipc_event.set_payload(event.payload);
+        ipc_event
+    }
+}
+
+impl From<HostCall> for ipc::HostCall {
+    fn from(host_call: HostCall) -> ipc::HostCall {
+        let mut ipc_host_call = ipc::HostCall::new();
+        ipc_host_call.set_function(host_call.function.to_string());
+        ipc_host_call.set_args(protobuf::RepeatedField::from_vec(host_call.args));
+        ipc_host_call.set_result(host_call.result);
+        ipc_host_call.set_gas_before(host_call.gas_before);
+        ipc_host_call.set_gas_after(host_call.gas_after);
+        ipc_host_call.set_depth(host_call.depth as u32);
+        ipc_host_call
+    }
+}
+
 impl From<ExecutionResult> for ipc::DeployResult {
     fn from(er: ExecutionResult) -> ipc::DeployResult {
         match er {
-            ExecutionResult {
-                result: Ok(effects),
+            ExecutionResult::Success {
+                effect,
+                events,
                 cost,
+                storage_cost,
             } => {
-                let mut ipc_ee = effects.into();
+                let mut ipc_ee = effect.into();
+                let ipc_events = events.into_iter().map(Into::into).collect();
                 let mut deploy_result = ipc::DeployResult::new();
                 deploy_result.set_effects(ipc_ee);
+                deploy_result.set_events(protobuf::RepeatedField::from_vec(ipc_events));
                 deploy_result.set_cost(cost);
+                deploy_result.set_computation_cost(cost - storage_cost);
+                deploy_result.set_storage_cost(storage_cost);
                 deploy_result
             }
-            ExecutionResult {
-                result: Err(err),
+            ExecutionResult::Failure {
+                error: err,
+                effect,
                 cost,
             } => {
-                match err {
+                let mut deploy_result = match err {
                     // TODO(mateusz.gorski): Fix error model for the storage errors.
                     // We don't have separate IPC messages for storage errors
                     // so for the time being they are all reported as "wasm errors".
@@ -492,6 +822,24 @@ impl From<ExecutionResult> for ipc::DeployResult {
                             deploy_result.set_cost(cost);
                             deploy_result
                         }
+                        ExecutionError::Revert(status) => {
+                            let mut deploy_result = ipc::DeployResult::new();
+                            let mut deploy_error = ipc::DeployError::new();
+                            let mut revert_error = ipc::RevertError::new();
+                            revert_error.set_status(status);
+                            deploy_error.set_revertErr(revert_error);
+                            deploy_result.set_error(deploy_error);
+                            deploy_result.set_cost(cost);
+                            deploy_result
+                        }
+                        ExecutionError::Overflow => {
+                            let mut deploy_result = ipc::DeployResult::new();
+                            let mut deploy_error = ipc::DeployError::new();
+                            deploy_error.set_overflowErr(ipc::OverflowError::new());
+                            deploy_result.set_error(deploy_error);
+                            deploy_result.set_cost(cost);
+                            deploy_result
+                        }
                         ExecutionError::KeyNotFound(key) => {
                             let msg = format!("Key {:?} not found.", key);
                             wasm_error(msg)
@@ -504,12 +852,56 @@ impl From<ExecutionResult> for ipc::DeployResult {
                             err
                         }
                     },
-                }
+                };
+                // Effects of failed deploys aren't stored, so only the execution is charged.
+                let computation_cost = deploy_result.get_cost();
+                deploy_result.set_computation_cost(computation_cost);
+                deploy_result.set_error_effects(effect.into());
+                deploy_result
             }
         }
     }
 }
 
+impl From<ExecutionResult> for ipc::EstimateResult {
+    fn from(er: ExecutionResult) -> ipc::EstimateResult {
+        let mut deploy_result: ipc::DeployResult = er.into();
+        let mut estimate_result = ipc::EstimateResult::new();
+        estimate_result.set_cost(deploy_result.get_cost());
+        estimate_result.set_computation_cost(deploy_result.get_computation_cost());
+        estimate_result.set_storage_cost(deploy_result.get_storage_cost());
+        let mut effect = if deploy_result.has_error() {
+            estimate_result.set_error(deploy_result.take_error());
+            deploy_result.take_error_effects()
+        } else {
+            estimate_result.set_success(true);
+            deploy_result.take_effects()
+        };
+        let mut estimated_effect = ipc::EstimatedEffect::new();
+        estimated_effect.set_op_map(effect.take_op_map());
+        estimated_effect.set_transform_map(effect.take_transform_map());
+        estimate_result.set_effects(estimated_effect);
+        estimate_result
+    }
+}
+
+impl From<ViewResult> for ipc::CallViewResult {
+    fn from(view_result: ViewResult) -> ipc::CallViewResult {
+        let mut ipc_view_result = ipc::CallViewResult::new();
+        match view_result.value {
+            Ok(value) => ipc_view_result.set_value(value),
+            Err(error) => {
+                // Errors of views are reported the same way as errors of deploys.
+                let mut deploy_result: ipc::DeployResult =
+                    ExecutionResult::precondition_failure(error).into();
+                ipc_view_result.set_error(deploy_result.take_error());
+            }
+        }
+        ipc_view_result.set_cost(view_result.cost);
+        ipc_view_result
+    }
+}
+
 pub fn grpc_response_from_commit_result<H>(
     prestate_hash: Blake2bHash,
     input: Result<CommitResult, H::Error>,
@@ -561,6 +953,12 @@ where
             commit_response.set_type_mismatch(type_mismatch.into());
             commit_response
         }
+        Ok(CommitResult::Overflow) => {
+            logging::log_warning("Overflow");
+            let mut commit_response = ipc::CommitResponse::new();
+            commit_response.set_overflow(ipc::OverflowError::new());
+            commit_response
+        }
         // TODO(mateusz.gorski): We should be more specific about errors here.
         Err(storage_error) => {
             let log_message = format!("storage error {:?} when applying effects", storage_error);
@@ -589,9 +987,13 @@ mod tests {
     use super::wasm_error;
     use common::key::AccessRights;
     use common::key::Key;
+    use common::value::Value;
+    use execution_engine::engine_state::block_context::BlockContext;
     use execution_engine::engine_state::error::{Error as EngineError, RootNotFound};
+    use execution_engine::engine_state::event::Event;
     use execution_engine::engine_state::execution_effect::ExecutionEffect;
     use execution_engine::engine_state::execution_result::ExecutionResult;
+    use execution_engine::engine_state::op::Op;
     use shared::newtypes::Blake2bHash;
     use shared::transform::Transform;
     use std::collections::HashMap;
@@ -630,10 +1032,17 @@ mod tests {
         };
         let execution_effect: ExecutionEffect =
             ExecutionEffect(HashMap::new(), input_transforms.clone());
-        let cost: u64 = 123;
-        let execution_result: ExecutionResult = ExecutionResult::success(execution_effect, cost);
+        let computation_cost: u64 = 123;
+        let storage_cost: u64 = 40;
+        let execution_result: ExecutionResult =
+            ExecutionResult::success(execution_effect, Vec::new(), computation_cost, storage_cost);
         let mut ipc_deploy_result: super::ipc::DeployResult = execution_result.into();
-        assert_eq!(ipc_deploy_result.get_cost(), cost);
+        assert_eq!(
+            ipc_deploy_result.get_cost(),
+            computation_cost + storage_cost
+        );
+        assert_eq!(ipc_deploy_result.get_computation_cost(), computation_cost);
+        assert_eq!(ipc_deploy_result.get_storage_cost(), storage_cost);
 
         // Extract transform map from the IPC message and parse it back to the domain
         let ipc_transforms: HashMap<Key, Transform> = {
@@ -649,7 +1058,7 @@ mod tests {
     }
 
     fn into_execution_failure<E: Into<EngineError>>(error: E, cost: u64) -> ExecutionResult {
-        ExecutionResult::failure(error.into(), cost)
+        ExecutionResult::failure(error.into(), ExecutionEffect::default(), cost)
     }
 
     fn test_cost<E: Into<EngineError>>(expected_cost: u64, err: E) -> u64 {
@@ -689,4 +1098,177 @@ mod tests {
             execution_engine::execution::Error::ForgedReference(Key::Account([1u8; 32]));
         assert_eq!(test_cost(cost, forged_ref_error), cost);
     }
+
+    #[test]
+    fn deploy_result_to_ipc_failure_has_effects() {
+        let input_transforms: HashMap<Key, Transform> = {
+            let mut tmp_map = HashMap::new();
+            tmp_map.insert(
+                Key::Account([1u8; 32]),
+                Transform::Write(common::value::Value::Int32(1)),
+            );
+            tmp_map
+        };
+        let execution_effect: ExecutionEffect =
+            ExecutionEffect(HashMap::new(), input_transforms.clone());
+        let error = execution_engine::execution::Error::GasLimit;
+        let execution_result: ExecutionResult =
+            ExecutionResult::failure(error.into(), execution_effect, 100);
+        let mut ipc_deploy_result: super::ipc::DeployResult = execution_result.into();
+        assert!(ipc_deploy_result.has_error());
+
+        let ipc_transforms: HashMap<Key, Transform> = {
+            let mut ipc_effects = ipc_deploy_result.take_error_effects();
+            let ipc_effects_tnfs = ipc_effects.take_transform_map().into_vec();
+            ipc_effects_tnfs
+                .iter()
+                .map(|e| e.try_into())
+                .collect::<Result<HashMap<Key, Transform>, _>>()
+                .unwrap()
+        };
+        assert_eq!(&input_transforms, &ipc_transforms);
+    }
+
+    #[test]
+    fn revert_error_has_status_and_cost() {
+        let error = execution_engine::execution::Error::Revert(7);
+        let execution_result = into_execution_failure(error, 100);
+        let mut ipc_deploy_result: super::ipc::DeployResult = execution_result.into();
+        assert_eq!(ipc_deploy_result.get_cost(), 100);
+        let mut ipc_error = ipc_deploy_result.take_error();
+        assert!(ipc_error.has_revertErr());
+        assert_eq!(ipc_error.take_revertErr().get_status(), 7);
+    }
+
+    #[test]
+    fn overflow_error_has_cost() {
+        let error = execution_engine::execution::Error::Overflow;
+        let execution_result = into_execution_failure(error, 100);
+        let ipc_deploy_result: super::ipc::DeployResult = execution_result.into();
+        assert_eq!(ipc_deploy_result.get_cost(), 100);
+        assert!(ipc_deploy_result.get_error().has_overflowErr());
+    }
+
+    #[test]
+    fn overflow_transform_failure() {
+        let transform = Transform::Failure(shared::transform::Error::Overflow);
+        let ipc_transform: super::ipc::Transform = transform.into();
+        assert!(ipc_transform.get_failure().has_overflow());
+    }
+
+    #[test]
+    fn deploy_result_to_ipc_success_has_events() {
+        let events = vec![
+            Event::new("first".to_string(), vec![1u8, 2]),
+            Event::new("second".to_string(), vec![]),
+        ];
+        let execution_result: ExecutionResult =
+            ExecutionResult::success(ExecutionEffect::default(), events, 10, 0);
+        let ipc_deploy_result: super::ipc::DeployResult = execution_result.into();
+        let ipc_events = ipc_deploy_result.get_events();
+        assert_eq!(ipc_events.len(), 2);
+        assert_eq!(ipc_events[0].get_topic(), "first");
+        assert_eq!(ipc_events[0].get_payload(), &[1u8, 2]);
+        assert_eq!(ipc_events[1].get_topic(), "second");
+        assert!(ipc_events[1].get_payload().is_empty());
+    }
+
+    #[test]
+    fn estimate_result_reports_failure_and_its_effects() {
+        let mut transforms = HashMap::new();
+        transforms.insert(Key::Account([1u8; 32]), Transform::Write(Value::Int32(1)));
+        let effect = ExecutionEffect(HashMap::new(), transforms);
+        let error = execution_engine::execution::Error::Revert(3);
+        let execution_result = ExecutionResult::failure(error.into(), effect, 40);
+        let ipc_estimate_result: super::ipc::EstimateResult = execution_result.into();
+        assert!(!ipc_estimate_result.get_success());
+        assert_eq!(ipc_estimate_result.get_cost(), 40);
+        assert!(ipc_estimate_result.get_error().has_revertErr());
+        assert_eq!(
+            ipc_estimate_result.get_effects().get_transform_map().len(),
+            1
+        );
+
+        let execution_result = ExecutionResult::success(ExecutionEffect::default(), vec![], 10, 5);
+        let ipc_estimate_result: super::ipc::EstimateResult = execution_result.into();
+        assert!(ipc_estimate_result.get_success());
+        assert!(!ipc_estimate_result.has_error());
+        assert_eq!(ipc_estimate_result.get_cost(), 15);
+        assert_eq!(ipc_estimate_result.get_storage_cost(), 5);
+    }
+
+    #[test]
+    fn remove_keys_transform_roundtrip() {
+        let transform = Transform::RemoveKeys {
+            removed: vec!["a".to_string(), "b".to_string()].into_iter().collect(),
+            added: std::iter::once(("c".to_string(), Key::URef([1u8; 32], AccessRights::READ)))
+                .collect(),
+        };
+        let ipc_transform: super::ipc::Transform = transform.clone().into();
+        assert!(ipc_transform.has_remove_keys());
+        let parsed: Transform = (&ipc_transform).try_into().unwrap();
+        assert_eq!(parsed, transform);
+    }
+
+    #[test]
+    fn map_value_roundtrip() {
+        let mut map = std::collections::BTreeMap::new();
+        map.insert(
+            Value::String("keys".to_string()),
+            Value::List(vec![Value::Key(Key::Hash([2u8; 32])), Value::Unit]),
+        );
+        map.insert(Value::UInt64(7), Value::Bool(true));
+        let value = Value::Map(map);
+        let ipc_value: super::ipc::Value = value.clone().into();
+        assert!(ipc_value.has_map());
+        let parsed: Value = (&ipc_value).try_into().unwrap();
+        assert_eq!(parsed, value);
+    }
+
+    #[test]
+    fn map_value_with_duplicate_keys_is_rejected() {
+        let mut entry = super::ipc::ValueMap_Entry::new();
+        entry.set_key(Value::Int32(1).into());
+        entry.set_value(Value::Unit.into());
+        let mut value_map = super::ipc::ValueMap::new();
+        value_map.set_entries(protobuf::RepeatedField::from_vec(vec![
+            entry.clone(),
+            entry,
+        ]));
+        let mut ipc_value = super::ipc::Value::new();
+        ipc_value.set_map(value_map);
+        let parsed: Result<Value, _> = (&ipc_value).try_into();
+        assert!(parsed.is_err());
+    }
+
+    #[test]
+    fn block_context_proposer_length() {
+        let mut ipc_block_context = super::ipc::BlockContext::new();
+        ipc_block_context.set_time(1000);
+        ipc_block_context.set_height(10);
+        let block_context: BlockContext = (&ipc_block_context).try_into().unwrap();
+        assert_eq!(block_context, BlockContext::new(1000, 10, [0u8; 32]));
+
+        ipc_block_context.set_proposer(vec![1u8; 32]);
+        let block_context: BlockContext = (&ipc_block_context).try_into().unwrap();
+        assert_eq!(block_context.proposer, [1u8; 32]);
+
+        ipc_block_context.set_proposer(vec![1u8; 20]);
+        let result: Result<BlockContext, _> = (&ipc_block_context).try_into();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn execution_effect_round_trip() {
+        let key = Key::Hash([1u8; 32]);
+        let mut ops = HashMap::new();
+        ops.insert(key, Op::Add);
+        let mut transforms = HashMap::new();
+        transforms.insert(key, Transform::AddInt32(1));
+        let ipc_effect: super::ipc::ExecutionEffect =
+            ExecutionEffect(ops.clone(), transforms.clone()).into();
+        let effect: ExecutionEffect = (&ipc_effect).try_into().unwrap();
+        assert_eq!(effect.0, ops);
+        assert_eq!(effect.1, transforms);
+    }
 }
@@ -4,7 +4,7 @@ use super::{ipc, parse_error, ParsingError};
 use std::convert::TryFrom;
 
 /// Map a result into the expected error for this module, while also
-/// converting the type into a Value. Use case: parsing U128, U256,
+/// converting the type into a Value. Use case: parsing u64, U128, U256,
 /// U512 from a string.
 fn result_to_value<T, E>(r: Result<T, E>) -> Result<common::value::Value, ParsingError>
 where
@@ -21,6 +21,7 @@ impl TryFrom<&ipc::RustBigInt> for common::value::Value {
     fn try_from(b: &ipc::RustBigInt) -> Result<common::value::Value, ParsingError> {
         let n = b.get_value();
         match b.get_bit_width() {
+            64 => result_to_value(n.parse::<u64>()),
             128 => result_to_value(common::value::U128::from_dec_str(n)),
             256 => result_to_value(common::value::U256::from_dec_str(n)),
             512 => result_to_value(common::value::U512::from_dec_str(n)),
@@ -42,6 +43,7 @@ macro_rules! from_uint_for_rust_big_int {
     };
 }
 
+from_uint_for_rust_big_int!(u64, 64);
 from_uint_for_rust_big_int!(common::value::U128, 128);
 from_uint_for_rust_big_int!(common::value::U256, 256);
 from_uint_for_rust_big_int!(common::value::U512, 512);
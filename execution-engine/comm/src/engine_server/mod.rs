@@ -5,18 +5,22 @@ use std::io::ErrorKind;
 use std::marker::{Send, Sync};
 
 use common::key::Key;
+use common::value::Value;
+use execution_engine::engine_state::deploy::{DeployItem, DeployOutcome};
 use execution_engine::engine_state::error::Error as EngineError;
+use execution_engine::engine_state::execution_effect::ExecutionEffect;
+use execution_engine::engine_state::merge::merge_effects;
 use execution_engine::engine_state::EngineState;
-use execution_engine::execution::{Executor, WasmiExecutor};
+use execution_engine::execution::WasmiExecutor;
 use execution_engine::tracking_copy::QueryResult;
 use ipc::*;
 use ipc_grpc::ExecutionEngineService;
 use mappings::*;
 use shared::newtypes::Blake2bHash;
-use shared::transform::Transform;
-use storage::global_state::History;
+use shared::transform::{Arithmetic, Transform};
+use storage::global_state::{CommitResult, History};
 use wasm_prep::wasm_costs::WasmCosts;
-use wasm_prep::{Preprocessor, WasmiPreprocessor};
+use wasm_prep::WasmiPreprocessor;
 
 use shared::logging;
 
@@ -30,7 +34,7 @@ pub mod mappings;
 // This way core won't depend on comm (outer layer) leading to cleaner design.
 impl<H> ipc_grpc::ExecutionEngineService for EngineState<H>
 where
-    H: History,
+    H: History + Send,
     EngineError: From<H::Error>,
     H::Error: Into<execution_engine::execution::Error> + Debug,
 {
@@ -83,9 +87,11 @@ where
                 result.set_failure(error);
                 result
             }
-            Ok(QueryResult::Success(value)) => {
+            Ok(QueryResult::Success(value, keys)) => {
                 let mut result = ipc::QueryResponse::new();
                 result.set_success(value.into());
+                let keys: Vec<ipc::Key> = keys.iter().map(Into::into).collect();
+                result.set_traversed_keys(protobuf::RepeatedField::from_vec(keys));
                 result
             }
         };
@@ -100,24 +106,43 @@ where
         let executor = WasmiExecutor;
         // TODO: don't unwrap
         let prestate_hash: Blake2bHash = p.get_parent_state_hash().try_into().unwrap();
-        let deploys = p.get_deploys();
+        let deploys: Result<Vec<DeployItem>, String> =
+            p.get_deploys().iter().map(deploy_item).collect();
+        let deploys = match deploys {
+            Err(error) => {
+                logging::log_error(&error);
+                let mut exec_response = ipc::ExecResponse::new();
+                exec_response.set_failure(error);
+                return grpc::SingleResponse::completed(exec_response);
+            }
+            Ok(deploys) => deploys,
+        };
         let protocol_version = p.get_protocol_version();
         // TODO: don't unwrap
         let wasm_costs = WasmCosts::from_version(protocol_version.version).unwrap();
+        let block_context = match p.get_block_context().try_into() {
+            Err(ParsingError(error)) => {
+                logging::log_error(&error);
+                let mut exec_response = ipc::ExecResponse::new();
+                exec_response.set_failure(error);
+                return grpc::SingleResponse::completed(exec_response);
+            }
+            Ok(block_context) => block_context,
+        };
         let preprocessor: WasmiPreprocessor = WasmiPreprocessor::new(wasm_costs);
-        let deploys_result: Result<Vec<DeployResult>, RootNotFound> = run_deploys(
+        let exec_result: Result<ipc::ExecResult, RootNotFound> = run_deploys(
             &self,
             &executor,
             &preprocessor,
             prestate_hash,
-            deploys,
+            &deploys,
             protocol_version,
+            block_context,
+            p.get_chained(),
         );
-        match deploys_result {
-            Ok(deploy_results) => {
+        match exec_result {
+            Ok(exec_result) => {
                 let mut exec_response = ipc::ExecResponse::new();
-                let mut exec_result = ipc::ExecResult::new();
-                exec_result.set_deploy_results(protobuf::RepeatedField::from_vec(deploy_results));
                 exec_response.set_success(exec_result);
                 grpc::SingleResponse::completed(exec_response)
             }
@@ -130,6 +155,248 @@ where
         }
     }
 
+    fn exec_and_commit(
+        &self,
+        _o: ::grpc::RequestOptions,
+        p: ipc::ExecAndCommitRequest,
+    ) -> grpc::SingleResponse<ipc::ExecAndCommitResponse> {
+        let executor = WasmiExecutor;
+        let mut response = ipc::ExecAndCommitResponse::new();
+        let prestate_hash: Blake2bHash = match p.get_parent_state_hash().try_into() {
+            Err(_) => {
+                let error = format!(
+                    "Invalid parent state hash length: {}",
+                    p.get_parent_state_hash().len()
+                );
+                logging::log_error(&error);
+                response.set_failure(error);
+                return grpc::SingleResponse::completed(response);
+            }
+            Ok(prestate_hash) => prestate_hash,
+        };
+        let deploys: Result<Vec<DeployItem>, String> =
+            p.get_deploys().iter().map(deploy_item).collect();
+        let deploys = match deploys {
+            Err(error) => {
+                logging::log_error(&error);
+                response.set_failure(error);
+                return grpc::SingleResponse::completed(response);
+            }
+            Ok(deploys) => deploys,
+        };
+        let protocol_version = p.get_protocol_version();
+        let wasm_costs = match WasmCosts::from_version(protocol_version.version) {
+            None => {
+                let error = format!("Unsupported protocol version: {}", protocol_version.version);
+                logging::log_error(&error);
+                response.set_failure(error);
+                return grpc::SingleResponse::completed(response);
+            }
+            Some(wasm_costs) => wasm_costs,
+        };
+        let block_context = match p.get_block_context().try_into() {
+            Err(ParsingError(error)) => {
+                logging::log_error(&error);
+                response.set_failure(error);
+                return grpc::SingleResponse::completed(response);
+            }
+            Ok(block_context) => block_context,
+        };
+        let preprocessor: WasmiPreprocessor = WasmiPreprocessor::new(wasm_costs);
+        let response = run_and_commit_deploys(
+            &self,
+            &executor,
+            &preprocessor,
+            prestate_hash,
+            &deploys,
+            protocol_version,
+            block_context,
+        );
+        grpc::SingleResponse::completed(response)
+    }
+
+    fn estimate(
+        &self,
+        _o: ::grpc::RequestOptions,
+        p: ipc::EstimateRequest,
+    ) -> grpc::SingleResponse<ipc::EstimateResponse> {
+        let executor = WasmiExecutor;
+        let mut response = ipc::EstimateResponse::new();
+        let state_hash: Blake2bHash = match p.get_state_hash().try_into() {
+            Err(_) => {
+                let error = format!("Invalid state hash length: {}", p.get_state_hash().len());
+                logging::log_error(&error);
+                response.set_failure(error);
+                return grpc::SingleResponse::completed(response);
+            }
+            Ok(state_hash) => state_hash,
+        };
+        let protocol_version = p.get_protocol_version();
+        let wasm_costs = match WasmCosts::from_version(protocol_version.version) {
+            None => {
+                let error = format!("Unsupported protocol version: {}", protocol_version.version);
+                logging::log_error(&error);
+                response.set_failure(error);
+                return grpc::SingleResponse::completed(response);
+            }
+            Some(wasm_costs) => wasm_costs,
+        };
+        let block_context = match p.get_block_context().try_into() {
+            Err(ParsingError(error)) => {
+                logging::log_error(&error);
+                response.set_failure(error);
+                return grpc::SingleResponse::completed(response);
+            }
+            Ok(block_context) => block_context,
+        };
+        let preprocessor: WasmiPreprocessor = WasmiPreprocessor::new(wasm_costs);
+        let deploy = p.get_deploy();
+        let item = match deploy_item(deploy) {
+            Err(error) => {
+                logging::log_error(&error);
+                response.set_failure(error);
+                return grpc::SingleResponse::completed(response);
+            }
+            Ok(item) => item,
+        };
+        let payment = deploy.get_payment();
+        match self.estimate_deploy(
+            (payment.get_code(), payment.get_args()),
+            (item.module_bytes, item.args),
+            item.address,
+            item.timestamp,
+            item.nonce,
+            state_hash,
+            protocol_version.get_version(),
+            block_context,
+            &executor,
+            &preprocessor,
+        ) {
+            Ok(execution_result) => response.set_success(execution_result.into()),
+            Err(error) => {
+                logging::log_error("estimate error: RootNotFound");
+                response.set_missing_parent(error.into());
+            }
+        }
+        grpc::SingleResponse::completed(response)
+    }
+
+    fn call_view(
+        &self,
+        _o: ::grpc::RequestOptions,
+        p: ipc::CallViewRequest,
+    ) -> grpc::SingleResponse<ipc::CallViewResponse> {
+        let executor = WasmiExecutor;
+        let mut response = ipc::CallViewResponse::new();
+        let state_hash: Blake2bHash = match p.get_state_hash().try_into() {
+            Err(_) => {
+                let error = format!("Invalid state hash length: {}", p.get_state_hash().len());
+                logging::log_error(&error);
+                response.set_failure(error);
+                return grpc::SingleResponse::completed(response);
+            }
+            Ok(state_hash) => state_hash,
+        };
+        let block_context = match p.get_block_context().try_into() {
+            Err(ParsingError(error)) => {
+                logging::log_error(&error);
+                response.set_failure(error);
+                return grpc::SingleResponse::completed(response);
+            }
+            Ok(block_context) => block_context,
+        };
+        let address = match account_address(p.get_address()) {
+            Err(error) => {
+                logging::log_error(&error);
+                response.set_failure(error);
+                return grpc::SingleResponse::completed(response);
+            }
+            Ok(address) => address,
+        };
+        let contract_key = match p.get_contract().try_into() {
+            Err(ParsingError(error)) => {
+                logging::log_error(&error);
+                response.set_failure(error);
+                return grpc::SingleResponse::completed(response);
+            }
+            Ok(key) => key,
+        };
+        match self.run_view(
+            contract_key,
+            p.get_args(),
+            address,
+            state_hash,
+            block_context,
+            &executor,
+        ) {
+            Ok(view_result) => response.set_success(view_result.into()),
+            Err(error) => {
+                logging::log_error("call view error: RootNotFound");
+                response.set_missing_parent(error.into());
+            }
+        }
+        grpc::SingleResponse::completed(response)
+    }
+
+    fn transfer(
+        &self,
+        _o: ::grpc::RequestOptions,
+        p: ipc::TransferRequest,
+    ) -> grpc::SingleResponse<ipc::TransferResponse> {
+        let mut response = ipc::TransferResponse::new();
+        let prestate_hash: Blake2bHash = match p.get_parent_state_hash().try_into() {
+            Err(_) => {
+                let error = format!(
+                    "Invalid parent state hash length: {}",
+                    p.get_parent_state_hash().len()
+                );
+                logging::log_error(&error);
+                response.set_failure(error);
+                return grpc::SingleResponse::completed(response);
+            }
+            Ok(prestate_hash) => prestate_hash,
+        };
+        let addresses = account_address(p.get_address())
+            .and_then(|address| account_address(p.get_target()).map(|target| (address, target)));
+        let (address, target) = match addresses {
+            Err(error) => {
+                logging::log_error(&error);
+                response.set_failure(error);
+                return grpc::SingleResponse::completed(response);
+            }
+            Ok(addresses) => addresses,
+        };
+        let amount = match p.get_amount().try_into() {
+            Err(ParsingError(error)) => {
+                logging::log_error(&error);
+                response.set_failure(error);
+                return grpc::SingleResponse::completed(response);
+            }
+            Ok(Value::UInt512(amount)) => amount,
+            Ok(other) => {
+                let error = format!("Transfer amount isn't a U512: {:?}", other);
+                logging::log_error(&error);
+                response.set_failure(error);
+                return grpc::SingleResponse::completed(response);
+            }
+        };
+        match self.run_transfer(
+            address,
+            target,
+            amount,
+            p.get_timestamp(),
+            p.get_nonce(),
+            prestate_hash,
+        ) {
+            Ok(execution_result) => response.set_success(execution_result.into()),
+            Err(error) => {
+                logging::log_error("transfer error: RootNotFound");
+                response.set_missing_parent(error.into());
+            }
+        }
+        grpc::SingleResponse::completed(response)
+    }
+
     fn commit(
         &self,
         _o: ::grpc::RequestOptions,
@@ -149,15 +416,37 @@ where
                 grpc::SingleResponse::completed(res)
             }
             Ok(effects) => {
+                let protocol_version = p.get_protocol_version().get_version();
                 let result = grpc_response_from_commit_result::<H>(
                     prestate_hash,
-                    self.apply_effect(prestate_hash, effects),
+                    self.apply_effect(prestate_hash, effects, protocol_version),
                 );
                 grpc::SingleResponse::completed(result)
             }
         }
     }
 
+    fn merge(
+        &self,
+        _o: ::grpc::RequestOptions,
+        p: ipc::MergeRequest,
+    ) -> grpc::SingleResponse<ipc::MergeResponse> {
+        let effects_result: Result<Vec<ExecutionEffect>, ParsingError> =
+            p.get_effects().iter().map(TryInto::try_into).collect();
+        let mut result = ipc::MergeResponse::new();
+        match effects_result {
+            Err(ParsingError(error_message)) => {
+                logging::log_error(&error_message);
+                result.set_failure(error_message);
+            }
+            Ok(effects) => {
+                let arithmetic = Arithmetic::from_version(p.get_protocol_version().get_version());
+                result.set_success(merge_effects(&effects, arithmetic).into())
+            }
+        }
+        grpc::SingleResponse::completed(result)
+    }
+
     fn validate(
         &self,
         _o: ::grpc::RequestOptions,
@@ -187,57 +476,146 @@ where
     }
 }
 
-fn run_deploys<A, H, E, P>(
+#[allow(clippy::too_many_arguments)]
+fn run_deploys<H>(
     engine_state: &EngineState<H>,
-    executor: &E,
-    preprocessor: &P,
+    executor: &WasmiExecutor,
+    preprocessor: &WasmiPreprocessor,
     prestate_hash: Blake2bHash,
-    deploys: &[ipc::Deploy],
+    deploy_items: &[DeployItem],
     protocol_version: &ProtocolVersion,
-) -> Result<Vec<DeployResult>, RootNotFound>
+    block_context: execution_engine::engine_state::block_context::BlockContext,
+    chained: bool,
+) -> Result<ipc::ExecResult, RootNotFound>
 where
-    H: History,
-    E: Executor<A>,
-    P: Preprocessor<A>,
+    H: History + Send,
     EngineError: From<H::Error>,
     H::Error: Into<execution_engine::execution::Error>,
 {
-    // We want to treat RootNotFound error differently b/c it should short-circuit
-    // the execution of ALL deploys within the block. This is because all of them share
-    // the same prestate and all of them would fail.
-    // Iterator (Result<_, _> + collect()) will short circuit the execution
-    // when run_deploy returns Err.
-    deploys
-        .iter()
-        .map(|deploy| {
-            let session_contract = deploy.get_session();
-            let module_bytes = &session_contract.code;
-            let args = &session_contract.args;
-            let address: [u8; 32] = {
-                let mut tmp = [0u8; 32];
-                tmp.copy_from_slice(&deploy.address);
-                tmp
-            };
-            let timestamp = deploy.timestamp;
-            let nonce = deploy.nonce;
-            let gas_limit = deploy.gas_limit as u64;
-            engine_state
-                .run_deploy(
-                    module_bytes,
-                    args,
-                    address,
-                    timestamp,
-                    nonce,
+    let mut exec_result = ipc::ExecResult::new();
+    // RootNotFound fails the execution of ALL deploys within the block, as all of them
+    // share the same prestate.
+    let outcomes = if chained {
+        let chained_outcomes = engine_state.run_deploys_chained(
+            deploy_items,
+            prestate_hash,
+            protocol_version.get_version(),
+            block_context,
+            executor,
+            preprocessor,
+        )?;
+        exec_result.set_combined_effect(chained_outcomes.effect.into());
+        chained_outcomes.outcomes
+    } else {
+        engine_state.run_deploys(
+            deploy_items,
+            prestate_hash,
+            protocol_version.get_version(),
+            block_context,
+            executor,
+            preprocessor,
+        )?
+    };
+    let deploy_results: Vec<DeployResult> = outcomes.into_iter().map(deploy_result).collect();
+    exec_result.set_deploy_results(protobuf::RepeatedField::from_vec(deploy_results));
+    Ok(exec_result)
+}
+
+/// Executes `deploys` one after another, each seeing the effects of the preceding ones,
+/// and commits their combined effect once. Failed deploys only contribute their nonce
+/// increment to it.
+#[allow(clippy::too_many_arguments)]
+fn run_and_commit_deploys<H>(
+    engine_state: &EngineState<H>,
+    executor: &WasmiExecutor,
+    preprocessor: &WasmiPreprocessor,
+    prestate_hash: Blake2bHash,
+    deploy_items: &[DeployItem],
+    protocol_version: &ProtocolVersion,
+    block_context: execution_engine::engine_state::block_context::BlockContext,
+) -> ipc::ExecAndCommitResponse
+where
+    H: History,
+    EngineError: From<H::Error>,
+    H::Error: Into<execution_engine::execution::Error> + Debug,
+{
+    let mut response = ipc::ExecAndCommitResponse::new();
+    let chained_outcomes = match engine_state.run_deploys_chained(
+        deploy_items,
+        prestate_hash,
+        protocol_version.get_version(),
+        block_context,
+        executor,
+        preprocessor,
+    ) {
+        Err(error) => {
+            logging::log_error("deploy results error: RootNotFound");
+            response.set_missing_parent(error.into());
+            return response;
+        }
+        Ok(chained_outcomes) => chained_outcomes,
+    };
+    let transforms = chained_outcomes.effect.1;
+    let poststate_hash = if transforms.is_empty() {
+        prestate_hash
+    } else {
+        match engine_state.apply_effect(prestate_hash, transforms, protocol_version.get_version()) {
+            Ok(CommitResult::Success(poststate_hash)) => poststate_hash,
+            commit_result => {
+                response.set_failed_commit(grpc_response_from_commit_result::<H>(
                     prestate_hash,
-                    gas_limit,
-                    protocol_version.get_version(),
-                    executor,
-                    preprocessor,
-                )
-                .map(Into::into)
-                .map_err(Into::into)
-        })
-        .collect()
+                    commit_result,
+                ));
+                return response;
+            }
+        }
+    };
+    let deploy_results: Vec<DeployResult> = chained_outcomes
+        .outcomes
+        .into_iter()
+        .map(deploy_result)
+        .collect();
+    let mut result = ipc::ExecAndCommitResult::new();
+    result.set_deploy_results(protobuf::RepeatedField::from_vec(deploy_results));
+    result.set_poststate_hash(poststate_hash.to_vec());
+    response.set_success(result);
+    response
+}
+
+/// Fails if `bytes` aren't 32 bytes long.
+fn account_address(bytes: &[u8]) -> Result<[u8; 32], String> {
+    if bytes.len() != 32 {
+        return Err(format!("Invalid account address length: {}", bytes.len()));
+    }
+    let mut address = [0u8; 32];
+    address.copy_from_slice(bytes);
+    Ok(address)
+}
+
+/// Fails if the account address of `deploy` isn't 32 bytes long.
+fn deploy_item(deploy: &ipc::Deploy) -> Result<DeployItem, String> {
+    let address = account_address(deploy.get_address())?;
+    let session_contract = deploy.get_session();
+    Ok(DeployItem {
+        module_bytes: &session_contract.code,
+        args: &session_contract.args,
+        address,
+        timestamp: deploy.timestamp,
+        nonce: deploy.nonce,
+        gas_limit: deploy.gas_limit as u64,
+        trace: deploy.trace,
+    })
+}
+
+fn deploy_result(outcome: DeployOutcome) -> DeployResult {
+    let mut deploy_result: DeployResult = outcome.result.into();
+    if let Some(trace) = outcome.trace {
+        let ipc_host_calls = trace.calls.into_iter().map(Into::into).collect();
+        deploy_result.set_trace(protobuf::RepeatedField::from_vec(ipc_host_calls));
+        deploy_result.set_trace_truncated(trace.truncated);
+    }
+    deploy_result.set_conflicts(outcome.conflicts.into_iter().map(|i| i as u32).collect());
+    deploy_result
 }
 
 // Helper method which returns single DeployResult that is set to be a WasmError.
@@ -259,3 +637,213 @@ pub fn new<E: ExecutionEngineService + Sync + Send + 'static>(
     server.add_service(ipc_grpc::ExecutionEngineServiceServer::new_service_def(e));
     server
 }
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use common::key::Key;
+    use common::value::{Value, U512};
+    use execution_engine::engine_state::block_context::BlockContext;
+    use execution_engine::engine_state::deploy::DeployItem;
+    use execution_engine::engine_state::EngineState;
+    use execution_engine::execution::WasmiExecutor;
+    use grpc;
+    use protobuf;
+    use shared::init::mocked_account;
+    use shared::newtypes::{Blake2bHash, Validated};
+    use storage::global_state::in_memory::InMemoryGlobalState;
+    use storage::global_state::CommitResult;
+    use wabt;
+    use wasm_prep::wasm_costs::WasmCosts;
+    use wasm_prep::WasmiPreprocessor;
+
+    use super::ipc_grpc::ExecutionEngineService;
+    use super::{deploy_item, ipc, run_and_commit_deploys};
+
+    const ACCOUNT: [u8; 32] = [1u8; 32];
+
+    fn engine_state() -> (EngineState<InMemoryGlobalState>, Blake2bHash) {
+        let state = InMemoryGlobalState::from_pairs(&mocked_account(ACCOUNT)).unwrap();
+        let root_hash = state.root_hash;
+        (EngineState::new(state), root_hash)
+    }
+
+    fn preprocessor() -> WasmiPreprocessor {
+        WasmiPreprocessor::new(WasmCosts::from_version(1).unwrap())
+    }
+
+    fn protocol_version() -> ipc::ProtocolVersion {
+        let mut protocol_version = ipc::ProtocolVersion::new();
+        protocol_version.set_version(1);
+        protocol_version
+    }
+
+    /// Deploy whose session code stores `Value::Int32(1)` under a new uref, and
+    /// reverts afterwards if `revert` is set.
+    fn store_deploy(nonce: u64, revert: bool) -> ipc::Deploy {
+        let revert = if revert {
+            "(call $revert (i32.const 1))"
+        } else {
+            ""
+        };
+        let wat = format!(
+            r#"(module
+                (import "env" "new_uref" (func $new_uref (param i32 i32 i32)))
+                (import "env" "revert" (func $revert (param i32)))
+                (memory 1)
+                (data (i32.const 0) "\00\01\00\00\00")
+                (func (export "call")
+                    (call $new_uref (i32.const 100) (i32.const 0) (i32.const 5))
+                    {}))"#,
+            revert
+        );
+        let mut session = ipc::DeployCode::new();
+        session.set_code(wabt::wat2wasm(wat).unwrap());
+        let mut deploy = ipc::Deploy::new();
+        deploy.set_address(ACCOUNT.to_vec());
+        deploy.set_session(session);
+        deploy.set_gas_limit(1_000_000_000);
+        deploy.set_nonce(nonce);
+        deploy
+    }
+
+    fn nonce(engine_state: &EngineState<InMemoryGlobalState>, state_hash: Blake2bHash) -> u64 {
+        let mut tracking_copy = engine_state.tracking_copy(state_hash).unwrap().unwrap();
+        let key = Validated::new(Key::Account(ACCOUNT), Validated::valid).unwrap();
+        match tracking_copy.get(&key).unwrap() {
+            Some(Value::Account(account)) => account.nonce(),
+            other => panic!("Account should exist: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn combined_effect_is_committed_once() {
+        let deploys = vec![store_deploy(1, false), store_deploy(2, true)];
+        let deploy_items: Vec<DeployItem> = deploys
+            .iter()
+            .map(|deploy| deploy_item(deploy).unwrap())
+            .collect();
+        let (expected_state, _) = engine_state();
+        let (engine_state, root_hash) = engine_state();
+        let mut response = run_and_commit_deploys(
+            &engine_state,
+            &WasmiExecutor,
+            &preprocessor(),
+            root_hash,
+            &deploy_items,
+            &protocol_version(),
+            BlockContext::default(),
+        );
+        assert!(response.has_success());
+        let result = response.take_success();
+        let deploy_results = result.get_deploy_results();
+        assert_eq!(deploy_results.len(), 2);
+        assert!(deploy_results[0].has_effects());
+        assert!(deploy_results[1].has_error());
+        let poststate_hash: Blake2bHash = result.get_poststate_hash().try_into().unwrap();
+        // The nonce increment of the failed deploy is committed.
+        assert_eq!(nonce(&engine_state, poststate_hash), 2);
+
+        // Committing the combined effect of the chained deploys leads to the same state.
+        let chained = expected_state
+            .run_deploys_chained(
+                &deploy_items,
+                root_hash,
+                1,
+                BlockContext::default(),
+                &WasmiExecutor,
+                &preprocessor(),
+            )
+            .unwrap();
+        match expected_state
+            .apply_effect(root_hash, chained.effect.1, 1)
+            .unwrap()
+        {
+            CommitResult::Success(expected_hash) => assert_eq!(poststate_hash, expected_hash),
+            other => panic!("Commit should succeed: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_parent_is_reported() {
+        let (engine_state, _) = engine_state();
+        let missing_hash = Blake2bHash::new(&[0u8; 32]);
+        let deploy = store_deploy(1, false);
+        let response = run_and_commit_deploys(
+            &engine_state,
+            &WasmiExecutor,
+            &preprocessor(),
+            missing_hash,
+            &[deploy_item(&deploy).unwrap()],
+            &protocol_version(),
+            BlockContext::default(),
+        );
+        assert!(response.has_missing_parent());
+    }
+
+    #[test]
+    fn short_address_is_reported() {
+        let (engine_state, root_hash) = engine_state();
+        let mut deploy = store_deploy(1, false);
+        deploy.set_address(vec![1u8; 20]);
+        let expected_error = "Invalid account address length: 20";
+
+        let mut request = ipc::ExecAndCommitRequest::new();
+        request.set_parent_state_hash(root_hash.to_vec());
+        request.set_deploys(protobuf::RepeatedField::from_vec(vec![deploy.clone()]));
+        request.set_protocol_version(protocol_version());
+        let response = engine_state
+            .exec_and_commit(grpc::RequestOptions::new(), request)
+            .wait_drop_metadata()
+            .unwrap();
+        assert_eq!(response.get_failure(), expected_error);
+
+        let mut request = ipc::ExecRequest::new();
+        request.set_parent_state_hash(root_hash.to_vec());
+        request.set_deploys(protobuf::RepeatedField::from_vec(vec![deploy]));
+        request.set_protocol_version(protocol_version());
+        let response = engine_state
+            .exec(grpc::RequestOptions::new(), request)
+            .wait_drop_metadata()
+            .unwrap();
+        assert_eq!(response.get_failure(), expected_error);
+    }
+
+    fn transfer_request(root_hash: Blake2bHash, target: &[u8]) -> ipc::TransferRequest {
+        let mut request = ipc::TransferRequest::new();
+        request.set_parent_state_hash(root_hash.to_vec());
+        request.set_address(ACCOUNT.to_vec());
+        request.set_target(target.to_vec());
+        request.set_amount(U512::from(10).into());
+        request.set_nonce(1);
+        request
+    }
+
+    #[test]
+    fn transfer_returns_effects() {
+        let (engine_state, root_hash) = engine_state();
+        let response = engine_state
+            .transfer(
+                grpc::RequestOptions::new(),
+                transfer_request(root_hash, &[2u8; 32]),
+            )
+            .wait_drop_metadata()
+            .unwrap();
+        assert!(response.has_success());
+        assert!(response.get_success().has_effects());
+    }
+
+    #[test]
+    fn transfer_to_short_address_is_reported() {
+        let (engine_state, root_hash) = engine_state();
+        let response = engine_state
+            .transfer(
+                grpc::RequestOptions::new(),
+                transfer_request(root_hash, &[2u8; 20]),
+            )
+            .wait_drop_metadata()
+            .unwrap();
+        assert_eq!(response.get_failure(), "Invalid account address length: 20");
+    }
+}
@@ -11,7 +11,9 @@ use test::Bencher;
 
 use casperlabs_contract_ffi::bytesrepr::{FromBytes, ToBytes};
 use casperlabs_contract_ffi::key::{AccessRights, Key};
-use casperlabs_contract_ffi::value::account::{AssociatedKeys, PublicKey, Weight};
+use casperlabs_contract_ffi::value::account::{
+    ActionThresholds, AssociatedKeys, PublicKey, PurseId, Weight,
+};
 use casperlabs_contract_ffi::value::{
     account::Account,
     contract::Contract,
@@ -357,6 +359,8 @@ fn make_account() -> Account {
         2_635_333_365_164_409_670u64,
         known_urefs,
         associated_keys,
+        PurseId::new([0u8; 32]),
+        ActionThresholds::default(),
     )
 }
 
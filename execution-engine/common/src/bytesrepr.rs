@@ -38,6 +38,9 @@ pub enum Error {
 
     #[fail(display = "Serialization error: out of memory")]
     OutOfMemoryError,
+
+    #[fail(display = "Value exceeds nesting depth or collection size limits")]
+    LimitExceeded,
 }
 
 pub fn deserialize<T: FromBytes>(bytes: &[u8]) -> Result<T, Error> {
@@ -74,6 +77,22 @@ impl FromBytes for u8 {
     }
 }
 
+impl ToBytes for bool {
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        u8::from(*self).to_bytes()
+    }
+}
+
+impl FromBytes for bool {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), Error> {
+        match FromBytes::from_bytes(bytes)? {
+            (0u8, rem) => Ok((false, rem)),
+            (1u8, rem) => Ok((true, rem)),
+            _ => Err(Error::FormattingError),
+        }
+    }
+}
+
 impl ToBytes for i32 {
     fn to_bytes(&self) -> Result<Vec<u8>, Error> {
         Ok(self.to_le_bytes().to_vec())
@@ -194,6 +213,39 @@ impl<T: FromBytes> FromBytes for Option<T> {
     }
 }
 
+impl<T: ToBytes, E: ToBytes> ToBytes for Result<T, E> {
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let (tag, mut value) = match self {
+            Err(e) => (0u32, e.to_bytes()?),
+            Ok(t) => (1u32, t.to_bytes()?),
+        };
+        if value.len() >= u32::max_value() as usize - U32_SIZE {
+            return Err(Error::OutOfMemoryError);
+        }
+        let mut result: Vec<u8> = Vec::with_capacity(U32_SIZE + value.len());
+        result.append(&mut tag.to_bytes()?);
+        result.append(&mut value);
+        Ok(result)
+    }
+}
+
+impl<T: FromBytes, E: FromBytes> FromBytes for Result<T, E> {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), Error> {
+        let (tag, rem): (u32, &[u8]) = FromBytes::from_bytes(bytes)?;
+        match tag {
+            0 => {
+                let (e, rem): (E, &[u8]) = FromBytes::from_bytes(rem)?;
+                Ok((Err(e), rem))
+            }
+            1 => {
+                let (t, rem): (T, &[u8]) = FromBytes::from_bytes(rem)?;
+                Ok((Ok(t), rem))
+            }
+            _ => Err(Error::FormattingError),
+        }
+    }
+}
+
 impl ToBytes for Vec<i32> {
     fn to_bytes(&self) -> Result<Vec<u8>, Error> {
         // Return error if size of vector would exceed length of serialized data
@@ -439,6 +491,7 @@ mod proptests {
     // Bring the macros and other important things into scope.
     use crate::gens::*;
     use crate::test_utils::test_serialization_roundtrip;
+    use alloc::string::String;
     use proptest::collection::vec;
     use proptest::prelude::*;
 
@@ -449,6 +502,11 @@ mod proptests {
             assert!(test_serialization_roundtrip(&u));
         }
 
+        #[test]
+        fn test_bool(b in any::<bool>()) {
+            assert!(test_serialization_roundtrip(&b));
+        }
+
         #[test]
         fn test_u32(u in any::<u32>()) {
             assert!(test_serialization_roundtrip(&u));
@@ -504,6 +562,14 @@ mod proptests {
             assert!(test_serialization_roundtrip(&o));
         }
 
+        #[test]
+        fn test_result(r in prop_oneof![
+            any::<u32>().prop_map(Ok::<u32, String>),
+            "\\PC*".prop_map(Err::<u32, String>)
+        ]) {
+            assert!(test_serialization_roundtrip(&r));
+        }
+
         #[test]
         fn test_unit(unit in Just(())) {
             assert!(test_serialization_roundtrip(&unit));
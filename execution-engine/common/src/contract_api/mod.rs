@@ -4,10 +4,11 @@ pub mod pointers;
 
 use self::alloc_util::*;
 use self::pointers::*;
-use crate::bytesrepr::{deserialize, FromBytes, ToBytes};
+use crate::bytesrepr::{self, deserialize, FromBytes, ToBytes};
 use crate::ext_ffi;
 use crate::key::{Key, LOCAL_KEY_HASH_SIZE, LOCAL_SEED_SIZE, UREF_SIZE};
-use crate::value::{Contract, Value};
+use crate::value::account::PublicKey;
+use crate::value::{Contract, Value, U512};
 use alloc::collections::BTreeMap;
 use alloc::string::String;
 use alloc::vec::Vec;
@@ -192,6 +193,52 @@ pub fn store_function(name: &str, known_urefs: BTreeMap<String, Key>) -> Contrac
     ContractPointer::Hash(tmp)
 }
 
+/// Stores an exported function (see `fn_by_name`) as a contract which can be upgraded
+/// later on with `upgrade_contract`. Returns the key of the contract together with its
+/// admin URef, which has to be kept safe as anyone holding it with WRITE rights can
+/// replace the contract's code.
+pub fn store_upgradable_function(
+    name: &str,
+    known_urefs: BTreeMap<String, Key>,
+) -> (ContractPointer, UPointer<Key>) {
+    let (fn_ptr, fn_size, _bytes1) = str_ref_to_ptr(name);
+    let (urefs_ptr, urefs_size, _bytes2) = to_ptr(&known_urefs);
+    let mut hash_bytes = [0u8; 32];
+    let hash_ptr = hash_bytes.as_mut_ptr();
+    let admin_ptr = alloc_bytes(UREF_SIZE);
+    let admin_bytes = unsafe {
+        ext_ffi::store_upgradable_function(
+            fn_ptr, fn_size, urefs_ptr, urefs_size, hash_ptr, admin_ptr,
+        );
+        Vec::from_raw_parts(admin_ptr, UREF_SIZE, UREF_SIZE)
+    };
+    let admin: Key = deserialize(&admin_bytes).unwrap();
+    if let Key::URef(id, access_rights) = admin {
+        (
+            ContractPointer::Hash(hash_bytes),
+            UPointer::new(id, access_rights),
+        )
+    } else {
+        panic!("store_upgradable_function FFI did not return a URef!");
+    }
+}
+
+/// Replaces the code of the upgradable contract `contract` with the function exported
+/// under `name` by the current module. Known urefs of the contract are kept. The host
+/// traps unless `admin` is the admin URef of the contract with WRITE rights.
+pub fn upgrade_contract(contract: ContractPointer, admin: UPointer<Key>, name: &str) {
+    let contract_key: Key = contract.into();
+    let admin_key: Key = admin.into();
+    let (key_ptr, key_size, _bytes1) = to_ptr(&contract_key);
+    let (admin_ptr, admin_size, _bytes2) = to_ptr(&admin_key);
+    let (name_ptr, name_size, _bytes3) = str_ref_to_ptr(name);
+    unsafe {
+        ext_ffi::upgrade_contract(
+            key_ptr, key_size, admin_ptr, admin_size, name_ptr, name_size,
+        );
+    }
+}
+
 /// Finds function by the name and stores it at the unforgable name.
 pub fn store_function_at(name: &str, known_urefs: BTreeMap<String, Key>, uref: UPointer<Contract>) {
     let contract = fn_by_name(name, known_urefs);
@@ -240,6 +287,13 @@ pub fn add_uref(name: &str, key: &Key) {
     unsafe { ext_ffi::add_uref(name_ptr, name_size, key_ptr, key_size) };
 }
 
+/// Removes the key stored under the given name from the known_urefs map.
+/// Does nothing if there is no such name.
+pub fn remove_uref(name: &str) {
+    let (name_ptr, name_size, _bytes) = str_ref_to_ptr(name);
+    unsafe { ext_ffi::remove_uref(name_ptr, name_size) };
+}
+
 /// Return `t` to the host, terminating the currently running module.
 /// Note this function is only relevent to contracts stored on chain which
 /// return a value to their caller. The return value of a directly deployed
@@ -280,6 +334,82 @@ pub fn call_contract<A: ArgsParser, T: FromBytes>(
     deserialize(&res_bytes).unwrap()
 }
 
+/// Reason why a contract called with `try_call_contract` failed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CallError {
+    /// The called contract stopped the execution with `revert`.
+    Revert(u32),
+    OutOfGas,
+    /// The called contract tried to use a URef it doesn't have access to.
+    ForgedReference,
+    /// Any other error which occurred during the call.
+    Failed,
+}
+
+const CALL_ERROR_REVERT_ID: u8 = 0;
+const CALL_ERROR_OUT_OF_GAS_ID: u8 = 1;
+const CALL_ERROR_FORGED_REFERENCE_ID: u8 = 2;
+const CALL_ERROR_FAILED_ID: u8 = 3;
+
+impl ToBytes for CallError {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        match self {
+            CallError::Revert(status) => {
+                let mut result = vec![CALL_ERROR_REVERT_ID];
+                result.append(&mut status.to_bytes()?);
+                Ok(result)
+            }
+            CallError::OutOfGas => Ok(vec![CALL_ERROR_OUT_OF_GAS_ID]),
+            CallError::ForgedReference => Ok(vec![CALL_ERROR_FORGED_REFERENCE_ID]),
+            CallError::Failed => Ok(vec![CALL_ERROR_FAILED_ID]),
+        }
+    }
+}
+
+impl FromBytes for CallError {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (id, rem): (u8, &[u8]) = FromBytes::from_bytes(bytes)?;
+        match id {
+            CALL_ERROR_REVERT_ID => {
+                let (status, rem): (u32, &[u8]) = FromBytes::from_bytes(rem)?;
+                Ok((CallError::Revert(status), rem))
+            }
+            CALL_ERROR_OUT_OF_GAS_ID => Ok((CallError::OutOfGas, rem)),
+            CALL_ERROR_FORGED_REFERENCE_ID => Ok((CallError::ForgedReference, rem)),
+            CALL_ERROR_FAILED_ID => Ok((CallError::Failed, rem)),
+            _ => Err(bytesrepr::Error::FormattingError),
+        }
+    }
+}
+
+/// Like `call_contract`, but a failure of the called contract is returned to the
+/// caller instead of failing the whole deploy. All effects and events of a failed
+/// call are discarded, while the gas it used stays charged.
+#[allow(clippy::ptr_arg)]
+pub fn try_call_contract<A: ArgsParser, T: FromBytes>(
+    c_ptr: ContractPointer,
+    args: &A,
+    extra_urefs: &Vec<Key>,
+) -> Result<T, CallError> {
+    let contract_key: Key = c_ptr.into();
+    let (key_ptr, key_size, _bytes1) = to_ptr(&contract_key);
+    let (args_ptr, args_size, _bytes2) = ArgsParser::parse(args).map(|args| to_ptr(&args)).unwrap();
+    let (urefs_ptr, urefs_size, _bytes3) = to_ptr(extra_urefs);
+    let res_size = unsafe {
+        ext_ffi::try_call_contract(
+            key_ptr, key_size, args_ptr, args_size, urefs_ptr, urefs_size,
+        )
+    };
+    let res_ptr = alloc_bytes(res_size);
+    let res_bytes = unsafe {
+        ext_ffi::get_call_result(res_ptr);
+        Vec::from_raw_parts(res_ptr, res_size, res_size)
+    };
+    // The host returns the serialized result of the callee wrapped in a `Result`.
+    let outcome: Result<Vec<u8>, CallError> = deserialize(&res_bytes).unwrap();
+    outcome.map(|bytes| deserialize(&bytes).unwrap())
+}
+
 /// Checks if all the keys contained in the given `Value`
 /// (rather, thing that can be turned into a `Value`) are
 /// valid, in the sense that all of the urefs (and their access rights)
@@ -291,3 +421,115 @@ pub fn is_valid<T: Into<Value>>(t: T) -> bool {
     let result = unsafe { ext_ffi::is_valid(value_ptr, value_size) };
     result != 0
 }
+
+/// Emits an event which is returned together with the result of the deploy,
+/// so that it can be picked up by off-chain indexers. Events are not stored
+/// in the global state and are dropped if the deploy fails.
+pub fn emit_event(topic: &str, payload: &[u8]) {
+    let (topic_ptr, topic_size, _bytes) = str_ref_to_ptr(topic);
+    unsafe { ext_ffi::emit_event(topic_ptr, topic_size, payload.as_ptr(), payload.len()) };
+}
+
+/// Outcome of a transfer between the main purses of two accounts.
+#[repr(i32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TransferResult {
+    TransferredToExistingAccount = 0,
+    /// There was no account under the target key, so a new one has been created.
+    TransferredToNewAccount = 1,
+    /// The main purse of the calling account doesn't hold enough funds.
+    InsufficientFunds = 2,
+}
+
+/// Transfers `amount` from the main purse of the account in whose context
+/// the deploy is run, to the main purse of the account identified by `target`.
+/// The target account is created if it doesn't exist yet.
+pub fn transfer_to_account(target: PublicKey, amount: U512) -> TransferResult {
+    let (target_ptr, target_size, _bytes1) = to_ptr(&target);
+    let (amount_ptr, amount_size, _bytes2) = to_ptr(&amount);
+    let result =
+        unsafe { ext_ffi::transfer_to_account(target_ptr, target_size, amount_ptr, amount_size) };
+    match result {
+        0 => TransferResult::TransferredToExistingAccount,
+        1 => TransferResult::TransferredToNewAccount,
+        2 => TransferResult::InsufficientFunds,
+        other => panic!("Unknown transfer result: {}", other),
+    }
+}
+
+/// Returns the time of the block in which the deploy is executed, as provided by the node
+/// (in milliseconds since the Unix epoch).
+pub fn get_blocktime() -> u64 {
+    unsafe { ext_ffi::get_blocktime() }
+}
+
+/// Returns the height of the block in which the deploy is executed.
+pub fn get_block_height() -> u64 {
+    unsafe { ext_ffi::get_block_height() }
+}
+
+/// Returns the key of the entity which called the current module: the account
+/// which sent the deploy for session code, or the calling contract for sub-calls.
+pub fn get_caller() -> Key {
+    let caller_size = unsafe { ext_ffi::load_caller() };
+    let dest_ptr = alloc_bytes(caller_size);
+    let caller_bytes = unsafe {
+        ext_ffi::get_caller(dest_ptr);
+        Vec::from_raw_parts(dest_ptr, caller_size, caller_size)
+    };
+    // TODO: better error handling (i.e. pass the `Result` on)
+    deserialize(&caller_bytes).unwrap()
+}
+
+/// Phase of the deploy in which the current module is executed.
+#[repr(i32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Phase {
+    Payment = 0,
+    Session = 1,
+}
+
+/// Returns whether the current module runs as payment or session code.
+pub fn get_phase() -> Phase {
+    match unsafe { ext_ffi::get_phase() } {
+        0 => Phase::Payment,
+        1 => Phase::Session,
+        other => panic!("Unknown phase: {}", other),
+    }
+}
+
+/// Status with which `unwrap_or_revert` reverts the execution.
+pub const UNSPECIFIED_REVERT_STATUS: u32 = u32::max_value();
+
+/// Stops the execution of the deploy, discarding its effects, and reports `status`
+/// (a contract-defined error code) in the result of the deploy.
+pub fn revert(status: u32) -> ! {
+    unsafe { ext_ffi::revert(status) }
+}
+
+/// Unwrapping of `Option` and `Result` which reverts the execution instead of panicking,
+/// so that the reason of the failure is reported to the client.
+pub trait UnwrapOrRevert<T> {
+    /// Returns the contained value or reverts the execution with `status`.
+    fn unwrap_or_revert_with(self, status: u32) -> T;
+
+    /// Returns the contained value or reverts the execution with `UNSPECIFIED_REVERT_STATUS`.
+    fn unwrap_or_revert(self) -> T
+    where
+        Self: Sized,
+    {
+        self.unwrap_or_revert_with(UNSPECIFIED_REVERT_STATUS)
+    }
+}
+
+impl<T> UnwrapOrRevert<T> for Option<T> {
+    fn unwrap_or_revert_with(self, status: u32) -> T {
+        self.unwrap_or_else(|| revert(status))
+    }
+}
+
+impl<T, E> UnwrapOrRevert<T> for Result<T, E> {
+    fn unwrap_or_revert_with(self, status: u32) -> T {
+        self.unwrap_or_else(|_| revert(status))
+    }
+}
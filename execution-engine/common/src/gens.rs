@@ -1,9 +1,13 @@
 use crate::key::*;
-use crate::value::account::{AssociatedKeys, PublicKey, Weight, MAX_KEYS};
+use crate::value::account::{
+    ActionThresholds, AssociatedKeys, PublicKey, PurseId, Weight, MAX_KEYS,
+};
 use crate::value::*;
+use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
 use alloc::string::String;
 use proptest::collection::{btree_map, vec};
+use proptest::option;
 use proptest::prelude::*;
 
 pub fn u8_slice_32() -> impl Strategy<Value = [u8; 32]> {
@@ -58,13 +62,32 @@ pub fn associated_keys_arb(size: usize) -> impl Strategy<Value = AssociatedKeys>
     })
 }
 
+pub fn purse_id_arb() -> impl Strategy<Value = PurseId> {
+    u8_slice_32().prop_map(PurseId::new)
+}
+
+pub fn action_thresholds_arb() -> impl Strategy<Value = ActionThresholds> {
+    (weight_arb(), weight_arb())
+        .prop_map(|(deployment, key_management)| ActionThresholds::new(deployment, key_management))
+}
+
 pub fn account_arb() -> impl Strategy<Value = Account> {
     u8_slice_32().prop_flat_map(|b| {
         any::<u64>().prop_flat_map(move |u64arb| {
             associated_keys_arb(MAX_KEYS - 1).prop_flat_map(move |mut associated_keys| {
                 associated_keys.add_key(b.into(), Weight::new(1)).unwrap();
-                uref_map_arb(3)
-                    .prop_map(move |urefs| Account::new(b, u64arb, urefs, associated_keys.clone()))
+                (uref_map_arb(3), purse_id_arb(), action_thresholds_arb()).prop_map(
+                    move |(urefs, purse_id, action_thresholds)| {
+                        Account::new(
+                            b,
+                            u64arb,
+                            urefs,
+                            associated_keys.clone(),
+                            purse_id,
+                            action_thresholds,
+                        )
+                    },
+                )
             })
         })
     })
@@ -73,8 +96,13 @@ pub fn account_arb() -> impl Strategy<Value = Account> {
 pub fn contract_arb() -> impl Strategy<Value = Contract> {
     any::<u64>().prop_flat_map(move |u64arb| {
         uref_map_arb(20).prop_flat_map(move |urefs| {
-            vec(any::<u8>(), 1..1000)
-                .prop_map(move |body| Contract::new(body, urefs.clone(), u64arb))
+            option::of(u8_slice_32()).prop_flat_map(move |admin| {
+                let urefs = urefs.clone();
+                vec(any::<u8>(), 1..1000).prop_map(move |body| match admin {
+                    Some(admin) => Contract::upgradable(body, urefs.clone(), u64arb, admin),
+                    None => Contract::new(body, urefs.clone(), u64arb),
+                })
+            })
         })
     })
 }
@@ -104,6 +132,21 @@ pub fn value_arb() -> impl Strategy<Value = Value> {
         contract_arb().prop_map(Value::Contract),
         u128_arb().prop_map(Value::UInt128),
         u256_arb().prop_map(Value::UInt256),
-        u512_arb().prop_map(Value::UInt512)
+        u512_arb().prop_map(Value::UInt512),
+        any::<bool>().prop_map(Value::Bool),
+        any::<u64>().prop_map(Value::UInt64),
+        Just(Value::Unit),
+        option::of(any::<i32>())
+            .prop_map(|option| Value::Option(option.map(|i| Box::new(Value::Int32(i))))),
+        (any::<u64>(), key_arb())
+            .prop_map(|(u, k)| Value::Tuple(vec![Value::UInt64(u), Value::Key(k)])),
+        vec(key_arb(), 0..100).prop_map(Value::ListKey),
+        vec(any::<i32>().prop_map(Value::Int32), 0..100).prop_map(Value::List),
+        btree_map(
+            "\\PC*".prop_map(Value::String),
+            key_arb().prop_map(Value::Key),
+            0..50
+        )
+        .prop_map(Value::Map)
     ]
 }
@@ -51,6 +51,22 @@ mod ext_ffi {
             extra_urefs_size: usize,
             hash_ptr: *const u8,
         );
+        pub fn store_upgradable_function(
+            value_ptr: *const u8,
+            value_size: usize,
+            extra_urefs_ptr: *const u8,
+            extra_urefs_size: usize,
+            hash_ptr: *const u8,
+            admin_ptr: *const u8,
+        );
+        pub fn upgrade_contract(
+            key_ptr: *const u8,
+            key_size: usize,
+            admin_ptr: *const u8,
+            admin_size: usize,
+            name_ptr: *const u8,
+            name_size: usize,
+        );
         pub fn load_arg(i: u32) -> usize;
         pub fn get_arg(dest: *mut u8); //can only be called after `load_arg`
         pub fn ret(
@@ -69,13 +85,41 @@ mod ext_ffi {
             extra_urefs_ptr: *const u8,
             extra_urefs_size: usize,
         ) -> usize;
-        pub fn get_call_result(res_ptr: *mut u8); //can only be called after `call_contract`
+        pub fn try_call_contract(
+            key_ptr: *const u8,
+            key_size: usize,
+            args_ptr: *const u8,
+            args_size: usize,
+            extra_urefs_ptr: *const u8,
+            extra_urefs_size: usize,
+        ) -> usize;
+        //can only be called after `call_contract` or `try_call_contract`
+        pub fn get_call_result(res_ptr: *mut u8);
         pub fn get_uref(name_ptr: *const u8, name_size: usize, dest: *mut u8);
         pub fn has_uref_name(name_ptr: *const u8, name_size: usize) -> i32;
         pub fn add_uref(name_ptr: *const u8, name_size: usize, key_ptr: *const u8, key_size: usize);
+        pub fn remove_uref(name_ptr: *const u8, name_size: usize);
         pub fn protocol_version() -> u64;
         pub fn seed(dest: *mut u8);
         pub fn is_valid(value_ptr: *const u8, value_size: usize) -> i32;
+        pub fn transfer_to_account(
+            target_ptr: *const u8,
+            target_size: usize,
+            amount_ptr: *const u8,
+            amount_size: usize,
+        ) -> i32;
+        pub fn emit_event(
+            topic_ptr: *const u8,
+            topic_size: usize,
+            payload_ptr: *const u8,
+            payload_size: usize,
+        );
+        pub fn get_blocktime() -> u64;
+        pub fn get_block_height() -> u64;
+        pub fn load_caller() -> usize;
+        pub fn get_caller(dest_ptr: *mut u8); //can only be called after `load_caller`
+        pub fn get_phase() -> i32;
+        pub fn revert(status: u32) -> !;
     }
 }
 
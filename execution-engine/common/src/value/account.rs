@@ -1,6 +1,7 @@
 use crate::bytesrepr::{Error, FromBytes, ToBytes, U32_SIZE, U64_SIZE, U8_SIZE};
-use crate::key::{Key, UREF_SIZE};
+use crate::key::{AccessRights, Key, UREF_SIZE};
 use alloc::collections::btree_map::BTreeMap;
+use alloc::collections::btree_set::BTreeSet;
 use alloc::string::String;
 use alloc::vec::Vec;
 
@@ -17,6 +18,10 @@ impl Weight {
     pub fn new(weight: u8) -> Weight {
         Weight(weight)
     }
+
+    pub fn value(&self) -> u8 {
+        self.0
+    }
 }
 
 pub const WEIGHT_SIZE: usize = U8_SIZE;
@@ -30,6 +35,10 @@ impl PublicKey {
     pub fn new(key: [u8; KEY_SIZE]) -> PublicKey {
         PublicKey(key)
     }
+
+    pub fn value(&self) -> [u8; KEY_SIZE] {
+        self.0
+    }
 }
 
 impl From<[u8; KEY_SIZE]> for PublicKey {
@@ -83,12 +92,69 @@ impl AssociatedKeys {
     }
 }
 
-#[derive(PartialEq, Eq, Clone, Debug)]
+/// Identifies a purse - an unforgeable reference under which a balance
+/// (`Value::UInt512`) is stored.
+#[derive(PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Debug)]
+pub struct PurseId([u8; PURSE_ID_SIZE]);
+
+pub const PURSE_ID_SIZE: usize = 32;
+
+impl PurseId {
+    pub fn new(id: [u8; PURSE_ID_SIZE]) -> PurseId {
+        PurseId(id)
+    }
+
+    pub fn value(&self) -> [u8; PURSE_ID_SIZE] {
+        self.0
+    }
+
+    /// Key under which the balance of the purse is stored.
+    pub fn balance_key(&self) -> Key {
+        Key::URef(self.0, AccessRights::READ_ADD_WRITE)
+    }
+}
+
+/// Weights which the associated keys signing a deploy have to meet
+/// in order to perform the given kind of action.
+#[derive(PartialOrd, Ord, PartialEq, Eq, Clone, Debug)]
+pub struct ActionThresholds {
+    deployment: Weight,
+    key_management: Weight,
+}
+
+pub const ACTION_THRESHOLDS_SIZE: usize = 2 * WEIGHT_SIZE;
+
+impl ActionThresholds {
+    pub fn new(deployment: Weight, key_management: Weight) -> ActionThresholds {
+        ActionThresholds {
+            deployment,
+            key_management,
+        }
+    }
+
+    pub fn deployment(&self) -> &Weight {
+        &self.deployment
+    }
+
+    pub fn key_management(&self) -> &Weight {
+        &self.key_management
+    }
+}
+
+impl Default for ActionThresholds {
+    fn default() -> Self {
+        ActionThresholds::new(Weight::new(1), Weight::new(1))
+    }
+}
+
+#[derive(PartialOrd, Ord, PartialEq, Eq, Clone, Debug)]
 pub struct Account {
     public_key: [u8; 32],
     nonce: u64,
     known_urefs: BTreeMap<String, Key>,
     associated_keys: AssociatedKeys,
+    purse_id: PurseId,
+    action_thresholds: ActionThresholds,
 }
 
 impl Account {
@@ -97,19 +163,43 @@ impl Account {
         nonce: u64,
         known_urefs: BTreeMap<String, Key>,
         associated_keys: AssociatedKeys,
+        purse_id: PurseId,
+        action_thresholds: ActionThresholds,
     ) -> Self {
         Account {
             public_key,
             nonce,
             known_urefs,
             associated_keys,
+            purse_id,
+            action_thresholds,
         }
     }
 
+    /// Creates a fresh account for `public_key`. The account is controlled by
+    /// that key alone and uses the default action thresholds.
+    pub fn create(public_key: [u8; 32], purse_id: PurseId) -> Self {
+        let associated_keys = AssociatedKeys::new(PublicKey::new(public_key), Weight::new(1));
+        Account::new(
+            public_key,
+            0,
+            BTreeMap::new(),
+            associated_keys,
+            purse_id,
+            ActionThresholds::default(),
+        )
+    }
+
     pub fn insert_urefs(&mut self, keys: &mut BTreeMap<String, Key>) {
         self.known_urefs.append(keys);
     }
 
+    pub fn remove_urefs(&mut self, names: &BTreeSet<String>) {
+        for name in names {
+            self.known_urefs.remove(name);
+        }
+    }
+
     pub fn urefs_lookup(&self) -> &BTreeMap<String, Key> {
         &self.known_urefs
     }
@@ -125,6 +215,24 @@ impl Account {
     pub fn nonce(&self) -> u64 {
         self.nonce
     }
+
+    pub fn associated_keys(&self) -> &AssociatedKeys {
+        &self.associated_keys
+    }
+
+    pub fn purse_id(&self) -> PurseId {
+        self.purse_id
+    }
+
+    pub fn action_thresholds(&self) -> &ActionThresholds {
+        &self.action_thresholds
+    }
+
+    /// Bumps the nonce of the account by one. Called for every deploy
+    /// executed in the context of this account.
+    pub fn increment_nonce(&mut self) {
+        self.nonce += 1;
+    }
 }
 
 impl ToBytes for Weight {
@@ -173,22 +281,53 @@ impl FromBytes for AssociatedKeys {
     }
 }
 
+impl ToBytes for PurseId {
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        ToBytes::to_bytes(&self.0)
+    }
+}
+
+impl FromBytes for PurseId {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), Error> {
+        let (id, rem): ([u8; PURSE_ID_SIZE], &[u8]) = FromBytes::from_bytes(bytes)?;
+        Ok((PurseId(id), rem))
+    }
+}
+
+impl ToBytes for ActionThresholds {
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut result: Vec<u8> = Vec::with_capacity(ACTION_THRESHOLDS_SIZE);
+        result.append(&mut self.deployment.to_bytes()?);
+        result.append(&mut self.key_management.to_bytes()?);
+        Ok(result)
+    }
+}
+
+impl FromBytes for ActionThresholds {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), Error> {
+        let (deployment, rem1): (Weight, &[u8]) = FromBytes::from_bytes(bytes)?;
+        let (key_management, rem2): (Weight, &[u8]) = FromBytes::from_bytes(rem1)?;
+        Ok((ActionThresholds::new(deployment, key_management), rem2))
+    }
+}
+
 impl ToBytes for Account {
     fn to_bytes(&self) -> Result<Vec<u8>, Error> {
         let associated_keys_size =
             self.associated_keys.0.len() * (PUBLIC_KEY_SIZE + WEIGHT_SIZE) + U32_SIZE;
         let known_urefs_size = UREF_SIZE * self.known_urefs.len() + U32_SIZE;
-        if known_urefs_size + associated_keys_size
-            >= u32::max_value() as usize - KEY_SIZE - U64_SIZE
-        {
+        let fixed_size = KEY_SIZE + U64_SIZE + PURSE_ID_SIZE + ACTION_THRESHOLDS_SIZE;
+        if known_urefs_size + associated_keys_size >= u32::max_value() as usize - fixed_size {
             return Err(Error::OutOfMemoryError);
         }
         let mut result: Vec<u8> =
-            Vec::with_capacity(KEY_SIZE + U64_SIZE + known_urefs_size + associated_keys_size);
+            Vec::with_capacity(fixed_size + known_urefs_size + associated_keys_size);
         result.extend(&self.public_key.to_bytes()?);
         result.append(&mut self.nonce.to_bytes()?);
         result.append(&mut self.known_urefs.to_bytes()?);
         result.append(&mut self.associated_keys.to_bytes()?);
+        result.append(&mut self.purse_id.to_bytes()?);
+        result.append(&mut self.action_thresholds.to_bytes()?);
         Ok(result)
     }
 }
@@ -199,14 +338,18 @@ impl FromBytes for Account {
         let (nonce, rem2): (u64, &[u8]) = FromBytes::from_bytes(rem1)?;
         let (known_urefs, rem3): (BTreeMap<String, Key>, &[u8]) = FromBytes::from_bytes(rem2)?;
         let (associated_keys, rem4): (AssociatedKeys, &[u8]) = FromBytes::from_bytes(rem3)?;
+        let (purse_id, rem5): (PurseId, &[u8]) = FromBytes::from_bytes(rem4)?;
+        let (action_thresholds, rem6): (ActionThresholds, &[u8]) = FromBytes::from_bytes(rem5)?;
         Ok((
             Account {
                 public_key,
                 nonce,
                 known_urefs,
                 associated_keys,
+                purse_id,
+                action_thresholds,
             },
-            rem4,
+            rem6,
         ))
     }
 }
@@ -214,7 +357,7 @@ impl FromBytes for Account {
 #[cfg(test)]
 mod tests {
     use crate::value::account::{
-        AddKeyFailure, AssociatedKeys, PublicKey, Weight, KEY_SIZE, MAX_KEYS,
+        Account, AddKeyFailure, AssociatedKeys, PublicKey, PurseId, Weight, KEY_SIZE, MAX_KEYS,
     };
 
     #[test]
@@ -261,4 +404,26 @@ mod tests {
         assert!(keys.remove_key(&pk));
         assert!(!keys.remove_key(&PublicKey([1u8; KEY_SIZE])));
     }
+
+    #[test]
+    fn account_increment_nonce() {
+        let mut account = Account::create([0u8; KEY_SIZE], PurseId::new([1u8; 32]));
+        account.increment_nonce();
+        account.increment_nonce();
+        assert_eq!(account.nonce(), 2);
+    }
+
+    #[test]
+    fn created_account_is_controlled_by_its_key() {
+        let public_key = [3u8; KEY_SIZE];
+        let account = Account::create(public_key, PurseId::new([1u8; 32]));
+        assert_eq!(account.nonce(), 0);
+        assert!(account.urefs_lookup().is_empty());
+        assert_eq!(
+            account.associated_keys().get(&PublicKey::new(public_key)),
+            Some(&Weight::new(1))
+        );
+        assert_eq!(account.purse_id(), PurseId::new([1u8; 32]));
+        assert_eq!(account.action_thresholds(), &Default::default());
+    }
 }
@@ -1,14 +1,21 @@
 use crate::bytesrepr::{Error, FromBytes, ToBytes, U32_SIZE, U64_SIZE};
 use crate::key::{Key, UREF_SIZE};
 use alloc::collections::btree_map::BTreeMap;
+use alloc::collections::btree_set::BTreeSet;
 use alloc::string::String;
 use alloc::vec::Vec;
 
-#[derive(PartialEq, Eq, Clone, Debug)]
+/// Serialized size of an optional admin URef address (tag + address).
+const ADMIN_SIZE: usize = U32_SIZE + 32;
+
+#[derive(PartialOrd, Ord, PartialEq, Eq, Clone, Debug)]
 pub struct Contract {
     bytes: Vec<u8>,
     known_urefs: BTreeMap<String, Key>,
     protocol_version: u64,
+    /// Address of the URef which grants the right to upgrade the contract.
+    /// Contracts without an admin are immutable.
+    admin: Option<[u8; 32]>,
 }
 
 impl Contract {
@@ -17,6 +24,33 @@ impl Contract {
             bytes,
             known_urefs,
             protocol_version,
+            admin: None,
+        }
+    }
+
+    /// Creates a contract which can be upgraded by anyone holding the URef
+    /// with address `admin` and WRITE rights.
+    pub fn upgradable(
+        bytes: Vec<u8>,
+        known_urefs: BTreeMap<String, Key>,
+        protocol_version: u64,
+        admin: [u8; 32],
+    ) -> Self {
+        Contract {
+            bytes,
+            known_urefs,
+            protocol_version,
+            admin: Some(admin),
+        }
+    }
+
+    /// Returns a copy of this contract with its body replaced by `bytes`.
+    /// Known urefs and the admin are preserved.
+    pub fn upgrade(self, bytes: Vec<u8>, protocol_version: u64) -> Self {
+        Contract {
+            bytes,
+            protocol_version,
+            ..self
         }
     }
 
@@ -24,6 +58,12 @@ impl Contract {
         self.known_urefs.append(keys);
     }
 
+    pub fn remove_urefs(&mut self, names: &BTreeSet<String>) {
+        for name in names {
+            self.known_urefs.remove(name);
+        }
+    }
+
     pub fn urefs_lookup(&self) -> &BTreeMap<String, Key> {
         &self.known_urefs
     }
@@ -39,11 +79,15 @@ impl Contract {
     pub fn protocol_version(&self) -> u64 {
         self.protocol_version
     }
+
+    pub fn admin(&self) -> Option<[u8; 32]> {
+        self.admin
+    }
 }
 
 impl ToBytes for Contract {
     fn to_bytes(&self) -> Result<Vec<u8>, Error> {
-        if self.bytes.len() + UREF_SIZE * self.known_urefs.len() + U64_SIZE
+        if self.bytes.len() + UREF_SIZE * self.known_urefs.len() + U64_SIZE + ADMIN_SIZE
             >= u32::max_value() as usize - U32_SIZE * 2
         {
             return Err(Error::OutOfMemoryError);
@@ -52,12 +96,14 @@ impl ToBytes for Contract {
                     self.bytes.len() +                  //size for elements of bytes
                     U32_SIZE +                                 //size for length of known_urefs
                     UREF_SIZE * self.known_urefs.len() + //size for known_urefs elements
-                    U64_SIZE; // size for protocol_version
+                    U64_SIZE + // size for protocol_version
+                    ADMIN_SIZE; // size for admin
 
         let mut result = Vec::with_capacity(size);
         result.append(&mut self.bytes.to_bytes()?);
         result.append(&mut self.known_urefs.to_bytes()?);
         result.append(&mut self.protocol_version.to_bytes()?);
+        result.append(&mut self.admin.to_bytes()?);
         Ok(result)
     }
 }
@@ -67,13 +113,21 @@ impl FromBytes for Contract {
         let (bytes, rem1): (Vec<u8>, &[u8]) = FromBytes::from_bytes(bytes)?;
         let (known_urefs, rem2): (BTreeMap<String, Key>, &[u8]) = FromBytes::from_bytes(rem1)?;
         let (protocol_version, rem3): (u64, &[u8]) = FromBytes::from_bytes(rem2)?;
+        // Contracts stored before upgradable contracts were introduced end with the protocol
+        // version. They are always the last value of their input, so they have no admin.
+        let (admin, rem4): (Option<[u8; 32]>, &[u8]) = if rem3.is_empty() {
+            (None, rem3)
+        } else {
+            FromBytes::from_bytes(rem3)?
+        };
         Ok((
             Contract {
                 bytes,
                 known_urefs,
                 protocol_version,
+                admin,
             },
-            rem3,
+            rem4,
         ))
     }
 }
@@ -6,8 +6,11 @@ use crate::bytesrepr::{
     Error, FromBytes, ToBytes, U128_SIZE, U256_SIZE, U32_SIZE, U512_SIZE, U8_SIZE,
 };
 use crate::key::{self, UREF_SIZE};
+use alloc::boxed::Box;
+use alloc::collections::btree_map::BTreeMap;
 use alloc::string::String;
 use alloc::vec::Vec;
+use core::cmp;
 use core::convert::TryFrom;
 use core::iter;
 use core::mem::size_of;
@@ -16,7 +19,7 @@ pub use self::account::Account;
 pub use self::contract::Contract;
 pub use self::uint::{U128, U256, U512};
 
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
 pub enum Value {
     Int32(i32),
     UInt128(U128),
@@ -30,6 +33,15 @@ pub enum Value {
     Key(key::Key),
     Account(account::Account),
     Contract(contract::Contract),
+    Bool(bool),
+    UInt64(u64),
+    Unit,
+    Option(Option<Box<Value>>),
+    Tuple(Vec<Value>),
+    ListKey(Vec<key::Key>),
+    List(Vec<Value>),
+    /// Entries are kept (and serialized) in the order defined by `Ord` for `Value`.
+    Map(BTreeMap<Value, Value>),
 }
 
 const INT32_ID: u8 = 0;
@@ -44,37 +56,88 @@ const U128_ID: u8 = 8;
 const U256_ID: u8 = 9;
 const U512_ID: u8 = 10;
 const KEY_ID: u8 = 11;
+const BOOL_ID: u8 = 12;
+const U64_ID: u8 = 13;
+const UNIT_ID: u8 = 14;
+const OPTION_ID: u8 = 15;
+const TUPLE_ID: u8 = 16;
+const LISTKEY_ID: u8 = 17;
+const LIST_ID: u8 = 18;
+const MAP_ID: u8 = 19;
 
-use self::Value::*;
+/// Maximum nesting depth of `Option`, `Tuple`, `List` and `Map` values.
+pub const MAX_VALUE_DEPTH: usize = 16;
+/// Maximum number of elements of a single `Tuple`, `List` or `Map`.
+pub const MAX_COLLECTION_LEN: usize = 65_536;
 
 impl ToBytes for Value {
     fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        self.check_limits()?;
+        self.write_bytes()
+    }
+}
+
+/// Appends serialized `values` to `result`, which has to already contain their number.
+fn append_values<'a>(
+    result: &mut Vec<u8>,
+    values: impl Iterator<Item = &'a Value>,
+) -> Result<(), Error> {
+    for value in values {
+        let mut bytes = value.write_bytes()?;
+        if bytes.len() >= u32::max_value() as usize - result.len() {
+            return Err(Error::OutOfMemoryError);
+        }
+        result.append(&mut bytes);
+    }
+    Ok(())
+}
+
+/// Reads the number of elements of a collection, making sure it is within limits.
+fn read_collection_len(bytes: &[u8]) -> Result<(usize, &[u8]), Error> {
+    let (len, rem): (u32, &[u8]) = FromBytes::from_bytes(bytes)?;
+    if len as usize > MAX_COLLECTION_LEN {
+        return Err(Error::LimitExceeded);
+    }
+    Ok((len as usize, rem))
+}
+
+/// Creates the vector for the `len` elements of a collection encoded in `bytes`. As every
+/// element takes at least one byte, no more than `bytes.len()` elements are reserved,
+/// whatever the length read from the input.
+fn collection_with_capacity(len: usize, bytes: &[u8]) -> Vec<Value> {
+    Vec::with_capacity(cmp::min(len, bytes.len()))
+}
+
+impl Value {
+    /// Serializes the value. Limits are checked only once, for the outermost value
+    /// (see `to_bytes`), so nested values are serialized with this function.
+    fn write_bytes(&self) -> Result<Vec<u8>, Error> {
         match self {
-            Int32(i) => {
+            Value::Int32(i) => {
                 let mut result = Vec::with_capacity(U8_SIZE + U32_SIZE);
                 result.push(INT32_ID);
                 result.append(&mut i.to_bytes()?);
                 Ok(result)
             }
-            UInt128(u) => {
+            Value::UInt128(u) => {
                 let mut result = Vec::with_capacity(U8_SIZE + U128_SIZE);
                 result.push(U128_ID);
                 result.append(&mut u.to_bytes()?);
                 Ok(result)
             }
-            UInt256(u) => {
+            Value::UInt256(u) => {
                 let mut result = Vec::with_capacity(U8_SIZE + U256_SIZE);
                 result.push(U256_ID);
                 result.append(&mut u.to_bytes()?);
                 Ok(result)
             }
-            UInt512(u) => {
+            Value::UInt512(u) => {
                 let mut result = Vec::with_capacity(U8_SIZE + U512_SIZE);
                 result.push(U512_ID);
                 result.append(&mut u.to_bytes()?);
                 Ok(result)
             }
-            ByteArray(arr) => {
+            Value::ByteArray(arr) => {
                 if arr.len() >= u32::max_value() as usize - U8_SIZE - U32_SIZE {
                     return Err(Error::OutOfMemoryError);
                 }
@@ -83,7 +146,7 @@ impl ToBytes for Value {
                 result.append(&mut arr.to_bytes()?);
                 Ok(result)
             }
-            ListInt32(arr) => {
+            Value::ListInt32(arr) => {
                 if arr.len() * size_of::<i32>() >= u32::max_value() as usize - U8_SIZE - U32_SIZE {
                     return Err(Error::OutOfMemoryError);
                 }
@@ -92,7 +155,7 @@ impl ToBytes for Value {
                 result.append(&mut arr.to_bytes()?);
                 Ok(result)
             }
-            String(s) => {
+            Value::String(s) => {
                 if s.len() >= u32::max_value() as usize - U8_SIZE - U32_SIZE {
                     return Err(Error::OutOfMemoryError);
                 }
@@ -102,7 +165,7 @@ impl ToBytes for Value {
                 result.append(&mut s.to_bytes()?);
                 Ok(result)
             }
-            Account(a) => {
+            Value::Account(a) => {
                 let mut result = Vec::new();
                 result.push(ACCT_ID);
                 let mut bytes = a.to_bytes()?;
@@ -112,8 +175,8 @@ impl ToBytes for Value {
                 result.append(&mut bytes);
                 Ok(result)
             }
-            Contract(c) => Ok(iter::once(CONTRACT_ID).chain(c.to_bytes()?).collect()),
-            NamedKey(n, k) => {
+            Value::Contract(c) => Ok(iter::once(CONTRACT_ID).chain(c.to_bytes()?).collect()),
+            Value::NamedKey(n, k) => {
                 if n.len() + UREF_SIZE >= u32::max_value() as usize - U32_SIZE - U8_SIZE {
                     return Err(Error::OutOfMemoryError);
                 }
@@ -127,14 +190,14 @@ impl ToBytes for Value {
                 result.append(&mut k.to_bytes()?);
                 Ok(result)
             }
-            Key(k) => {
+            Value::Key(k) => {
                 let size: usize = U8_SIZE + UREF_SIZE;
                 let mut result = Vec::with_capacity(size);
                 result.push(KEY_ID);
                 result.append(&mut k.to_bytes()?);
                 Ok(result)
             }
-            ListString(arr) => {
+            Value::ListString(arr) => {
                 let size: usize = U8_SIZE + U32_SIZE + arr.len();
                 let mut result = Vec::with_capacity(size);
                 result.push(LISTSTRING_ID);
@@ -145,88 +208,268 @@ impl ToBytes for Value {
                 result.append(&mut arr.to_bytes()?);
                 Ok(result)
             }
+            Value::Bool(b) => {
+                let mut result = Vec::with_capacity(U8_SIZE + U8_SIZE);
+                result.push(BOOL_ID);
+                result.append(&mut b.to_bytes()?);
+                Ok(result)
+            }
+            Value::UInt64(u) => {
+                let mut result = Vec::with_capacity(U8_SIZE + U64_SIZE);
+                result.push(U64_ID);
+                result.append(&mut u.to_bytes()?);
+                Ok(result)
+            }
+            Value::Unit => Ok(vec![UNIT_ID]),
+            // Tagged the same way as `Option<T>` in `bytesrepr`.
+            Value::Option(option) => {
+                let mut result = Vec::with_capacity(U8_SIZE + U32_SIZE);
+                result.push(OPTION_ID);
+                match option {
+                    None => result.append(&mut 0u32.to_bytes()?),
+                    Some(value) => {
+                        result.append(&mut 1u32.to_bytes()?);
+                        result.append(&mut value.write_bytes()?);
+                    }
+                }
+                Ok(result)
+            }
+            Value::Tuple(values) => {
+                let mut result = Vec::with_capacity(U8_SIZE + U32_SIZE);
+                result.push(TUPLE_ID);
+                result.append(&mut (values.len() as u32).to_bytes()?);
+                append_values(&mut result, values.iter())?;
+                Ok(result)
+            }
+            Value::ListKey(keys) => {
+                if keys.len() * UREF_SIZE >= u32::max_value() as usize - U8_SIZE - U32_SIZE {
+                    return Err(Error::OutOfMemoryError);
+                }
+                let mut result = Vec::with_capacity(U8_SIZE + U32_SIZE + UREF_SIZE * keys.len());
+                result.push(LISTKEY_ID);
+                result.append(&mut keys.to_bytes()?);
+                Ok(result)
+            }
+            Value::List(values) => {
+                let mut result = Vec::with_capacity(U8_SIZE + U32_SIZE);
+                result.push(LIST_ID);
+                result.append(&mut (values.len() as u32).to_bytes()?);
+                append_values(&mut result, values.iter())?;
+                Ok(result)
+            }
+            Value::Map(map) => {
+                let mut result = Vec::with_capacity(U8_SIZE + U32_SIZE);
+                result.push(MAP_ID);
+                result.append(&mut (map.len() as u32).to_bytes()?);
+                append_values(
+                    &mut result,
+                    map.iter()
+                        .flat_map(|(k, v)| iter::once(k).chain(iter::once(v))),
+                )?;
+                Ok(result)
+            }
         }
     }
-}
-impl FromBytes for Value {
-    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), Error> {
+
+    /// Deserializes a value nested `depth` levels deep in the outermost value.
+    fn read_bytes(bytes: &[u8], depth: usize) -> Result<(Self, &[u8]), Error> {
+        if depth > MAX_VALUE_DEPTH {
+            return Err(Error::LimitExceeded);
+        }
         let (id, rest): (u8, &[u8]) = FromBytes::from_bytes(bytes)?;
         match id {
             INT32_ID => {
                 let (i, rem): (i32, &[u8]) = FromBytes::from_bytes(rest)?;
-                Ok((Int32(i), rem))
+                Ok((Value::Int32(i), rem))
             }
             U128_ID => {
                 let (u, rem): (U128, &[u8]) = FromBytes::from_bytes(rest)?;
-                Ok((UInt128(u), rem))
+                Ok((Value::UInt128(u), rem))
             }
             U256_ID => {
                 let (u, rem): (U256, &[u8]) = FromBytes::from_bytes(rest)?;
-                Ok((UInt256(u), rem))
+                Ok((Value::UInt256(u), rem))
             }
             U512_ID => {
                 let (u, rem): (U512, &[u8]) = FromBytes::from_bytes(rest)?;
-                Ok((UInt512(u), rem))
+                Ok((Value::UInt512(u), rem))
             }
             BYTEARRAY_ID => {
                 let (arr, rem): (Vec<u8>, &[u8]) = FromBytes::from_bytes(rest)?;
-                Ok((ByteArray(arr), rem))
+                Ok((Value::ByteArray(arr), rem))
             }
             LISTINT32_ID => {
                 let (arr, rem): (Vec<i32>, &[u8]) = FromBytes::from_bytes(rest)?;
-                Ok((ListInt32(arr), rem))
+                Ok((Value::ListInt32(arr), rem))
             }
             STRING_ID => {
                 let (s, rem): (String, &[u8]) = FromBytes::from_bytes(rest)?;
-                Ok((String(s), rem))
+                Ok((Value::String(s), rem))
             }
             ACCT_ID => {
                 let (a, rem): (account::Account, &[u8]) = FromBytes::from_bytes(rest)?;
-                Ok((Account(a), rem))
+                Ok((Value::Account(a), rem))
             }
             CONTRACT_ID => {
                 let (contract, rem): (contract::Contract, &[u8]) = FromBytes::from_bytes(rest)?;
-                Ok((Contract(contract), rem))
+                Ok((Value::Contract(contract), rem))
             }
             NAMEDKEY_ID => {
                 let (name, rem1): (String, &[u8]) = FromBytes::from_bytes(rest)?;
                 let (key, rem2): (key::Key, &[u8]) = FromBytes::from_bytes(rem1)?;
-                Ok((NamedKey(name, key), rem2))
+                Ok((Value::NamedKey(name, key), rem2))
             }
             KEY_ID => {
                 let (key, rem): (key::Key, &[u8]) = FromBytes::from_bytes(rest)?;
-                Ok((Key(key), rem))
+                Ok((Value::Key(key), rem))
             }
             LISTSTRING_ID => {
                 let (arr, rem): (Vec<String>, &[u8]) = FromBytes::from_bytes(rest)?;
-                Ok((ListString(arr), rem))
+                Ok((Value::ListString(arr), rem))
+            }
+            BOOL_ID => {
+                let (b, rem): (bool, &[u8]) = FromBytes::from_bytes(rest)?;
+                Ok((Value::Bool(b), rem))
+            }
+            U64_ID => {
+                let (u, rem): (u64, &[u8]) = FromBytes::from_bytes(rest)?;
+                Ok((Value::UInt64(u), rem))
+            }
+            UNIT_ID => Ok((Value::Unit, rest)),
+            OPTION_ID => {
+                let (tag, rem): (u32, &[u8]) = FromBytes::from_bytes(rest)?;
+                match tag {
+                    0 => Ok((Value::Option(None), rem)),
+                    1 => {
+                        let (value, rem) = Value::read_bytes(rem, depth + 1)?;
+                        Ok((Value::Option(Some(Box::new(value))), rem))
+                    }
+                    _ => Err(Error::FormattingError),
+                }
+            }
+            TUPLE_ID => {
+                let (len, mut stream) = read_collection_len(rest)?;
+                let mut values = collection_with_capacity(len, stream);
+                for _ in 0..len {
+                    let (value, rem) = Value::read_bytes(stream, depth + 1)?;
+                    values.push(value);
+                    stream = rem;
+                }
+                Ok((Value::Tuple(values), stream))
+            }
+            LISTKEY_ID => {
+                let (keys, rem): (Vec<key::Key>, &[u8]) = FromBytes::from_bytes(rest)?;
+                Ok((Value::ListKey(keys), rem))
+            }
+            LIST_ID => {
+                let (len, mut stream) = read_collection_len(rest)?;
+                let mut values = collection_with_capacity(len, stream);
+                for _ in 0..len {
+                    let (value, rem) = Value::read_bytes(stream, depth + 1)?;
+                    values.push(value);
+                    stream = rem;
+                }
+                Ok((Value::List(values), stream))
+            }
+            MAP_ID => {
+                let (len, mut stream) = read_collection_len(rest)?;
+                let mut map = BTreeMap::new();
+                for _ in 0..len {
+                    let (key, rem) = Value::read_bytes(stream, depth + 1)?;
+                    let (value, rem) = Value::read_bytes(rem, depth + 1)?;
+                    // Keys have to be strictly increasing, so that every map
+                    // has exactly one (canonical) encoding.
+                    if let Some((last_key, _)) = map.iter().next_back() {
+                        if *last_key >= key {
+                            return Err(Error::FormattingError);
+                        }
+                    }
+                    map.insert(key, value);
+                    stream = rem;
+                }
+                Ok((Value::Map(map), stream))
             }
             _ => Err(Error::FormattingError),
         }
     }
+
+    /// Checks that nested values are at most `MAX_VALUE_DEPTH` levels deep and that
+    /// collections hold at most `MAX_COLLECTION_LEN` elements.
+    pub fn check_limits(&self) -> Result<(), Error> {
+        self.check_limits_at_depth(0)
+    }
+
+    fn check_limits_at_depth(&self, depth: usize) -> Result<(), Error> {
+        if depth > MAX_VALUE_DEPTH {
+            return Err(Error::LimitExceeded);
+        }
+        match self {
+            Value::Option(Some(value)) => value.check_limits_at_depth(depth + 1),
+            Value::Tuple(values) | Value::List(values) => {
+                if values.len() > MAX_COLLECTION_LEN {
+                    return Err(Error::LimitExceeded);
+                }
+                values
+                    .iter()
+                    .try_for_each(|value| value.check_limits_at_depth(depth + 1))
+            }
+            Value::Map(map) => {
+                if map.len() > MAX_COLLECTION_LEN {
+                    return Err(Error::LimitExceeded);
+                }
+                map.iter().try_for_each(|(k, v)| {
+                    k.check_limits_at_depth(depth + 1)?;
+                    v.check_limits_at_depth(depth + 1)
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl FromBytes for Value {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), Error> {
+        Value::read_bytes(bytes, 0)
+    }
 }
 
 impl Value {
     pub fn type_string(&self) -> String {
         match self {
-            Int32(_) => String::from("Int32"),
-            UInt128(_) => String::from("UInt128"),
-            UInt256(_) => String::from("UInt256"),
-            UInt512(_) => String::from("UInt512"),
-            ListInt32(_) => String::from("List[Int32]"),
-            String(_) => String::from("String"),
-            ByteArray(_) => String::from("ByteArray"),
-            Account(_) => String::from("Account"),
-            Contract(_) => String::from("Contract"),
-            NamedKey(_, _) => String::from("NamedKey"),
-            Key(_) => String::from("Key"),
-            ListString(_) => String::from("List[String]"),
+            Value::Int32(_) => String::from("Int32"),
+            Value::UInt128(_) => String::from("UInt128"),
+            Value::UInt256(_) => String::from("UInt256"),
+            Value::UInt512(_) => String::from("UInt512"),
+            Value::ListInt32(_) => String::from("List[Int32]"),
+            Value::String(_) => String::from("String"),
+            Value::ByteArray(_) => String::from("ByteArray"),
+            Value::Account(_) => String::from("Account"),
+            Value::Contract(_) => String::from("Contract"),
+            Value::NamedKey(_, _) => String::from("NamedKey"),
+            Value::Key(_) => String::from("Key"),
+            Value::ListString(_) => String::from("List[String]"),
+            Value::Bool(_) => String::from("Bool"),
+            Value::UInt64(_) => String::from("UInt64"),
+            Value::Unit => String::from("Unit"),
+            Value::Option(_) => String::from("Option"),
+            Value::Tuple(_) => String::from("Tuple"),
+            Value::ListKey(_) => String::from("List[Key]"),
+            Value::List(_) => String::from("List"),
+            Value::Map(_) => String::from("Map"),
+        }
+    }
+
+    /// Returns the wrapped value if this is a `Value::Option`.
+    pub fn into_option(self) -> Result<Option<Value>, String> {
+        match self {
+            Value::Option(option) => Ok(option.map(|value| *value)),
+            other => Err(other.type_string()),
         }
     }
 
     pub fn as_account(&self) -> &account::Account {
         match self {
-            Account(a) => a,
+            Value::Account(a) => a,
             _ => panic!("Not an account: {:?}", self),
         }
     }
@@ -265,6 +508,83 @@ from_try_from_impl!(String, String);
 from_try_from_impl!(key::Key, Key);
 from_try_from_impl!(account::Account, Account);
 from_try_from_impl!(contract::Contract, Contract);
+from_try_from_impl!(bool, Bool);
+from_try_from_impl!(u64, UInt64);
+from_try_from_impl!(Vec<key::Key>, ListKey);
+from_try_from_impl!(Vec<Value>, List);
+from_try_from_impl!(BTreeMap<Value, Value>, Map);
+
+impl From<()> for Value {
+    fn from(_unit: ()) -> Self {
+        Value::Unit
+    }
+}
+
+impl TryFrom<Value> for () {
+    type Error = String;
+
+    fn try_from(v: Value) -> Result<(), String> {
+        if let Value::Unit = v {
+            Ok(())
+        } else {
+            Err(v.type_string())
+        }
+    }
+}
+
+// `TryFrom<Value> for Option<Value>` is already provided by core (through `From<Value>`),
+// so an optional value is extracted with `Value::into_option` instead.
+impl From<Option<Value>> for Value {
+    fn from(option: Option<Value>) -> Self {
+        Value::Option(option.map(Box::new))
+    }
+}
+
+impl From<(Value, Value)> for Value {
+    fn from(tuple: (Value, Value)) -> Self {
+        Value::Tuple(vec![tuple.0, tuple.1])
+    }
+}
+
+impl TryFrom<Value> for (Value, Value) {
+    type Error = String;
+
+    fn try_from(v: Value) -> Result<(Value, Value), String> {
+        match v {
+            Value::Tuple(values) => {
+                let mut values = values.into_iter();
+                match (values.next(), values.next(), values.next()) {
+                    (Some(v1), Some(v2), None) => Ok((v1, v2)),
+                    _ => Err(String::from("Tuple")),
+                }
+            }
+            other => Err(other.type_string()),
+        }
+    }
+}
+
+impl From<(Value, Value, Value)> for Value {
+    fn from(tuple: (Value, Value, Value)) -> Self {
+        Value::Tuple(vec![tuple.0, tuple.1, tuple.2])
+    }
+}
+
+impl TryFrom<Value> for (Value, Value, Value) {
+    type Error = String;
+
+    fn try_from(v: Value) -> Result<(Value, Value, Value), String> {
+        match v {
+            Value::Tuple(values) => {
+                let mut values = values.into_iter();
+                match (values.next(), values.next(), values.next(), values.next()) {
+                    (Some(v1), Some(v2), Some(v3), None) => Ok((v1, v2, v3)),
+                    _ => Err(String::from("Tuple")),
+                }
+            }
+            other => Err(other.type_string()),
+        }
+    }
+}
 
 impl From<(String, key::Key)> for Value {
     fn from(tuple: (String, key::Key)) -> Self {
@@ -283,3 +603,143 @@ impl TryFrom<Value> for (String, key::Key) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Contract, Value, MAX_COLLECTION_LEN, MAX_VALUE_DEPTH};
+    use crate::bytesrepr::{self, Error, ToBytes, U32_SIZE};
+    use crate::key::{AccessRights, Key};
+    use alloc::boxed::Box;
+    use alloc::collections::btree_map::BTreeMap;
+    use alloc::string::String;
+    use core::convert::TryFrom;
+
+    fn nested_list(depth: usize) -> Value {
+        (0..depth).fold(Value::Unit, |value, _| Value::List(vec![value]))
+    }
+
+    #[test]
+    fn existing_encodings_are_unchanged() {
+        assert_eq!(Value::Int32(1).to_bytes().unwrap(), vec![0, 1, 0, 0, 0]);
+        assert_eq!(
+            Value::String(String::from("a")).to_bytes().unwrap(),
+            vec![3, 1, 0, 0, 0, 97]
+        );
+    }
+
+    #[test]
+    fn contract_without_admin_is_decoded() {
+        let contract = Contract::new(vec![1, 2], BTreeMap::new(), 1);
+        let mut bytes = Value::Contract(contract.clone()).to_bytes().unwrap();
+        // Encoding of the contract before upgradable contracts: no admin field at all.
+        bytes.truncate(bytes.len() - U32_SIZE);
+        assert_eq!(
+            bytesrepr::deserialize(&bytes),
+            Ok(Value::Contract(contract))
+        );
+
+        let upgradable = Contract::upgradable(vec![1, 2], BTreeMap::new(), 1, [3u8; 32]);
+        let bytes = Value::Contract(upgradable.clone()).to_bytes().unwrap();
+        assert_eq!(
+            bytesrepr::deserialize(&bytes),
+            Ok(Value::Contract(upgradable))
+        );
+    }
+
+    #[test]
+    fn new_variants_encodings() {
+        assert_eq!(Value::Bool(true).to_bytes().unwrap(), vec![12, 1]);
+        assert_eq!(
+            Value::UInt64(1).to_bytes().unwrap(),
+            vec![13, 1, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(Value::Unit.to_bytes().unwrap(), vec![14]);
+        assert_eq!(
+            Value::Option(None).to_bytes().unwrap(),
+            vec![15, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            Value::Option(Some(Box::new(Value::Unit)))
+                .to_bytes()
+                .unwrap(),
+            vec![15, 1, 0, 0, 0, 14]
+        );
+        assert_eq!(
+            Value::Tuple(vec![Value::Unit, Value::Bool(false)])
+                .to_bytes()
+                .unwrap(),
+            vec![16, 2, 0, 0, 0, 14, 12, 0]
+        );
+    }
+
+    #[test]
+    fn tuple_conversions() {
+        let key = Key::URef([1u8; 32], AccessRights::READ);
+        let value: Value = (Value::UInt64(1), Value::Key(key)).into();
+        assert_eq!(
+            <(Value, Value)>::try_from(value.clone()),
+            Ok((Value::UInt64(1), Value::Key(key)))
+        );
+        assert!(<(Value, Value, Value)>::try_from(value).is_err());
+    }
+
+    #[test]
+    fn map_encoding_is_canonical() {
+        let mut map = BTreeMap::new();
+        map.insert(Value::Int32(2), Value::Unit);
+        map.insert(Value::Int32(1), Value::Bool(true));
+        let bytes = Value::Map(map.clone()).to_bytes().unwrap();
+        assert_eq!(
+            bytes,
+            vec![19, 2, 0, 0, 0, 0, 1, 0, 0, 0, 12, 1, 0, 2, 0, 0, 0, 14]
+        );
+        assert_eq!(bytesrepr::deserialize(&bytes), Ok(Value::Map(map)));
+
+        // Same entries, keys out of order.
+        let unordered = vec![19, 2, 0, 0, 0, 0, 2, 0, 0, 0, 14, 0, 1, 0, 0, 0, 12, 1];
+        assert_eq!(
+            bytesrepr::deserialize::<Value>(&unordered),
+            Err(Error::FormattingError)
+        );
+        // Duplicated key.
+        let duplicated = vec![19, 2, 0, 0, 0, 0, 1, 0, 0, 0, 14, 0, 1, 0, 0, 0, 14];
+        assert_eq!(
+            bytesrepr::deserialize::<Value>(&duplicated),
+            Err(Error::FormattingError)
+        );
+    }
+
+    #[test]
+    fn nesting_depth_is_limited() {
+        let deepest = nested_list(MAX_VALUE_DEPTH);
+        let bytes = deepest.to_bytes().unwrap();
+        assert_eq!(bytesrepr::deserialize(&bytes), Ok(deepest));
+
+        let too_deep = nested_list(MAX_VALUE_DEPTH + 1);
+        assert_eq!(too_deep.to_bytes(), Err(Error::LimitExceeded));
+        // A list around the deepest allowed value, encoded by hand.
+        let mut too_deep_bytes = vec![18, 1, 0, 0, 0];
+        too_deep_bytes.extend(bytes);
+        assert_eq!(
+            bytesrepr::deserialize::<Value>(&too_deep_bytes),
+            Err(Error::LimitExceeded)
+        );
+    }
+
+    #[test]
+    fn collection_length_is_limited() {
+        let bytes = vec![18, 0xff, 0xff, 0xff, 0xff];
+        assert_eq!(
+            bytesrepr::deserialize::<Value>(&bytes),
+            Err(Error::LimitExceeded)
+        );
+        // A length within limits, with fewer elements than announced.
+        let mut bytes = vec![18];
+        bytes.extend((MAX_COLLECTION_LEN as u32).to_bytes().unwrap());
+        bytes.push(14);
+        assert_eq!(
+            bytesrepr::deserialize::<Value>(&bytes),
+            Err(Error::EarlyEndOfStream)
+        );
+    }
+}
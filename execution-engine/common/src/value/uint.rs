@@ -1,6 +1,6 @@
 use crate::bytesrepr::{self, Error, FromBytes, ToBytes};
 use alloc::vec::Vec;
-use num::traits::{WrappingAdd, WrappingSub};
+use num::traits::{CheckedAdd, CheckedSub, ToPrimitive, WrappingAdd, WrappingSub};
 use num::{Bounded, Num, One, Unsigned, Zero};
 
 // Clippy generates a ton of warnings/errors for the code the macro generates.
@@ -116,6 +116,38 @@ macro_rules! ser_and_num_impls {
                 self.overflowing_sub(*other).0
             }
         }
+
+        impl CheckedAdd for $type {
+            fn checked_add(&self, other: &$type) -> Option<$type> {
+                match self.overflowing_add(*other) {
+                    (_, true) => None,
+                    (value, false) => Some(value),
+                }
+            }
+        }
+
+        impl CheckedSub for $type {
+            fn checked_sub(&self, other: &$type) -> Option<$type> {
+                match self.overflowing_sub(*other) {
+                    (_, true) => None,
+                    (value, false) => Some(value),
+                }
+            }
+        }
+
+        impl ToPrimitive for $type {
+            fn to_i64(&self) -> Option<i64> {
+                self.to_u64().and_then(|value| value.to_i64())
+            }
+
+            fn to_u64(&self) -> Option<u64> {
+                if *self > $type::from(u64::max_value()) {
+                    None
+                } else {
+                    Some(self.low_u64())
+                }
+            }
+        }
     };
 }
 
@@ -155,3 +187,23 @@ fn wrapping_test_u128() {
     let value = min.wrapping_sub(&1.into());
     assert_eq!(value, U128::max_value());
 }
+
+#[test]
+fn checked_test_u512() {
+    let max = U512::max_value();
+    assert_eq!(CheckedAdd::checked_add(&max, &1.into()), None);
+    assert_eq!(CheckedAdd::checked_add(&max, &0.into()), Some(max));
+
+    let min = U512::min_value();
+    assert_eq!(CheckedSub::checked_sub(&min, &1.into()), None);
+    assert_eq!(CheckedSub::checked_sub(&U512::one(), &1.into()), Some(min));
+}
+
+#[test]
+fn to_primitive_test_u512() {
+    let max_u64 = U512::from(u64::max_value());
+    assert_eq!(max_u64.to_u64(), Some(u64::max_value()));
+    assert_eq!(max_u64.to_i64(), None);
+    assert_eq!((max_u64 + U512::one()).to_u64(), None);
+    assert_eq!(U512::from(7).to_i32(), Some(7));
+}
@@ -48,7 +48,13 @@ impl ByteSize for Value {
     fn byte_size(&self) -> usize {
         std::mem::size_of::<Self>()
             + match self {
-                Value::Int32(_) | Value::UInt128(_) | Value::UInt256(_) | Value::UInt512(_) => 0,
+                Value::Int32(_)
+                | Value::UInt128(_)
+                | Value::UInt256(_)
+                | Value::UInt512(_)
+                | Value::Bool(_)
+                | Value::UInt64(_)
+                | Value::Unit => 0,
                 Value::ByteArray(vec) => std::mem::size_of::<Vec<u8>>() + vec.capacity(),
                 Value::ListInt32(list) => {
                     std::mem::size_of::<Vec<i32>>() + list.capacity() * I32_SIZE
@@ -62,6 +68,18 @@ impl ByteSize for Value {
                 Value::NamedKey(name, _key) => name.heap_size(),
                 Value::Account(account) => account.heap_size(),
                 Value::Contract(contract) => contract.heap_size(),
+                Value::Option(option) => option.as_ref().map_or(0, |value| value.byte_size()),
+                Value::Tuple(values) | Value::List(values) => {
+                    std::mem::size_of::<Vec<Value>>()
+                        + values.iter().fold(0, |sum, el| sum + el.byte_size())
+                }
+                Value::Map(map) => {
+                    std::mem::size_of::<BTreeMap<Value, Value>>()
+                        + map
+                            .iter()
+                            .fold(0, |sum, (k, v)| sum + k.byte_size() + v.byte_size())
+                }
+                Value::ListKey(keys) => std::mem::size_of::<Vec<Key>>() + keys.byte_size(),
             }
     }
 }
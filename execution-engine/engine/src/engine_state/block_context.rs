@@ -0,0 +1,20 @@
+/// Information about the block in which deploys are executed,
+/// which is made available to contracts through host functions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlockContext {
+    /// Block time in milliseconds since the Unix epoch.
+    pub time: u64,
+    pub height: u64,
+    /// Public key of the validator which proposed the block.
+    pub proposer: [u8; 32],
+}
+
+impl BlockContext {
+    pub fn new(time: u64, height: u64, proposer: [u8; 32]) -> BlockContext {
+        BlockContext {
+            time,
+            height,
+            proposer,
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use super::execution_effect::ExecutionEffect;
+use super::execution_result::ExecutionResult;
+use trace::Trace;
+
+/// A deploy of a block, executed with `EngineState::run_deploys` or `run_deploys_chained`.
+#[derive(Clone, Copy, Debug)]
+pub struct DeployItem<'a> {
+    pub module_bytes: &'a [u8],
+    pub args: &'a [u8],
+    pub address: [u8; 32],
+    pub timestamp: u64,
+    pub nonce: u64,
+    pub gas_limit: u64,
+    /// Whether the host calls made by the deploy are recorded.
+    pub trace: bool,
+}
+
+pub struct DeployOutcome {
+    pub result: ExecutionResult,
+    pub trace: Option<Trace>,
+    /// Indices of the preceding deploys of the block whose effects conflict with
+    /// the effect of this deploy.
+    pub conflicts: Vec<usize>,
+}
+
+pub struct ChainedOutcomes {
+    pub outcomes: Vec<DeployOutcome>,
+    /// Effects of all the deploys combined in order, to be committed on top of the prestate.
+    pub effect: ExecutionEffect,
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use common::key::Key;
+use common::value::Value;
+use shared::transform::{self, Arithmetic, Transform};
+use storage::global_state::StateReader;
+
+use execution;
+
+/// Reads the state of `base` with `transforms` applied on top of it, so that deploys can
+/// be executed against the effects of the preceding ones without committing them.
+pub struct EffectsReader<'a, R> {
+    base: R,
+    transforms: &'a HashMap<Key, Transform>,
+    arithmetic: Arithmetic,
+}
+
+impl<'a, R> EffectsReader<'a, R> {
+    pub fn new(
+        base: R,
+        transforms: &'a HashMap<Key, Transform>,
+        arithmetic: Arithmetic,
+    ) -> EffectsReader<'a, R> {
+        EffectsReader {
+            base,
+            transforms,
+            arithmetic,
+        }
+    }
+}
+
+impl<'a, R> StateReader<Key, Value> for EffectsReader<'a, R>
+where
+    R: StateReader<Key, Value>,
+    R::Error: Into<execution::Error>,
+{
+    type Error = execution::Error;
+
+    fn read(&self, key: &Key) -> Result<Option<Value>, Self::Error> {
+        let value = self.base.read(key).map_err(Into::into)?;
+        match (self.transforms.get(key), value) {
+            (None, value) | (Some(Transform::Identity), value) => Ok(value),
+            (Some(Transform::Write(value)), _) => Ok(Some(value.clone())),
+            (Some(transform), Some(value)) => {
+                match transform.clone().apply_with(value, self.arithmetic) {
+                    Ok(value) => Ok(Some(value)),
+                    Err(transform::Error::TypeMismatch(type_mismatch)) => {
+                        Err(execution::Error::TypeMismatch(type_mismatch))
+                    }
+                    Err(transform::Error::Overflow) => Err(execution::Error::Overflow),
+                }
+            }
+            (Some(_), None) => Err(execution::Error::KeyNotFound(*key)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use common::key::Key;
+    use common::value::Value;
+    use shared::transform::{Arithmetic, Transform};
+    use storage::global_state::StateReader;
+
+    use super::EffectsReader;
+    use execution;
+
+    struct MapReader(HashMap<Key, Value>);
+
+    impl StateReader<Key, Value> for MapReader {
+        type Error = execution::Error;
+
+        fn read(&self, key: &Key) -> Result<Option<Value>, Self::Error> {
+            Ok(self.0.get(key).cloned())
+        }
+    }
+
+    #[test]
+    fn transforms_are_applied_to_base_values() {
+        let counter = Key::Hash([1u8; 32]);
+        let written = Key::Hash([2u8; 32]);
+        let untouched = Key::Hash([3u8; 32]);
+        let mut base = HashMap::new();
+        base.insert(counter, Value::Int32(1));
+        base.insert(untouched, Value::Int32(7));
+        let mut transforms = HashMap::new();
+        transforms.insert(counter, Transform::AddInt32(2));
+        transforms.insert(written, Transform::Write(Value::Int32(5)));
+        let reader = EffectsReader::new(MapReader(base), &transforms, Arithmetic::Wrapping);
+        assert_eq!(reader.read(&counter).unwrap(), Some(Value::Int32(3)));
+        assert_eq!(reader.read(&written).unwrap(), Some(Value::Int32(5)));
+        assert_eq!(reader.read(&untouched).unwrap(), Some(Value::Int32(7)));
+        assert_eq!(reader.read(&Key::Hash([4u8; 32])).unwrap(), None);
+    }
+}
@@ -0,0 +1,14 @@
+/// An event emitted by a contract during execution of a deploy.
+/// Events are not stored in the global state; they are returned alongside
+/// the effects of a successful deploy so that off-chain indexers can consume them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    pub topic: String,
+    pub payload: Vec<u8>,
+}
+
+impl Event {
+    pub fn new(topic: String, payload: Vec<u8>) -> Event {
+        Event { topic, payload }
+    }
+}
@@ -1,9 +1,134 @@
 use std::collections::HashMap;
 
 use common::key::Key;
-use shared::transform::Transform;
+use shared::transform::{Arithmetic, Transform};
 
 use super::op::Op;
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct ExecutionEffect(pub HashMap<Key, Op>, pub HashMap<Key, Transform>);
+
+/// Both effects access `key`, with operations which don't commute.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Conflict {
+    pub key: Key,
+    pub op: Op,
+    pub other_op: Op,
+}
+
+impl ExecutionEffect {
+    /// Whether this effect and `other`, computed against the same prestate, access some key
+    /// with operations which don't commute, so they can't both be applied to that prestate.
+    pub fn conflicts_with(&self, other: &ExecutionEffect) -> bool {
+        self.0.iter().any(|(key, op)| {
+            other
+                .0
+                .get(key)
+                .map_or(false, |other_op| !op.commutes_with(other_op))
+        })
+    }
+
+    /// Returns the keys on which this effect conflicts with `other`, ordered by key.
+    pub fn conflicts(&self, other: &ExecutionEffect) -> Vec<Conflict> {
+        let mut conflicts: Vec<Conflict> = self
+            .0
+            .iter()
+            .filter_map(|(key, op)| match other.0.get(key) {
+                Some(other_op) if !op.commutes_with(other_op) => Some(Conflict {
+                    key: *key,
+                    op: op.clone(),
+                    other_op: other_op.clone(),
+                }),
+                _ => None,
+            })
+            .collect();
+        conflicts.sort_by_key(|conflict| conflict.key);
+        conflicts
+    }
+
+    /// Combines `other` into this effect, with numeric additions following `arithmetic`.
+    /// The effects should not conflict, so that the result doesn't depend on the order in
+    /// which they are combined.
+    pub fn merge(&mut self, other: ExecutionEffect, arithmetic: Arithmetic) {
+        let ExecutionEffect(ops, transforms) = other;
+        for (key, op) in ops {
+            let merged = match self.0.remove(&key) {
+                Some(current) => current + op,
+                None => op,
+            };
+            self.0.insert(key, merged);
+        }
+        for (key, transform) in transforms {
+            let merged = match self.1.remove(&key) {
+                Some(current) => current.add_with(transform, arithmetic),
+                None => transform,
+            };
+            self.1.insert(key, merged);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use common::key::Key;
+    use common::value::Value;
+    use shared::transform::{Arithmetic, Transform};
+
+    use super::{Conflict, ExecutionEffect};
+    use engine_state::op::Op;
+
+    fn effect(ops: &[(Key, Op)]) -> ExecutionEffect {
+        ExecutionEffect(ops.iter().cloned().collect(), HashMap::new())
+    }
+
+    #[test]
+    fn conflicts_name_keys_and_ops() {
+        let key = Key::Hash([1u8; 32]);
+        let other_key = Key::Hash([2u8; 32]);
+        let first = effect(&[(key, Op::Read), (other_key, Op::Add)]);
+        let second = effect(&[(key, Op::Write), (other_key, Op::Add)]);
+        assert_eq!(
+            first.conflicts(&second),
+            vec![Conflict {
+                key,
+                op: Op::Read,
+                other_op: Op::Write,
+            }]
+        );
+    }
+
+    #[test]
+    fn merged_adds_are_combined() {
+        let key = Key::Hash([1u8; 32]);
+        let other_key = Key::Hash([2u8; 32]);
+        let mut first = effect(&[(key, Op::Add)]);
+        first.1.insert(key, Transform::AddInt32(1));
+        let mut second = effect(&[(key, Op::Add), (other_key, Op::Write)]);
+        second.1.insert(key, Transform::AddInt32(2));
+        second
+            .1
+            .insert(other_key, Transform::Write(Value::Int32(5)));
+        first.merge(second, Arithmetic::Wrapping);
+        assert_eq!(first.0[&key], Op::Add);
+        assert_eq!(first.0[&other_key], Op::Write);
+        assert_eq!(first.1[&key], Transform::AddInt32(3));
+        assert_eq!(first.1[&other_key], Transform::Write(Value::Int32(5)));
+    }
+
+    #[test]
+    fn effects_conflict_on_non_commuting_ops() {
+        let key = Key::Hash([1u8; 32]);
+        let other_key = Key::Hash([2u8; 32]);
+        let read = effect(&[(key, Op::Read)]);
+        let write = effect(&[(key, Op::Write)]);
+        let add = effect(&[(key, Op::Add)]);
+        assert!(!read.conflicts_with(&read));
+        assert!(!add.conflicts_with(&add));
+        assert!(read.conflicts_with(&add));
+        assert!(write.conflicts_with(&read));
+        assert!(add.conflicts_with(&write));
+        assert!(!write.conflicts_with(&effect(&[(other_key, Op::Write)])));
+    }
+}
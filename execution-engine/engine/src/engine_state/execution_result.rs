@@ -1,23 +1,92 @@
 use super::error::Error;
+use super::event::Event;
 use super::execution_effect::ExecutionEffect;
 
-pub struct ExecutionResult {
-    pub result: Result<ExecutionEffect, Error>,
-    pub cost: u64,
+#[derive(Debug)]
+pub enum ExecutionResult {
+    /// An error condition that happened during execution. The effect carries
+    /// whatever has to be committed even though the deploy failed (i.e. the
+    /// account nonce increment).
+    Failure {
+        error: Error,
+        effect: ExecutionEffect,
+        cost: u64,
+    },
+    /// Execution was finished successfully. The `cost` includes the `storage_cost`
+    /// of the bytes which the effect adds to the global state.
+    Success {
+        effect: ExecutionEffect,
+        events: Vec<Event>,
+        cost: u64,
+        storage_cost: u64,
+    },
 }
 
 impl ExecutionResult {
-    pub fn failure(error: Error, cost: u64) -> ExecutionResult {
-        ExecutionResult {
-            result: Err(error),
+    pub fn failure(error: Error, effect: ExecutionEffect, cost: u64) -> ExecutionResult {
+        ExecutionResult::Failure {
+            error,
+            effect,
             cost,
         }
     }
 
-    pub fn success(effect: ExecutionEffect, cost: u64) -> ExecutionResult {
-        ExecutionResult {
-            result: Ok(effect),
-            cost,
+    /// A failure which happened before the deploy was executed. Such deploys are
+    /// not charged and leave no trace in the global state.
+    pub fn precondition_failure(error: Error) -> ExecutionResult {
+        ExecutionResult::Failure {
+            error,
+            effect: Default::default(),
+            cost: 0,
+        }
+    }
+
+    pub fn success(
+        effect: ExecutionEffect,
+        events: Vec<Event>,
+        computation_cost: u64,
+        storage_cost: u64,
+    ) -> ExecutionResult {
+        ExecutionResult::Success {
+            effect,
+            events,
+            cost: computation_cost.saturating_add(storage_cost),
+            storage_cost,
         }
     }
+
+    /// Effect of the deploy which has to be committed, whether it succeeded or not.
+    pub fn effect(&self) -> &ExecutionEffect {
+        match self {
+            ExecutionResult::Failure { effect, .. } => effect,
+            ExecutionResult::Success { effect, .. } => effect,
+        }
+    }
+
+    pub fn cost(&self) -> u64 {
+        match self {
+            ExecutionResult::Failure { cost, .. } => *cost,
+            ExecutionResult::Success { cost, .. } => *cost,
+        }
+    }
+
+    /// Part of the cost charged for the bytes added to the global state.
+    pub fn storage_cost(&self) -> u64 {
+        match self {
+            ExecutionResult::Failure { .. } => 0,
+            ExecutionResult::Success { storage_cost, .. } => *storage_cost,
+        }
+    }
+
+    /// Part of the cost charged for the execution of the deploy.
+    pub fn computation_cost(&self) -> u64 {
+        self.cost() - self.storage_cost()
+    }
+}
+
+/// Result of a view call: the bytes the contract passed to `ret` and the gas it used.
+/// View calls never have effects.
+pub struct ViewResult {
+    pub value: Result<Vec<u8>, Error>,
+    pub cost: u64,
 }
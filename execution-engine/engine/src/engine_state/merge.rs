@@ -0,0 +1,108 @@
+use shared::transform::Arithmetic;
+
+use super::execution_effect::{Conflict, ExecutionEffect};
+
+/// The effects at indices `first` and `second` conflict on `keys`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EffectConflict {
+    pub first: usize,
+    pub second: usize,
+    pub keys: Vec<Conflict>,
+}
+
+#[derive(Debug, Default)]
+pub struct MergeResult {
+    /// All the pairs of conflicting effects.
+    pub conflicts: Vec<EffectConflict>,
+    /// Indices of the merged effects: every effect which doesn't conflict with
+    /// the preceding merged ones.
+    pub merged: Vec<usize>,
+    pub effect: ExecutionEffect,
+}
+
+/// Finds the conflicts between `effects`, all computed against the same prestate, and merges
+/// a subset of them which can be applied together, with numeric additions following
+/// `arithmetic`. Effects are considered in order, so the ones which come first are preferred.
+pub fn merge_effects(effects: &[ExecutionEffect], arithmetic: Arithmetic) -> MergeResult {
+    let mut result = MergeResult::default();
+    for (second, effect) in effects.iter().enumerate() {
+        for (first, preceding) in effects[..second].iter().enumerate() {
+            let keys = preceding.conflicts(effect);
+            if !keys.is_empty() {
+                result.conflicts.push(EffectConflict {
+                    first,
+                    second,
+                    keys,
+                });
+            }
+        }
+        let can_merge = result
+            .merged
+            .iter()
+            .all(|merged| !effects[*merged].conflicts_with(effect));
+        if can_merge {
+            result.merged.push(second);
+            result.effect.merge(effect.clone(), arithmetic);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use common::key::Key;
+    use common::value::U512;
+    use shared::transform::{Arithmetic, Transform};
+
+    use super::merge_effects;
+    use engine_state::execution_effect::{Conflict, ExecutionEffect};
+    use engine_state::op::Op;
+
+    fn add_effect(key: Key, op: Op, amount: i32) -> ExecutionEffect {
+        let mut ops = HashMap::new();
+        ops.insert(key, op);
+        let mut transforms = HashMap::new();
+        transforms.insert(key, Transform::AddInt32(amount));
+        ExecutionEffect(ops, transforms)
+    }
+
+    #[test]
+    fn first_of_conflicting_effects_is_merged() {
+        let key = Key::Hash([1u8; 32]);
+        let effects = vec![
+            add_effect(key, Op::Add, 1),
+            add_effect(key, Op::Write, 2),
+            add_effect(key, Op::Add, 3),
+        ];
+        let result = merge_effects(&effects, Arithmetic::Wrapping);
+        assert_eq!(result.merged, vec![0, 2]);
+        assert_eq!(result.effect.1[&key], Transform::AddInt32(4));
+        let pairs: Vec<(usize, usize)> = result
+            .conflicts
+            .iter()
+            .map(|conflict| (conflict.first, conflict.second))
+            .collect();
+        assert_eq!(pairs, vec![(0, 1), (1, 2)]);
+        assert_eq!(
+            result.conflicts[0].keys,
+            vec![Conflict {
+                key,
+                op: Op::Add,
+                other_op: Op::Write,
+            }]
+        );
+    }
+
+    #[test]
+    fn checked_merge_sums_up_additions() {
+        let key = Key::Hash([1u8; 32]);
+        let mut first = add_effect(key, Op::Add, 0);
+        first.1.insert(key, Transform::AddUInt512(U512::from(3)));
+        let effects = vec![first, add_effect(key, Op::Add, -5)];
+        let result = merge_effects(&effects, Arithmetic::Checked);
+        assert_eq!(result.merged, vec![0, 1]);
+        assert_eq!(result.effect.1[&key], Transform::AddInt32(-2));
+    }
+}
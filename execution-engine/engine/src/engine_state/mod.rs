@@ -1,29 +1,68 @@
+pub mod block_context;
+pub mod deploy;
+pub mod effects_reader;
 pub mod error;
+pub mod event;
 pub mod execution_effect;
 pub mod execution_result;
+pub mod merge;
 pub mod op;
 
+#[cfg(test)]
+mod tests;
+
 use std::cell::RefCell;
+use std::cmp;
 use std::collections::HashMap;
+use std::panic;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
+use crossbeam_utils::thread;
+use parity_wasm::elements::Module;
 use parking_lot::Mutex;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaChaRng;
 
+use common::bytesrepr::ToBytes;
+use common::contract_api::Phase;
 use common::key::Key;
-use shared::newtypes::Blake2bHash;
-use shared::transform::Transform;
-use storage::global_state::{CommitResult, History};
+use common::value::account::{Account, PurseId, PURSE_ID_SIZE};
+use common::value::{Value, U512};
+use shared::newtypes::{Blake2bHash, Validated};
+use shared::transform::{Arithmetic, Transform};
+use storage::global_state::{CommitResult, History, StateReader};
 use wasm_prep::Preprocessor;
 
+use self::block_context::BlockContext;
+use self::deploy::{ChainedOutcomes, DeployItem, DeployOutcome};
+use self::effects_reader::EffectsReader;
 use self::error::{Error, RootNotFound};
-use self::execution_result::ExecutionResult;
+use self::execution_effect::ExecutionEffect;
+use self::execution_result::{ExecutionResult, ViewResult};
+use self::op::Op;
 use execution::{self, Executor};
+use host_costs::HostCosts;
+use module_cache::{ModuleCache, ModuleKind};
+use profile::GasProfile;
+use resolvers::error::ResolverError;
+use trace::Trace;
 use tracking_copy::TrackingCopy;
+use transfer;
+
+/// Gas limit of the deploys executed by `EngineState::estimate_deploy`: far above the limit any
+/// deploy is given, while still stopping the ones which never terminate.
+pub const ESTIMATE_GAS_LIMIT: u64 = 1_000_000_000_000;
+
+/// Gas limit of the contracts called by `EngineState::run_view`.
+pub const VIEW_GAS_LIMIT: u64 = 10_000_000;
 
 pub struct EngineState<H> {
     // Tracks the "state" of the blockchain (or is an interface to it).
     // I think it should be constrained with a lifetime parameter.
     state: Mutex<H>,
+    // Modules of the deploys and stored contracts, shared by all the deploys.
+    module_cache: ModuleCache,
 }
 
 impl<H> EngineState<H>
@@ -34,6 +73,7 @@ where
     pub fn new(state: H) -> EngineState<H> {
         EngineState {
             state: Mutex::new(state),
+            module_cache: ModuleCache::default(),
         }
     }
 
@@ -50,7 +90,7 @@ where
     // TODO run_deploy should perform preprocessing and validation of the deploy.
     // It should validate the signatures, ocaps etc.
     #[allow(clippy::too_many_arguments)]
-    pub fn run_deploy<A, P: Preprocessor<A>, E: Executor<A>>(
+    pub fn run_deploy<P: Preprocessor<Module>, E: Executor<Module>>(
         &self,
         module_bytes: &[u8],
         args: &[u8],
@@ -60,41 +100,641 @@ where
         prestate_hash: Blake2bHash,
         gas_limit: u64,
         protocol_version: u64,
+        block_context: BlockContext,
         executor: &E,
         preprocessor: &P,
+        trace: Option<Rc<RefCell<Trace>>>,
+        profile: Option<Rc<RefCell<GasProfile>>>,
     ) -> Result<ExecutionResult, RootNotFound> {
-        let module = match preprocessor.preprocess(module_bytes) {
-            Err(error) => return Ok(ExecutionResult::failure(error.into(), 0)),
-            Ok(module) => module,
+        let tracking_copy = match self.tracking_copy(prestate_hash) {
+            Err(error) => return Ok(ExecutionResult::precondition_failure(error)),
+            Ok(None) => return Err(RootNotFound(prestate_hash)),
+            Ok(Some(tracking_copy)) => tracking_copy,
         };
-        let checkout_result = match self.tracking_copy(prestate_hash) {
-            Err(error) => return Ok(ExecutionResult::failure(error, 0)),
-            Ok(checkout_result) => checkout_result,
+        Ok(self.execute_deploy(
+            tracking_copy,
+            module_bytes,
+            args,
+            address,
+            timestamp,
+            nonce,
+            gas_limit,
+            protocol_version,
+            block_context,
+            executor,
+            preprocessor,
+            trace,
+            profile,
+        ))
+    }
+
+    /// Estimates the gas needed by a deploy with the given payment and session code by
+    /// executing both against `prestate_hash` with `ESTIMATE_GAS_LIMIT`. Nothing is
+    /// committed, and as the effect is computed under a gas limit the deploy won't actually
+    /// have, it is only meant to be inspected.
+    #[allow(clippy::too_many_arguments)]
+    pub fn estimate_deploy<P: Preprocessor<Module>, E: Executor<Module>>(
+        &self,
+        payment: (&[u8], &[u8]),
+        session: (&[u8], &[u8]),
+        address: [u8; 32],
+        timestamp: u64,
+        nonce: u64,
+        prestate_hash: Blake2bHash,
+        protocol_version: u64,
+        block_context: BlockContext,
+        executor: &E,
+        preprocessor: &P,
+    ) -> Result<ExecutionResult, RootNotFound> {
+        let tracking_copy = match self.tracking_copy(prestate_hash) {
+            Err(error) => return Ok(ExecutionResult::precondition_failure(error)),
+            Ok(None) => return Err(RootNotFound(prestate_hash)),
+            Ok(Some(tracking_copy)) => tracking_copy,
         };
-        let tracking_copy = match checkout_result {
-            None => return Err(RootNotFound(prestate_hash)),
-            Some(mut tracking_copy) => Rc::new(RefCell::new(tracking_copy)),
+        let (payment_bytes, payment_args) = payment;
+        let (session_bytes, session_args) = session;
+        let mut phases = Vec::with_capacity(2);
+        // Deploys without payment code only run their session code.
+        if !payment_bytes.is_empty() {
+            phases.push((Phase::Payment, payment_bytes, payment_args));
+        }
+        phases.push((Phase::Session, session_bytes, session_args));
+        Ok(self.execute_phases(
+            tracking_copy,
+            &phases,
+            address,
+            timestamp,
+            nonce,
+            ESTIMATE_GAS_LIMIT,
+            protocol_version,
+            block_context,
+            executor,
+            preprocessor,
+            None,
+            None,
+        ))
+    }
+
+    /// Calls the contract stored under `contract_key` with `args` at `state_hash`, on behalf
+    /// of the account under `address`, and returns the bytes it passes to `ret`. The contract
+    /// can't change the global state, and runs with `VIEW_GAS_LIMIT`.
+    pub fn run_view<E: Executor<Module>>(
+        &self,
+        contract_key: Key,
+        args: &[u8],
+        address: [u8; 32],
+        state_hash: Blake2bHash,
+        block_context: BlockContext,
+        executor: &E,
+    ) -> Result<ViewResult, RootNotFound> {
+        let tracking_copy = match self.tracking_copy(state_hash) {
+            Err(error) => {
+                return Ok(ViewResult {
+                    value: Err(error),
+                    cost: 0,
+                })
+            }
+            Ok(None) => return Err(RootNotFound(state_hash)),
+            Ok(Some(tracking_copy)) => tracking_copy,
         };
-        match executor.exec(
-            module,
+        let (value, cost) = executor.call_view(
+            contract_key,
             args,
             address,
+            VIEW_GAS_LIMIT,
+            block_context,
+            Rc::new(RefCell::new(tracking_copy)),
+            &self.module_cache,
+        );
+        Ok(ViewResult {
+            value: value.map_err(Into::into),
+            cost,
+        })
+    }
+
+    /// Executes a deploy on `tracking_copy`, which can be backed by any view of the global state.
+    #[allow(clippy::too_many_arguments)]
+    fn execute_deploy<R, P, E>(
+        &self,
+        tracking_copy: TrackingCopy<R>,
+        module_bytes: &[u8],
+        args: &[u8],
+        address: [u8; 32],
+        timestamp: u64,
+        nonce: u64,
+        gas_limit: u64,
+        protocol_version: u64,
+        block_context: BlockContext,
+        executor: &E,
+        preprocessor: &P,
+        trace: Option<Rc<RefCell<Trace>>>,
+        profile: Option<Rc<RefCell<GasProfile>>>,
+    ) -> ExecutionResult
+    where
+        R: StateReader<Key, Value>,
+        R::Error: Into<execution::Error>,
+        P: Preprocessor<Module>,
+        E: Executor<Module>,
+    {
+        // Payment code isn't executed yet, so deploys only have a session phase.
+        self.execute_phases(
+            tracking_copy,
+            &[(Phase::Session, module_bytes, args)],
+            address,
             timestamp,
             nonce,
             gas_limit,
             protocol_version,
+            block_context,
+            executor,
+            preprocessor,
+            trace,
+            profile,
+        )
+    }
+
+    /// Executes the `(phase, module_bytes, args)` of `phases` one after another on
+    /// `tracking_copy`, sharing `gas_limit` between them. The deploy fails as soon as
+    /// one of the phases does.
+    #[allow(clippy::too_many_arguments)]
+    fn execute_phases<R, P, E>(
+        &self,
+        mut tracking_copy: TrackingCopy<R>,
+        phases: &[(Phase, &[u8], &[u8])],
+        address: [u8; 32],
+        timestamp: u64,
+        nonce: u64,
+        gas_limit: u64,
+        protocol_version: u64,
+        block_context: BlockContext,
+        executor: &E,
+        preprocessor: &P,
+        trace: Option<Rc<RefCell<Trace>>>,
+        profile: Option<Rc<RefCell<GasProfile>>>,
+    ) -> ExecutionResult
+    where
+        R: StateReader<Key, Value>,
+        R::Error: Into<execution::Error>,
+        P: Preprocessor<Module>,
+        E: Executor<Module>,
+    {
+        let host_costs = match HostCosts::from_version(protocol_version) {
+            None => {
+                let error = ResolverError::UnknownProtocolVersion(protocol_version);
+                return ExecutionResult::precondition_failure(execution::Error::from(error).into());
+            }
+            Some(host_costs) => host_costs,
+        };
+        tracking_copy.set_arithmetic(Arithmetic::from_version(protocol_version));
+        let mut modules = Vec::with_capacity(phases.len());
+        for (_, module_bytes, _) in phases {
+            // Modules instrumented for profiling aren't cached, as the cache key doesn't tell
+            // them apart from the regular ones.
+            let preprocessed = if profile.is_some() {
+                preprocessor.preprocess(module_bytes)
+            } else {
+                self.module_cache.get_or_insert_with(
+                    ModuleKind::Deploy,
+                    module_bytes,
+                    protocol_version,
+                    || preprocessor.preprocess(module_bytes),
+                )
+            };
+            match preprocessed {
+                Err(error) => return ExecutionResult::precondition_failure(error.into()),
+                Ok(module) => modules.push(module),
+            }
+        }
+        let account = match check_account(&mut tracking_copy, address, nonce) {
+            Err(error) => return ExecutionResult::precondition_failure(error),
+            Ok(account) => account,
+        };
+        let tracking_copy = Rc::new(RefCell::new(tracking_copy));
+        let account_key = Key::Account(address);
+        // From protocol version 2 on, every phase gets its own rng, so that the urefs created
+        // by the session code don't collide with the ones created by the payment code.
+        // Version 1 starts every phase with the same rng.
+        let mut deploy_rng =
+            execution::create_deploy_rng(&address, timestamp, nonce, protocol_version);
+        let mut cost = 0u64;
+        let mut events = Vec::new();
+        for ((phase, _, args), module) in phases.iter().zip(modules) {
+            let rng = if protocol_version >= 2 {
+                match ChaChaRng::from_rng(&mut deploy_rng) {
+                    Err(error) => {
+                        return ExecutionResult::failure(
+                            execution::Error::Rng(error).into(),
+                            nonce_increment_effect(account_key, account),
+                            cost,
+                        );
+                    }
+                    Ok(rng) => rng,
+                }
+            } else {
+                deploy_rng.clone()
+            };
+            match executor.exec(
+                module,
+                args,
+                address,
+                rng,
+                gas_limit.saturating_sub(cost),
+                protocol_version,
+                block_context,
+                *phase,
+                Rc::clone(&tracking_copy),
+                &self.module_cache,
+                trace.clone(),
+                profile.clone(),
+            ) {
+                (Ok((_, phase_events)), phase_cost) => {
+                    cost = cost.saturating_add(phase_cost);
+                    events.extend(phase_events);
+                }
+                // Effects of a failed execution are discarded but the deploy is still
+                // charged, so its nonce has to be consumed.
+                (Err(error), phase_cost) => {
+                    return ExecutionResult::failure(
+                        error.into(),
+                        nonce_increment_effect(account_key, account),
+                        cost.saturating_add(phase_cost),
+                    );
+                }
+            }
+        }
+        // The account might have been modified during the execution (new known urefs),
+        // so the nonce is incremented on top of its version in the tracking copy.
+        let result = {
+            let mut tracking_copy = tracking_copy.borrow_mut();
+            increment_nonce(&mut tracking_copy, account_key).and_then(|effect| {
+                let bytes = net_bytes_written(&mut tracking_copy, &effect)?;
+                Ok((effect, bytes))
+            })
+        };
+        match result {
+            Ok((effect, bytes)) => {
+                let storage_cost = bytes.saturating_mul(host_costs.storage.into());
+                if cost.saturating_add(storage_cost) > gas_limit {
+                    // Nothing is stored, so only the execution is charged.
+                    ExecutionResult::failure(
+                        execution::Error::GasLimit.into(),
+                        nonce_increment_effect(account_key, account),
+                        cost,
+                    )
+                } else {
+                    ExecutionResult::success(effect, events, cost, storage_cost)
+                }
+            }
+            Err(error) => {
+                ExecutionResult::failure(error, nonce_increment_effect(account_key, account), cost)
+            }
+        }
+    }
+
+    /// Executes the deploys of a block, each against `prestate_hash`, on a pool of threads.
+    ///
+    /// As every deploy runs on its own tracking copy of the prestate, the results are the
+    /// same as if the deploys were executed one after another. Deploys whose effects can't
+    /// be applied together with the effect of a preceding deploy, because they access some
+    /// key with operations which don't commute, are flagged in `DeployOutcome::conflicts`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn run_deploys<P, E>(
+        &self,
+        deploys: &[DeployItem],
+        prestate_hash: Blake2bHash,
+        protocol_version: u64,
+        block_context: BlockContext,
+        executor: &E,
+        preprocessor: &P,
+    ) -> Result<Vec<DeployOutcome>, RootNotFound>
+    where
+        H: Send,
+        P: Preprocessor<Module> + Sync,
+        E: Executor<Module> + Sync,
+    {
+        let threads = cmp::max(1, cmp::min(num_cpus::get(), deploys.len()));
+        let next_deploy = &AtomicUsize::new(0);
+        let no_effects = &HashMap::new();
+        let mut outcomes: Vec<(usize, Result<DeployOutcome, RootNotFound>)> =
+            thread::scope(|scope| {
+                let mut workers = Vec::with_capacity(threads);
+                for _ in 0..threads {
+                    workers.push(scope.spawn(move |_| {
+                        let mut outcomes = Vec::new();
+                        loop {
+                            let index = next_deploy.fetch_add(1, Ordering::SeqCst);
+                            let deploy = match deploys.get(index) {
+                                None => break,
+                                Some(deploy) => deploy,
+                            };
+                            let outcome = self.run_deploy_item(
+                                deploy,
+                                prestate_hash,
+                                no_effects,
+                                protocol_version,
+                                block_context,
+                                executor,
+                                preprocessor,
+                            );
+                            outcomes.push((index, outcome));
+                        }
+                        outcomes
+                    }));
+                }
+                workers
+                    .into_iter()
+                    .flat_map(|worker| {
+                        worker
+                            .join()
+                            .unwrap_or_else(|error| panic::resume_unwind(error))
+                    })
+                    .collect()
+            })
+            .unwrap_or_else(|error| panic::resume_unwind(error));
+        outcomes.sort_by_key(|(index, _)| *index);
+        // The first missing root is reported, as when deploys are executed one by one.
+        let mut outcomes = outcomes
+            .into_iter()
+            .map(|(_, outcome)| outcome)
+            .collect::<Result<Vec<DeployOutcome>, RootNotFound>>()?;
+        let conflicts: Vec<Vec<usize>> = outcomes
+            .iter()
+            .enumerate()
+            .map(|(i, outcome)| {
+                let effect = outcome.result.effect();
+                outcomes[..i]
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, preceding)| effect.conflicts_with(preceding.result.effect()))
+                    .map(|(j, _)| j)
+                    .collect()
+            })
+            .collect();
+        for (outcome, conflicts) in outcomes.iter_mut().zip(conflicts) {
+            outcome.conflicts = conflicts;
+        }
+        Ok(outcomes)
+    }
+
+    /// Executes the deploys of a block one after another, each against `prestate_hash`
+    /// with the effects of the preceding deploys applied, so that a deploy can use what
+    /// the preceding ones created. This includes the nonce increments of failed deploys,
+    /// which are committed as well. Nothing is committed: the effects are accumulated in
+    /// memory and returned combined, together with the effect of each deploy.
+    pub fn run_deploys_chained<P, E>(
+        &self,
+        deploys: &[DeployItem],
+        prestate_hash: Blake2bHash,
+        protocol_version: u64,
+        block_context: BlockContext,
+        executor: &E,
+        preprocessor: &P,
+    ) -> Result<ChainedOutcomes, RootNotFound>
+    where
+        P: Preprocessor<Module>,
+        E: Executor<Module>,
+    {
+        let arithmetic = Arithmetic::from_version(protocol_version);
+        let mut effect = ExecutionEffect::default();
+        let mut outcomes = Vec::with_capacity(deploys.len());
+        for deploy in deploys {
+            let outcome = self.run_deploy_item(
+                deploy,
+                prestate_hash,
+                &effect.1,
+                protocol_version,
+                block_context,
+                executor,
+                preprocessor,
+            )?;
+            effect.merge(outcome.result.effect().clone(), arithmetic);
+            outcomes.push(outcome);
+        }
+        Ok(ChainedOutcomes { outcomes, effect })
+    }
+
+    /// Executes `deploy` against `prestate_hash` with `transforms` applied.
+    #[allow(clippy::too_many_arguments)]
+    fn run_deploy_item<P: Preprocessor<Module>, E: Executor<Module>>(
+        &self,
+        deploy: &DeployItem,
+        prestate_hash: Blake2bHash,
+        transforms: &HashMap<Key, Transform>,
+        protocol_version: u64,
+        block_context: BlockContext,
+        executor: &E,
+        preprocessor: &P,
+    ) -> Result<DeployOutcome, RootNotFound> {
+        let base = match self.state.lock().checkout(prestate_hash) {
+            Err(error) => {
+                let error: execution::Error = error.into();
+                return Ok(DeployOutcome {
+                    result: ExecutionResult::precondition_failure(error.into()),
+                    trace: None,
+                    conflicts: Vec::new(),
+                });
+            }
+            Ok(None) => return Err(RootNotFound(prestate_hash)),
+            Ok(Some(base)) => base,
+        };
+        let arithmetic = Arithmetic::from_version(protocol_version);
+        let tracking_copy = TrackingCopy::new(EffectsReader::new(base, transforms, arithmetic));
+        let trace = if deploy.trace {
+            Some(Rc::new(RefCell::new(Trace::default())))
+        } else {
+            None
+        };
+        let result = self.execute_deploy(
             tracking_copy,
-        ) {
-            (Ok(ee), cost) => Ok(ExecutionResult::success(ee, cost)),
-            (Err(error), cost) => Ok(ExecutionResult::failure(error.into(), cost)),
+            deploy.module_bytes,
+            deploy.args,
+            deploy.address,
+            deploy.timestamp,
+            deploy.nonce,
+            deploy.gas_limit,
+            protocol_version,
+            block_context,
+            executor,
+            preprocessor,
+            trace.clone(),
+            None,
+        );
+        Ok(DeployOutcome {
+            result,
+            trace: trace.map(|trace| trace.replace(Trace::default())),
+            conflicts: Vec::new(),
+        })
+    }
+
+    /// Transfers `amount` from the main purse of the account under `address` to the main purse
+    /// of the account under `target`, creating the target account if it doesn't exist yet.
+    /// This is a built-in operation which doesn't execute any Wasm and therefore isn't charged,
+    /// but it is subject to the same nonce rules as deploys.
+    #[allow(clippy::too_many_arguments)]
+    pub fn run_transfer(
+        &self,
+        address: [u8; 32],
+        target: [u8; 32],
+        amount: U512,
+        timestamp: u64,
+        nonce: u64,
+        prestate_hash: Blake2bHash,
+    ) -> Result<ExecutionResult, RootNotFound> {
+        let (mut tracking_copy, account) =
+            match self.checkout_account(address, nonce, prestate_hash)? {
+                Err(error) => return Ok(ExecutionResult::precondition_failure(error)),
+                Ok(checked_out) => checked_out,
+            };
+        let account_key = Key::Account(address);
+        let new_purse_id = || {
+            let mut rng = execution::create_rng(&address, timestamp, nonce);
+            let mut id = [0u8; PURSE_ID_SIZE];
+            rng.fill_bytes(&mut id);
+            PurseId::new(id)
+        };
+        let result = transfer::transfer_to_account(
+            &mut tracking_copy,
+            &account,
+            target,
+            amount,
+            new_purse_id,
+        )
+        .map_err(Into::into)
+        .and_then(|_| increment_nonce(&mut tracking_copy, account_key));
+        match result {
+            Ok(effect) => Ok(ExecutionResult::success(effect, Vec::new(), 0, 0)),
+            Err(error) => Ok(ExecutionResult::failure(
+                error,
+                nonce_increment_effect(account_key, account),
+                0,
+            )),
         }
     }
 
+    /// Checks out the tracking copy at `prestate_hash` together with the account under
+    /// `address`, making sure that `nonce` is the next nonce of that account.
+    #[allow(clippy::type_complexity)]
+    fn checkout_account(
+        &self,
+        address: [u8; 32],
+        nonce: u64,
+        prestate_hash: Blake2bHash,
+    ) -> Result<Result<(TrackingCopy<H::Reader>, Account), Error>, RootNotFound> {
+        let mut tracking_copy = match self.tracking_copy(prestate_hash) {
+            Err(error) => return Ok(Err(error)),
+            Ok(None) => return Err(RootNotFound(prestate_hash)),
+            Ok(Some(tracking_copy)) => tracking_copy,
+        };
+        Ok(check_account(&mut tracking_copy, address, nonce)
+            .map(|account| (tracking_copy, account)))
+    }
+
+    /// Commits `effects` on top of `prestate_hash`, with the arithmetic of `protocol_version`.
     pub fn apply_effect(
         &self,
         prestate_hash: Blake2bHash,
         effects: HashMap<Key, Transform>,
+        protocol_version: u64,
     ) -> Result<CommitResult, H::Error> {
-        self.state.lock().commit(prestate_hash, effects)
+        let arithmetic = Arithmetic::from_version(protocol_version);
+        self.state.lock().commit(prestate_hash, effects, arithmetic)
+    }
+}
+
+fn get_account<R>(tracking_copy: &mut TrackingCopy<R>, account_key: Key) -> Result<Account, Error>
+where
+    R: StateReader<Key, Value>,
+    R::Error: Into<execution::Error>,
+{
+    let validated_key = Validated::new(account_key, Validated::valid)?;
+    match tracking_copy.get(&validated_key) {
+        Ok(Some(Value::Account(account))) => Ok(account),
+        Ok(_) => Err(execution::Error::KeyNotFound(account_key).into()),
+        Err(error) => Err(Error::ExecError(error.into())),
+    }
+}
+
+/// Returns the account under `address`, making sure that `nonce` is its next nonce.
+fn check_account<R>(
+    tracking_copy: &mut TrackingCopy<R>,
+    address: [u8; 32],
+    nonce: u64,
+) -> Result<Account, Error>
+where
+    R: StateReader<Key, Value>,
+    R::Error: Into<execution::Error>,
+{
+    let account = get_account(tracking_copy, Key::Account(address))?;
+    let expected_nonce = account.nonce() + 1;
+    if nonce != expected_nonce {
+        let error = execution::Error::InvalidNonce {
+            deploy_nonce: nonce,
+            expected_nonce,
+        };
+        return Err(error.into());
     }
+    Ok(account)
+}
+
+/// Writes back the account stored under `account_key` with its nonce incremented
+/// and returns the effect accumulated by `tracking_copy`.
+fn increment_nonce<R>(
+    tracking_copy: &mut TrackingCopy<R>,
+    account_key: Key,
+) -> Result<ExecutionEffect, Error>
+where
+    R: StateReader<Key, Value>,
+    R::Error: Into<execution::Error>,
+{
+    let mut account = get_account(tracking_copy, account_key)?;
+    account.increment_nonce();
+    tracking_copy.write(
+        Validated::new(account_key, Validated::valid)?,
+        Validated::new(Value::Account(account), Validated::valid)?,
+    );
+    Ok(tracking_copy.effect())
+}
+
+/// Returns the number of bytes which `effect` adds to the global state: for every key
+/// written by the deploy, the growth of its serialized value over its value before the deploy.
+fn net_bytes_written<R>(
+    tracking_copy: &mut TrackingCopy<R>,
+    effect: &ExecutionEffect,
+) -> Result<u64, Error>
+where
+    R: StateReader<Key, Value>,
+    R::Error: Into<execution::Error>,
+{
+    let serialized_size = |value: Option<Value>| -> Result<usize, Error> {
+        match value {
+            Some(value) => Ok(value.to_bytes().map_err(execution::Error::from)?.len()),
+            None => Ok(0),
+        }
+    };
+    let mut bytes = 0u64;
+    for (key, transform) in effect.1.iter() {
+        if let Transform::Identity = transform {
+            continue;
+        }
+        let validated_key = Validated::new(*key, Validated::valid)?;
+        let value = tracking_copy
+            .get(&validated_key)
+            .map_err(|error| Error::ExecError(error.into()))?;
+        let original_value = tracking_copy
+            .get_original(key)
+            .map_err(|error| Error::ExecError(error.into()))?;
+        let added = serialized_size(value)?.saturating_sub(serialized_size(original_value)?);
+        bytes = bytes.saturating_add(added as u64);
+    }
+    Ok(bytes)
+}
+
+/// Creates an effect which only writes `account` back with its nonce incremented.
+fn nonce_increment_effect(account_key: Key, mut account: Account) -> ExecutionEffect {
+    account.increment_nonce();
+    let mut ops = HashMap::new();
+    ops.insert(account_key, Op::Write);
+    let mut transforms = HashMap::new();
+    transforms.insert(account_key, Transform::Write(Value::Account(account)));
+    ExecutionEffect(ops, transforms)
 }
@@ -6,6 +6,18 @@ pub enum Op {
     NoOp,
 }
 
+impl Op {
+    /// Whether this operation and `other`, made on the same key by two deploys executed
+    /// against the same prestate, give the same result in either order.
+    pub fn commutes_with(&self, other: &Op) -> bool {
+        match (self, other) {
+            (Op::NoOp, _) | (_, Op::NoOp) => true,
+            (Op::Read, Op::Read) | (Op::Add, Op::Add) => true,
+            _ => false,
+        }
+    }
+}
+
 impl std::ops::Add for Op {
     type Output = Op;
 
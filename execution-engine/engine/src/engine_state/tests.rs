@@ -0,0 +1,899 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+use parity_wasm;
+use wabt;
+
+use common::bytesrepr::ToBytes;
+use common::contract_api::CallError;
+use common::key::Key;
+use common::value::account::{PublicKey, PurseId};
+use common::value::{Contract, Value, U512};
+use shared::init::{mocked_account, MOCKED_ACCOUNT_BALANCE};
+use shared::newtypes::{Blake2bHash, Validated};
+use shared::transform::Transform;
+use storage::global_state::in_memory::InMemoryGlobalState;
+use storage::global_state::CommitResult;
+use wasm_prep::wasm_costs::WasmCosts;
+use wasm_prep::{Preprocessor, WasmiPreprocessor};
+
+use super::block_context::BlockContext;
+use super::deploy::DeployItem;
+use super::error::Error;
+use super::execution_result::ExecutionResult;
+use super::op::Op;
+use super::{EngineState, VIEW_GAS_LIMIT};
+use execution::{self, WasmiExecutor};
+use host_costs::HostCosts;
+use profile::GasProfile;
+use trace::{Trace, MAX_TRACE_CALLS};
+
+const ACCOUNT: [u8; 32] = [1u8; 32];
+const TARGET: [u8; 32] = [2u8; 32];
+const CONTRACT: [u8; 32] = [3u8; 32];
+const GAS_LIMIT: u64 = 1_000_000_000;
+
+fn engine_state(pairs: &[(Key, Value)]) -> (EngineState<InMemoryGlobalState>, Blake2bHash) {
+    let state = InMemoryGlobalState::from_pairs(pairs).unwrap();
+    let root_hash = state.root_hash;
+    (EngineState::new(state), root_hash)
+}
+
+fn commit(
+    engine_state: &EngineState<InMemoryGlobalState>,
+    prestate_hash: Blake2bHash,
+    result: &ExecutionResult,
+) -> Blake2bHash {
+    let effects = result.effect().1.clone();
+    match engine_state
+        .apply_effect(prestate_hash, effects, 1)
+        .unwrap()
+    {
+        CommitResult::Success(post_state_hash) => post_state_hash,
+        other => panic!("Commit should succeed: {:?}", other),
+    }
+}
+
+fn read(
+    engine_state: &EngineState<InMemoryGlobalState>,
+    state_hash: Blake2bHash,
+    key: Key,
+) -> Option<Value> {
+    let mut tracking_copy = engine_state.tracking_copy(state_hash).unwrap().unwrap();
+    let key = Validated::new(key, Validated::valid).unwrap();
+    tracking_copy.get(&key).unwrap()
+}
+
+fn account_purse(
+    engine_state: &EngineState<InMemoryGlobalState>,
+    state_hash: Blake2bHash,
+    address: [u8; 32],
+) -> (u64, PurseId) {
+    match read(engine_state, state_hash, Key::Account(address)) {
+        Some(Value::Account(account)) => (account.nonce(), account.purse_id()),
+        other => panic!("Account should exist: {:?}", other),
+    }
+}
+
+fn preprocessor() -> WasmiPreprocessor {
+    WasmiPreprocessor::new(WasmCosts::from_version(1).unwrap())
+}
+
+/// Wasm data segment string holding `bytes`.
+fn data(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("\\{:02x}", byte)).collect()
+}
+
+/// Stored contracts aren't preprocessed when they are called, so they are stored
+/// preprocessed, as the deploys which store them do.
+fn contract(wat: &str) -> Value {
+    let module = preprocessor()
+        .preprocess(&wabt::wat2wasm(wat).unwrap())
+        .unwrap();
+    let bytes = parity_wasm::serialize(module).unwrap();
+    Value::Contract(Contract::new(bytes, BTreeMap::new(), 1))
+}
+
+/// Session code which calls the contract under `Key::Hash(CONTRACT)` without arguments.
+fn call_contract_session() -> Vec<u8> {
+    let key = Key::Hash(CONTRACT).to_bytes().unwrap();
+    let wat = format!(
+        r#"(module
+            (import "env" "call_contract"
+                (func $call_contract (param i32 i32 i32 i32 i32 i32) (result i32)))
+            (memory 1)
+            (data (i32.const 0) "{}")
+            (func (export "call")
+                ;; arguments and urefs are empty lists, which are serialized as zeroes
+                (drop (call $call_contract
+                    (i32.const 0) (i32.const {})
+                    (i32.const 100) (i32.const 4)
+                    (i32.const 100) (i32.const 4)))))"#,
+        data(&key),
+        key.len()
+    );
+    wabt::wat2wasm(wat).unwrap()
+}
+
+/// Session code which stores `Value::Int32(1)`, 5 serialized bytes, under a new uref.
+fn new_uref_session() -> Vec<u8> {
+    wabt::wat2wasm(
+        r#"(module
+            (import "env" "new_uref" (func $new_uref (param i32 i32 i32)))
+            (memory 1)
+            (data (i32.const 0) "\00\01\00\00\00")
+            (func (export "call")
+                (call $new_uref (i32.const 100) (i32.const 0) (i32.const 5))))"#,
+    )
+    .unwrap()
+}
+
+fn deploy_item(module_bytes: &[u8], address: [u8; 32], nonce: u64, trace: bool) -> DeployItem {
+    DeployItem {
+        module_bytes,
+        args: &[],
+        address,
+        timestamp: 0,
+        nonce,
+        gas_limit: GAS_LIMIT,
+        trace,
+    }
+}
+
+fn assert_same_result(result: &ExecutionResult, expected: &ExecutionResult) {
+    match (result, expected) {
+        (ExecutionResult::Success { .. }, ExecutionResult::Success { .. }) => {
+            assert_eq!(result.storage_cost(), expected.storage_cost())
+        }
+        (
+            ExecutionResult::Failure { error, .. },
+            ExecutionResult::Failure {
+                error: expected_error,
+                ..
+            },
+        ) => assert_eq!(format!("{:?}", error), format!("{:?}", expected_error)),
+        _ => panic!("Expected {:?}, got {:?}", expected, result),
+    }
+    assert_eq!(result.cost(), expected.cost());
+    assert_eq!(result.effect().0, expected.effect().0);
+    assert_eq!(result.effect().1, expected.effect().1);
+}
+
+fn run_session(
+    engine_state: &EngineState<InMemoryGlobalState>,
+    prestate_hash: Blake2bHash,
+    module_bytes: &[u8],
+    gas_limit: u64,
+) -> ExecutionResult {
+    engine_state
+        .run_deploy(
+            module_bytes,
+            &[],
+            ACCOUNT,
+            0,
+            1,
+            prestate_hash,
+            gas_limit,
+            1,
+            BlockContext::default(),
+            &WasmiExecutor,
+            &preprocessor(),
+            None,
+            None,
+        )
+        .unwrap()
+}
+
+fn balance(
+    engine_state: &EngineState<InMemoryGlobalState>,
+    state_hash: Blake2bHash,
+    purse_id: PurseId,
+) -> Option<Value> {
+    read(engine_state, state_hash, purse_id.balance_key())
+}
+
+#[test]
+fn run_transfer_creates_target_account() {
+    let (engine_state, root_hash) = engine_state(&mocked_account(ACCOUNT));
+    let amount = U512::from(10);
+    let result = engine_state
+        .run_transfer(ACCOUNT, TARGET, amount, 0, 1, root_hash)
+        .unwrap();
+    assert_matches!(result, ExecutionResult::Success { cost: 0, .. });
+    let post_state_hash = commit(&engine_state, root_hash, &result);
+
+    let (nonce, purse_id) = account_purse(&engine_state, post_state_hash, ACCOUNT);
+    assert_eq!(nonce, 1);
+    assert_eq!(
+        balance(&engine_state, post_state_hash, purse_id),
+        Some(Value::UInt512(U512::from(MOCKED_ACCOUNT_BALANCE) - amount))
+    );
+    let (nonce, purse_id) = account_purse(&engine_state, post_state_hash, TARGET);
+    assert_eq!(nonce, 0);
+    assert_eq!(
+        balance(&engine_state, post_state_hash, purse_id),
+        Some(Value::UInt512(amount))
+    );
+}
+
+#[test]
+fn run_transfer_to_existing_account() {
+    let mut pairs = mocked_account(ACCOUNT);
+    pairs.extend(mocked_account(TARGET));
+    let (engine_state, root_hash) = engine_state(&pairs);
+    let result = engine_state
+        .run_transfer(ACCOUNT, TARGET, U512::from(10), 0, 1, root_hash)
+        .unwrap();
+    let post_state_hash = commit(&engine_state, root_hash, &result);
+
+    let (_, purse_id) = account_purse(&engine_state, post_state_hash, TARGET);
+    assert_eq!(
+        balance(&engine_state, post_state_hash, purse_id),
+        Some(Value::UInt512(U512::from(MOCKED_ACCOUNT_BALANCE + 10)))
+    );
+}
+
+#[test]
+fn run_transfer_with_insufficient_funds_only_consumes_nonce() {
+    let (engine_state, root_hash) = engine_state(&mocked_account(ACCOUNT));
+    let amount = U512::from(MOCKED_ACCOUNT_BALANCE) + U512::from(1);
+    let result = engine_state
+        .run_transfer(ACCOUNT, TARGET, amount, 0, 1, root_hash)
+        .unwrap();
+    assert_matches!(
+        result,
+        ExecutionResult::Failure {
+            error: Error::ExecError(execution::Error::InsufficientFunds),
+            ..
+        }
+    );
+    let mut expected_ops = HashMap::new();
+    expected_ops.insert(Key::Account(ACCOUNT), Op::Write);
+    assert_eq!(result.effect().0, expected_ops);
+
+    let post_state_hash = commit(&engine_state, root_hash, &result);
+    let (nonce, purse_id) = account_purse(&engine_state, post_state_hash, ACCOUNT);
+    assert_eq!(nonce, 1);
+    assert_eq!(
+        balance(&engine_state, post_state_hash, purse_id),
+        Some(Value::UInt512(U512::from(MOCKED_ACCOUNT_BALANCE)))
+    );
+    assert_eq!(
+        read(&engine_state, post_state_hash, Key::Account(TARGET)),
+        None
+    );
+}
+
+#[test]
+fn run_transfer_with_invalid_nonce() {
+    let (engine_state, root_hash) = engine_state(&mocked_account(ACCOUNT));
+    let result = engine_state
+        .run_transfer(ACCOUNT, TARGET, U512::from(10), 0, 2, root_hash)
+        .unwrap();
+    assert_matches!(
+        result,
+        ExecutionResult::Failure {
+            error: Error::ExecError(execution::Error::InvalidNonce {
+                deploy_nonce: 2,
+                expected_nonce: 1,
+            }),
+            cost: 0,
+            ..
+        }
+    );
+    assert!(result.effect().1.is_empty());
+}
+
+#[test]
+fn run_transfer_with_unknown_root() {
+    let (engine_state, _) = engine_state(&mocked_account(ACCOUNT));
+    let unknown_root = Blake2bHash::new(&[0u8; 32]);
+    let result = engine_state.run_transfer(ACCOUNT, TARGET, U512::from(10), 0, 1, unknown_root);
+    assert_matches!(result, Err(_));
+}
+
+#[test]
+fn caller_is_passed_to_called_contract() {
+    // Stores the caller it sees under a new uref, as a `Value::Key` (tag 11).
+    let callee = contract(
+        r#"(module
+            (import "env" "load_caller" (func $load_caller (result i32)))
+            (import "env" "get_caller" (func $get_caller (param i32)))
+            (import "env" "new_uref" (func $new_uref (param i32 i32 i32)))
+            (memory 1)
+            (data (i32.const 0) "\0b")
+            (func (export "call")
+                (local $size i32)
+                (set_local $size (call $load_caller))
+                (call $get_caller (i32.const 1))
+                (call $new_uref
+                    (i32.const 100)
+                    (i32.const 0)
+                    (i32.add (get_local $size) (i32.const 1)))))"#,
+    );
+    let mut pairs = mocked_account(ACCOUNT);
+    pairs.push((Key::Hash(CONTRACT), callee));
+    let (engine_state, root_hash) = engine_state(&pairs);
+    let session = call_contract_session();
+    let result = run_session(&engine_state, root_hash, &session, GAS_LIMIT);
+    assert_matches!(result, ExecutionResult::Success { .. });
+
+    let caller = Transform::Write(Value::Key(Key::Account(ACCOUNT)));
+    let stored = result
+        .effect()
+        .1
+        .iter()
+        .filter(|(key, _)| match key {
+            Key::URef(..) => true,
+            _ => false,
+        })
+        .map(|(_, transform)| transform)
+        .collect::<Vec<_>>();
+    assert_eq!(stored, vec![&caller]);
+}
+
+#[test]
+fn caller_continues_after_callee_reverts() {
+    // Stores `Value::Int32(1)` under a new uref and reverts.
+    let callee = contract(
+        r#"(module
+            (import "env" "new_uref" (func $new_uref (param i32 i32 i32)))
+            (import "env" "revert" (func $revert (param i32)))
+            (memory 1)
+            (data (i32.const 0) "\00\01\00\00\00")
+            (func (export "call")
+                (call $new_uref (i32.const 100) (i32.const 0) (i32.const 5))
+                (call $revert (i32.const 2))))"#,
+    );
+    // Stores the result of the call under a new uref, as a `Value::ByteArray` (tag 1).
+    let key = Key::Hash(CONTRACT).to_bytes().unwrap();
+    let session = wabt::wat2wasm(format!(
+        r#"(module
+            (import "env" "try_call_contract"
+                (func $try_call_contract (param i32 i32 i32 i32 i32 i32) (result i32)))
+            (import "env" "get_call_result" (func $get_call_result (param i32)))
+            (import "env" "new_uref" (func $new_uref (param i32 i32 i32)))
+            (memory 1)
+            (data (i32.const 0) "{}")
+            (data (i32.const 200) "\01")
+            (func (export "call")
+                (local $size i32)
+                (set_local $size (call $try_call_contract
+                    (i32.const 0) (i32.const {})
+                    (i32.const 100) (i32.const 4)
+                    (i32.const 100) (i32.const 4)))
+                (i32.store (i32.const 201) (get_local $size))
+                (call $get_call_result (i32.const 205))
+                (call $new_uref
+                    (i32.const 300)
+                    (i32.const 200)
+                    (i32.add (get_local $size) (i32.const 5)))))"#,
+        data(&key),
+        key.len()
+    ))
+    .unwrap();
+    let mut pairs = mocked_account(ACCOUNT);
+    pairs.push((Key::Hash(CONTRACT), callee));
+    let (engine_state, root_hash) = engine_state(&pairs);
+    let result = run_session(&engine_state, root_hash, &session, GAS_LIMIT);
+    assert_matches!(result, ExecutionResult::Success { .. });
+
+    // The write of the callee is rolled back, but reading the contract isn't.
+    let effect = result.effect();
+    assert_eq!(effect.0.get(&Key::Hash(CONTRACT)), Some(&Op::Read));
+    let call_result: Result<Vec<u8>, CallError> = Err(CallError::Revert(2));
+    let stored = Transform::Write(Value::ByteArray(call_result.to_bytes().unwrap()));
+    let urefs = effect
+        .1
+        .iter()
+        .filter(|(key, _)| match key {
+            Key::URef(..) => true,
+            _ => false,
+        })
+        .map(|(_, transform)| transform)
+        .collect::<Vec<_>>();
+    assert_eq!(urefs, vec![&stored]);
+}
+
+#[test]
+fn host_calls_are_charged() {
+    let (engine_state, root_hash) = engine_state(&mocked_account(ACCOUNT));
+    // Both sessions execute the same number of Wasm instructions.
+    let with_call = wabt::wat2wasm(
+        r#"(module
+            (import "env" "get_phase" (func $get_phase (result i32)))
+            (memory 1)
+            (func (export "call") (drop (call $get_phase))))"#,
+    )
+    .unwrap();
+    let without_call = wabt::wat2wasm(
+        r#"(module
+            (memory 1)
+            (func (export "call") (drop (i32.const 0))))"#,
+    )
+    .unwrap();
+    let regular_cost = u64::from(HostCosts::from_version(1).unwrap().regular);
+
+    let result = run_session(&engine_state, root_hash, &without_call, GAS_LIMIT);
+    assert_matches!(result, ExecutionResult::Success { .. });
+    let execution_cost = result.cost() - result.storage_cost();
+    let result = run_session(&engine_state, root_hash, &with_call, GAS_LIMIT);
+    assert_matches!(result, ExecutionResult::Success { .. });
+    assert_eq!(
+        result.cost() - result.storage_cost(),
+        execution_cost + regular_cost
+    );
+
+    // Only the Wasm instructions fit in the limit.
+    let result = run_session(&engine_state, root_hash, &with_call, execution_cost);
+    assert_matches!(
+        result,
+        ExecutionResult::Failure {
+            error: Error::ExecError(execution::Error::GasLimit),
+            ..
+        }
+    );
+    assert_eq!(result.cost(), execution_cost);
+}
+
+#[test]
+fn storage_is_charged() {
+    let (engine_state, root_hash) = engine_state(&mocked_account(ACCOUNT));
+    let session = new_uref_session();
+    let storage_cost = 5 * u64::from(HostCosts::from_version(1).unwrap().storage);
+
+    let result = run_session(&engine_state, root_hash, &session, GAS_LIMIT);
+    assert_matches!(result, ExecutionResult::Success { .. });
+    assert_eq!(result.storage_cost(), storage_cost);
+    let execution_cost = result.cost() - storage_cost;
+
+    // The execution fits in the limit, but storing its effect doesn't.
+    let gas_limit = execution_cost + storage_cost - 1;
+    let result = run_session(&engine_state, root_hash, &session, gas_limit);
+    assert_matches!(
+        result,
+        ExecutionResult::Failure {
+            error: Error::ExecError(execution::Error::GasLimit),
+            ..
+        }
+    );
+    assert_eq!(result.cost(), execution_cost);
+    let mut expected_ops = HashMap::new();
+    expected_ops.insert(Key::Account(ACCOUNT), Op::Write);
+    assert_eq!(result.effect().0, expected_ops);
+}
+
+#[test]
+fn stored_contracts_and_deploys_are_cached_separately() {
+    // Stored without being preprocessed, so it can't be instantiated: its memory
+    // isn't imported.
+    let bytes = wabt::wat2wasm(
+        r#"(module
+            (memory 1)
+            (func (export "call")))"#,
+    )
+    .unwrap();
+    let mut pairs = mocked_account(ACCOUNT);
+    pairs.push((
+        Key::Hash(CONTRACT),
+        Value::Contract(Contract::new(bytes.clone(), BTreeMap::new(), 1)),
+    ));
+    let (engine_state, root_hash) = engine_state(&pairs);
+    let session = call_contract_session();
+    let result = run_session(&engine_state, root_hash, &session, GAS_LIMIT);
+    assert_matches!(result, ExecutionResult::Failure { .. });
+
+    // The same bytes are preprocessed when they are executed as a deploy, rather than
+    // taken from the module cached for the contract.
+    let result = run_session(&engine_state, root_hash, &bytes, GAS_LIMIT);
+    assert_matches!(result, ExecutionResult::Success { .. });
+}
+
+#[test]
+fn trace_is_truncated() {
+    let (engine_state, root_hash) = engine_state(&mocked_account(ACCOUNT));
+    let session = wabt::wat2wasm(format!(
+        r#"(module
+            (import "env" "get_phase" (func $get_phase (result i32)))
+            (memory 1)
+            (func (export "call")
+                (local $i i32)
+                (loop $continue
+                    (drop (call $get_phase))
+                    (set_local $i (i32.add (get_local $i) (i32.const 1)))
+                    (br_if $continue (i32.lt_u (get_local $i) (i32.const {}))))))"#,
+        MAX_TRACE_CALLS + 1
+    ))
+    .unwrap();
+    let trace = Rc::new(RefCell::new(Trace::default()));
+    let result = engine_state
+        .run_deploy(
+            &session,
+            &[],
+            ACCOUNT,
+            0,
+            1,
+            root_hash,
+            GAS_LIMIT,
+            1,
+            BlockContext::default(),
+            &WasmiExecutor,
+            &preprocessor(),
+            Some(Rc::clone(&trace)),
+            None,
+        )
+        .unwrap();
+    assert_matches!(result, ExecutionResult::Success { .. });
+
+    // The calls of the gas function, made in every iteration, aren't recorded.
+    let trace = trace.borrow();
+    assert!(trace.truncated);
+    assert_eq!(trace.calls.len(), MAX_TRACE_CALLS);
+    assert!(trace.calls.iter().all(|call| call.function == "get_phase"));
+}
+
+#[test]
+fn profiled_deploy_is_executed() {
+    let (engine_state, root_hash) = engine_state(&mocked_account(ACCOUNT));
+    let session = wabt::wat2wasm(
+        r#"(module
+            (import "env" "get_phase" (func $get_phase (result i32)))
+            (memory 1)
+            (func $phase (result i32) (call $get_phase))
+            (func (export "call") (drop (call $phase))))"#,
+    )
+    .unwrap();
+    // Instrumented modules have to validate and instantiate like any other.
+    let profile = Rc::new(RefCell::new(GasProfile::default()));
+    let result = engine_state
+        .run_deploy(
+            &session,
+            &[],
+            ACCOUNT,
+            0,
+            1,
+            root_hash,
+            GAS_LIMIT,
+            1,
+            BlockContext::default(),
+            &WasmiExecutor,
+            &WasmiPreprocessor::with_profiling(WasmCosts::from_version(1).unwrap()),
+            None,
+            Some(Rc::clone(&profile)),
+        )
+        .unwrap();
+    assert_matches!(result, ExecutionResult::Success { .. });
+
+    let profile = profile.borrow();
+    assert_eq!(profile.total(), result.cost() - result.storage_cost());
+    assert!(profile.to_folded().contains(";host:get_phase "));
+}
+
+#[test]
+fn run_deploys_in_parallel() {
+    const OTHER: [u8; 32] = [4u8; 32];
+    let mut pairs = mocked_account(ACCOUNT);
+    pairs.extend(mocked_account(TARGET));
+    pairs.extend(mocked_account(OTHER));
+    let (engine_state, root_hash) = engine_state(&pairs);
+    let store = new_uref_session();
+    let revert = wabt::wat2wasm(
+        r#"(module
+            (import "env" "revert" (func $revert (param i32)))
+            (memory 1)
+            (func (export "call") (call $revert (i32.const 1))))"#,
+    )
+    .unwrap();
+    let phase = wabt::wat2wasm(
+        r#"(module
+            (import "env" "get_phase" (func $get_phase (result i32)))
+            (memory 1)
+            (func (export "call") (drop (call $get_phase))))"#,
+    )
+    .unwrap();
+    let deploys = vec![
+        deploy_item(&store, ACCOUNT, 1, false),
+        deploy_item(&phase, TARGET, 1, true),
+        // conflicts with the first deploy, as both increment the nonce of the account
+        deploy_item(&store, ACCOUNT, 1, false),
+        deploy_item(&revert, OTHER, 1, false),
+        deploy_item(&store, OTHER, 2, false),
+    ];
+
+    let outcomes = engine_state
+        .run_deploys(
+            &deploys,
+            root_hash,
+            1,
+            BlockContext::default(),
+            &WasmiExecutor,
+            &preprocessor(),
+        )
+        .unwrap();
+    assert_eq!(outcomes.len(), deploys.len());
+    for (deploy, outcome) in deploys.iter().zip(outcomes.iter()) {
+        let trace = if deploy.trace {
+            Some(Rc::new(RefCell::new(Trace::default())))
+        } else {
+            None
+        };
+        let expected = engine_state
+            .run_deploy(
+                deploy.module_bytes,
+                deploy.args,
+                deploy.address,
+                deploy.timestamp,
+                deploy.nonce,
+                root_hash,
+                deploy.gas_limit,
+                1,
+                BlockContext::default(),
+                &WasmiExecutor,
+                &preprocessor(),
+                trace.clone(),
+                None,
+            )
+            .unwrap();
+        assert_same_result(&outcome.result, &expected);
+        assert_eq!(outcome.trace, trace.map(|trace| trace.borrow().clone()));
+    }
+    let conflicts: Vec<&[usize]> = outcomes
+        .iter()
+        .map(|outcome| outcome.conflicts.as_slice())
+        .collect();
+    assert_eq!(conflicts, vec![&[][..], &[], &[0], &[], &[]]);
+}
+
+#[test]
+fn run_deploys_chained() {
+    let (engine_state, root_hash) = engine_state(&mocked_account(ACCOUNT));
+    let store = new_uref_session();
+    let store_and_revert = wabt::wat2wasm(
+        r#"(module
+            (import "env" "new_uref" (func $new_uref (param i32 i32 i32)))
+            (import "env" "revert" (func $revert (param i32)))
+            (memory 1)
+            (data (i32.const 0) "\00\02\00\00\00")
+            (func (export "call")
+                (call $new_uref (i32.const 100) (i32.const 0) (i32.const 5))
+                (call $revert (i32.const 1))))"#,
+    )
+    .unwrap();
+    // Every deploy is only valid if it sees the nonce increment of the preceding one.
+    let deploys = vec![
+        deploy_item(&store, ACCOUNT, 1, false),
+        deploy_item(&store_and_revert, ACCOUNT, 2, false),
+        deploy_item(&store, ACCOUNT, 4, false),
+        deploy_item(&store, ACCOUNT, 3, false),
+    ];
+
+    let chained = engine_state
+        .run_deploys_chained(
+            &deploys,
+            root_hash,
+            1,
+            BlockContext::default(),
+            &WasmiExecutor,
+            &preprocessor(),
+        )
+        .unwrap();
+    let results: Vec<&ExecutionResult> = chained
+        .outcomes
+        .iter()
+        .map(|outcome| &outcome.result)
+        .collect();
+    assert_matches!(results[0], ExecutionResult::Success { .. });
+    assert_matches!(
+        results[1],
+        ExecutionResult::Failure {
+            error: Error::ExecError(execution::Error::Revert(1)),
+            ..
+        }
+    );
+    assert_matches!(
+        results[2],
+        ExecutionResult::Failure {
+            error: Error::ExecError(execution::Error::InvalidNonce { .. }),
+            cost: 0,
+            ..
+        }
+    );
+    assert_matches!(results[3], ExecutionResult::Success { .. });
+
+    // The write of the failed deploy is dropped, but its nonce increment isn't.
+    let stored: Vec<&Transform> = chained
+        .effect
+        .1
+        .iter()
+        .filter(|(key, _)| match key {
+            Key::URef(..) => true,
+            _ => false,
+        })
+        .map(|(_, transform)| transform)
+        .collect();
+    let written = Transform::Write(Value::Int32(1));
+    assert!(!stored.is_empty());
+    assert!(stored.iter().all(|transform| **transform == written));
+    let post_state_hash = match engine_state
+        .apply_effect(root_hash, chained.effect.1.clone(), 1)
+        .unwrap()
+    {
+        CommitResult::Success(post_state_hash) => post_state_hash,
+        other => panic!("Commit should succeed: {:?}", other),
+    };
+    let (nonce, _) = account_purse(&engine_state, post_state_hash, ACCOUNT);
+    assert_eq!(nonce, 3);
+}
+
+#[test]
+fn estimate_deploy_runs_payment_and_session() {
+    let (engine_state, root_hash) = engine_state(&mocked_account(ACCOUNT));
+    let store = new_uref_session();
+    let no_args: &[u8] = &[];
+    let result = engine_state
+        .estimate_deploy(
+            (&store[..], no_args),
+            (&store[..], no_args),
+            ACCOUNT,
+            0,
+            1,
+            root_hash,
+            2,
+            BlockContext::default(),
+            &WasmiExecutor,
+            &preprocessor(),
+        )
+        .unwrap();
+    assert_matches!(result, ExecutionResult::Success { .. });
+
+    // Both phases are charged.
+    let session_only = run_session(&engine_state, root_hash, &store, GAS_LIMIT);
+    assert!(result.cost() > session_only.cost());
+    // From protocol version 2 on, each phase creates its uref with its own rng, so they
+    // don't collide.
+    let urefs = result
+        .effect()
+        .1
+        .keys()
+        .filter(|key| match key {
+            Key::URef(..) => true,
+            _ => false,
+        })
+        .count();
+    assert_eq!(urefs, 2);
+}
+
+fn run_view(wat: &str) -> (Result<Vec<u8>, Error>, u64) {
+    let mut pairs = mocked_account(ACCOUNT);
+    pairs.push((Key::Hash(CONTRACT), contract(wat)));
+    let (engine_state, root_hash) = engine_state(&pairs);
+    let view_result = engine_state
+        .run_view(
+            Key::Hash(CONTRACT),
+            &[],
+            ACCOUNT,
+            root_hash,
+            BlockContext::default(),
+            &WasmiExecutor,
+        )
+        .unwrap();
+    (view_result.value, view_result.cost)
+}
+
+#[test]
+fn run_view_returns_ret_bytes() {
+    let (value, cost) = run_view(
+        r#"(module
+            (import "env" "ret" (func $ret (param i32 i32 i32 i32)))
+            (memory 1)
+            (data (i32.const 0) "\01\02\03")
+            ;; no urefs are returned, which is an empty list serialized as zeroes
+            (func (export "call")
+                (call $ret (i32.const 0) (i32.const 3) (i32.const 100) (i32.const 4))))"#,
+    );
+    assert_eq!(value.unwrap(), vec![1u8, 2, 3]);
+    assert!(cost > 0);
+}
+
+#[test]
+fn run_view_rejects_writes() {
+    let (value, _) = run_view(
+        r#"(module
+            (import "env" "new_uref" (func $new_uref (param i32 i32 i32)))
+            (memory 1)
+            (data (i32.const 0) "\00\01\00\00\00")
+            (func (export "call")
+                (call $new_uref (i32.const 100) (i32.const 0) (i32.const 5))))"#,
+    );
+    match value {
+        Err(error) => assert!(format!("{:?}", error).contains("InvalidAccess")),
+        Ok(bytes) => panic!("Write should be rejected, got {:?}", bytes),
+    }
+}
+
+#[test]
+fn run_view_is_limited_by_view_gas_limit() {
+    let (value, cost) = run_view(
+        r#"(module
+            (memory 1)
+            (func (export "call")
+                (loop $forever (br $forever))))"#,
+    );
+    assert_matches!(value, Err(Error::ExecError(execution::Error::GasLimit)));
+    assert!(cost <= VIEW_GAS_LIMIT);
+}
+
+#[test]
+fn v1_uref_is_unchanged() {
+    // Address of the first uref created by any deploy of protocol version 1, whose rng
+    // is seeded with the hash of no data.
+    const V1_UREF: [u8; 32] = [
+        58, 43, 238, 81, 100, 185, 116, 107, 101, 229, 9, 116, 205, 193, 70, 96, 114, 147, 29, 106,
+        154, 239, 52, 89, 181, 172, 63, 5, 136, 250, 165, 19,
+    ];
+    let (engine_state, root_hash) = engine_state(&mocked_account(ACCOUNT));
+    let result = run_session(&engine_state, root_hash, &new_uref_session(), GAS_LIMIT);
+    let urefs: Vec<[u8; 32]> = result
+        .effect()
+        .1
+        .keys()
+        .filter_map(|key| match key {
+            Key::URef(addr, _) => Some(*addr),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(urefs, vec![V1_UREF]);
+}
+
+#[test]
+fn transfer_to_existing_account_leaves_urefs_unchanged() {
+    let target = PublicKey::new(TARGET).to_bytes().unwrap();
+    let amount = U512::from(10).to_bytes().unwrap();
+    let wat = format!(
+        r#"(module
+            (import "env" "transfer_to_account"
+                (func $transfer_to_account (param i32 i32 i32 i32) (result i32)))
+            (import "env" "new_uref" (func $new_uref (param i32 i32 i32)))
+            (memory 1)
+            (data (i32.const 0) "\00\01\00\00\00")
+            (data (i32.const 100) "{}")
+            (data (i32.const 200) "{}")
+            (func (export "call")
+                (drop (call $transfer_to_account
+                    (i32.const 100) (i32.const {})
+                    (i32.const 200) (i32.const {})))
+                (call $new_uref (i32.const 300) (i32.const 0) (i32.const 5))))"#,
+        data(&target),
+        data(&amount),
+        target.len(),
+        amount.len()
+    );
+    let mut pairs = mocked_account(ACCOUNT);
+    pairs.extend(mocked_account(TARGET));
+    let (engine_state, root_hash) = engine_state(&pairs);
+    let urefs = |result: &ExecutionResult| -> Vec<[u8; 32]> {
+        result
+            .effect()
+            .1
+            .keys()
+            .filter_map(|key| match key {
+                Key::URef(addr, _) => Some(*addr),
+                _ => None,
+            })
+            .collect()
+    };
+
+    let transfer_result = run_session(
+        &engine_state,
+        root_hash,
+        &wabt::wat2wasm(wat).unwrap(),
+        GAS_LIMIT,
+    );
+    let new_uref_result = run_session(&engine_state, root_hash, &new_uref_session(), GAS_LIMIT);
+    match transfer_result {
+        ExecutionResult::Success { .. } => {}
+        ref other => panic!("Transfer should succeed: {:?}", other),
+    }
+    assert_eq!(urefs(&transfer_result), urefs(&new_uref_result));
+}
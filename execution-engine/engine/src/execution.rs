@@ -3,8 +3,9 @@ use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
 use std::iter::IntoIterator;
 use std::rc::Rc;
+use std::sync::Arc;
 
-use blake2::digest::VariableOutput;
+use blake2::digest::{Input, VariableOutput};
 use blake2::VarBlake2b;
 use itertools::Itertools;
 use parity_wasm::elements::{Error as ParityWasmError, Module};
@@ -16,25 +17,36 @@ use wasmi::{
 };
 
 use common::bytesrepr::{deserialize, Error as BytesReprError, ToBytes};
+use common::contract_api::{CallError, Phase, TransferResult};
 use common::key::{AccessRights, Key};
-use common::value::Value;
+use common::value::account::{Account, PublicKey};
+use common::value::{Contract, Value, U512};
 use shared::newtypes::Validated;
 use shared::transform::TypeMismatch;
 use storage::global_state::StateReader;
 
 use args::Args;
+use engine_state::block_context::BlockContext;
+use engine_state::event::Event;
 use engine_state::execution_effect::ExecutionEffect;
 use functions::{
-    ADD_FUNC_INDEX, ADD_UREF_FUNC_INDEX, CALL_CONTRACT_FUNC_INDEX, GAS_FUNC_INDEX,
-    GET_ARG_FUNC_INDEX, GET_CALL_RESULT_FUNC_INDEX, GET_FN_FUNC_INDEX, GET_READ_FUNC_INDEX,
-    GET_UREF_FUNC_INDEX, HAS_UREF_FUNC_INDEX, IS_VALID_FN_INDEX, LOAD_ARG_FUNC_INDEX,
-    NEW_FUNC_INDEX, PROTOCOL_VERSION_FUNC_INDEX, READ_FUNC_INDEX, RET_FUNC_INDEX, SEED_FN_INDEX,
-    SER_FN_FUNC_INDEX, STORE_FN_INDEX, WRITE_FUNC_INDEX,
+    ADD_FUNC_INDEX, ADD_UREF_FUNC_INDEX, CALL_CONTRACT_FUNC_INDEX, EMIT_EVENT_FUNC_INDEX,
+    GAS_FUNC_INDEX, GET_ARG_FUNC_INDEX, GET_BLOCKTIME_FUNC_INDEX, GET_BLOCK_HEIGHT_FUNC_INDEX,
+    GET_CALLER_FUNC_INDEX, GET_CALL_RESULT_FUNC_INDEX, GET_FN_FUNC_INDEX, GET_PHASE_FUNC_INDEX,
+    GET_READ_FUNC_INDEX, GET_UREF_FUNC_INDEX, HAS_UREF_FUNC_INDEX, IS_VALID_FN_INDEX,
+    LOAD_ARG_FUNC_INDEX, LOAD_CALLER_FUNC_INDEX, NEW_FUNC_INDEX, PROTOCOL_VERSION_FUNC_INDEX,
+    READ_FUNC_INDEX, REMOVE_UREF_FUNC_INDEX, RET_FUNC_INDEX, REVERT_FUNC_INDEX, SEED_FN_INDEX,
+    SER_FN_FUNC_INDEX, STORE_FN_INDEX, STORE_UPGRADABLE_FN_INDEX, TRANSFER_TO_ACCOUNT_FUNC_INDEX,
+    TRY_CALL_CONTRACT_FUNC_INDEX, UPGRADE_CONTRACT_FUNC_INDEX, WRITE_FUNC_INDEX,
 };
+use host_costs::HostCosts;
+use module_cache::{ModuleCache, ModuleKind};
+use profile::{GasProfile, Profiler};
 use resolvers::create_module_resolver;
 use resolvers::error::ResolverError;
 use resolvers::memory_resolver::MemoryResolver;
 use runtime_context::RuntimeContext;
+use trace::{self, HostCall, Trace, MAX_TRACE_CALLS};
 use tracking_copy::TrackingCopy;
 use URefAddr;
 
@@ -45,7 +57,9 @@ pub enum Error {
     BytesRepr(BytesReprError),
     KeyNotFound(Key),
     TypeMismatch(TypeMismatch),
-    InvalidAccess { required: AccessRights },
+    InvalidAccess {
+        required: AccessRights,
+    },
     ForgedReference(Key),
     ArgIndexOutOfBounds(usize),
     URefNotFound(String),
@@ -55,6 +69,19 @@ pub enum Error {
     Ret(Vec<Key>),
     Rng(rand::Error),
     ResolverError(ResolverError),
+    InvalidNonce {
+        deploy_nonce: u64,
+        expected_nonce: u64,
+    },
+    InsufficientFunds,
+    /// Funds can only be transferred from the main purse of an account by the account
+    /// itself, not by the contracts it calls.
+    TransferNotPermitted,
+    UpgradeNotPermitted(Key),
+    /// Execution was stopped by the contract with a contract-defined status.
+    Revert(u32),
+    /// A numeric addition didn't fit in the type of the value, under checked arithmetic.
+    Overflow,
 }
 
 impl fmt::Display for Error {
@@ -101,11 +128,58 @@ impl From<ResolverError> for Error {
 
 impl HostError for Error {}
 
+/// Classifies the failure of a contract called with `try_call_contract`.
+/// Returns `None` for storage errors, which aren't caused by the callee
+/// and still have to abort the deploy.
+fn call_error(error: &Error) -> Option<CallError> {
+    match error {
+        Error::Interpreter(e) => match e
+            .as_host_error()
+            .and_then(|host_error| host_error.downcast_ref::<Error>())
+        {
+            Some(host_error) => call_error(host_error),
+            None => Some(CallError::Failed),
+        },
+        Error::Storage(_) => None,
+        Error::Revert(status) => Some(CallError::Revert(*status)),
+        Error::GasLimit => Some(CallError::OutOfGas),
+        Error::ForgedReference(_) => Some(CallError::ForgedReference),
+        _ => Some(CallError::Failed),
+    }
+}
+
+/// Turns a Wasm execution error into `Error::Revert` if it was caused by the `revert`
+/// host function, so that the status reaches the client also from nested calls.
+/// Overflows of numeric additions and running out of gas are surfaced the same way.
+fn interpreter_error(error: InterpreterError) -> Error {
+    let surfaced = error
+        .as_host_error()
+        .and_then(|host_error| host_error.downcast_ref::<Error>())
+        .and_then(|host_error| match host_error {
+            Error::Revert(status) => Some(Error::Revert(*status)),
+            Error::Overflow => Some(Error::Overflow),
+            Error::GasLimit => Some(Error::GasLimit),
+            _ => None,
+        });
+    match surfaced {
+        Some(error) => error,
+        None => Error::Interpreter(error),
+    }
+}
+
 pub struct Runtime<'a, R> {
     memory: MemoryRef,
     module: Module,
     result: Vec<u8>,
     host_buf: Vec<u8>,
+    host_costs: HostCosts,
+    module_cache: &'a ModuleCache,
+    // Records host calls if tracing was requested; shared with the runtimes of called contracts.
+    trace: Option<Rc<RefCell<Trace>>>,
+    // Number of contract calls between the deploy and this runtime.
+    depth: usize,
+    // Attributes gas to Wasm and host functions if profiling was requested.
+    profiler: Option<Profiler>,
     context: RuntimeContext<'a, R>,
 }
 
@@ -130,12 +204,25 @@ where
     R::Error: Into<Error>,
 {
     #[allow(clippy::too_many_arguments)]
-    pub fn new(memory: MemoryRef, module: Module, context: RuntimeContext<'a, R>) -> Self {
+    pub fn new(
+        memory: MemoryRef,
+        module: Module,
+        host_costs: HostCosts,
+        module_cache: &'a ModuleCache,
+        trace: Option<Rc<RefCell<Trace>>>,
+        profiler: Option<Profiler>,
+        context: RuntimeContext<'a, R>,
+    ) -> Self {
         Runtime {
             memory,
             module,
             result: Vec::new(),
             host_buf: Vec::new(),
+            host_costs,
+            module_cache,
+            trace,
+            depth: 0,
+            profiler,
             context,
         }
     }
@@ -165,6 +252,56 @@ where
         }
     }
 
+    /// Number of bytes which the call to the host function `index` copies
+    /// between Wasm memory and the host.
+    fn host_call_bytes(&self, index: usize, args: &RuntimeArgs) -> Result<u64, Trap> {
+        // positions of the arguments holding sizes of the data read from Wasm memory
+        let size_args: &[usize] = match index {
+            READ_FUNC_INDEX
+            | SER_FN_FUNC_INDEX
+            | GET_UREF_FUNC_INDEX
+            | HAS_UREF_FUNC_INDEX
+            | REMOVE_UREF_FUNC_INDEX
+            | IS_VALID_FN_INDEX => &[1],
+            NEW_FUNC_INDEX => &[2],
+            WRITE_FUNC_INDEX
+            | ADD_FUNC_INDEX
+            | RET_FUNC_INDEX
+            | ADD_UREF_FUNC_INDEX
+            | STORE_FN_INDEX
+            | STORE_UPGRADABLE_FN_INDEX
+            | TRANSFER_TO_ACCOUNT_FUNC_INDEX
+            | EMIT_EVENT_FUNC_INDEX => &[1, 3],
+            CALL_CONTRACT_FUNC_INDEX
+            | TRY_CALL_CONTRACT_FUNC_INDEX
+            | UPGRADE_CONTRACT_FUNC_INDEX => &[1, 3, 5],
+            // these copy the host buffer into Wasm memory
+            GET_READ_FUNC_INDEX
+            | GET_FN_FUNC_INDEX
+            | GET_ARG_FUNC_INDEX
+            | GET_CALL_RESULT_FUNC_INDEX
+            | GET_CALLER_FUNC_INDEX => return Ok(self.host_buf.len() as u64),
+            _ => &[],
+        };
+        size_args.iter().try_fold(0u64, |bytes, i| {
+            let size: u32 = args.nth_checked(*i)?;
+            Ok(bytes.saturating_add(size.into()))
+        })
+    }
+
+    /// Charges the cost of the call to the host function `index` before it runs.
+    fn charge_host_call(&mut self, index: usize, args: &RuntimeArgs) -> Result<(), Trap> {
+        let bytes = self.host_call_bytes(index, args)?;
+        let cost = self.host_costs.cost(index, bytes);
+        self.gas(cost)?;
+        if let Some(profiler) = &self.profiler {
+            if cost > 0 {
+                profiler.record_host(trace::function_name(index), cost);
+            }
+        }
+        Ok(())
+    }
+
     fn bytes_from_mem(&self, ptr: u32, size: usize) -> Result<Vec<u8>, Error> {
         self.memory.get(ptr, size).map_err(Into::into)
     }
@@ -227,6 +364,12 @@ where
         }
     }
 
+    /// Load the serialized key of the caller into the host buffer.
+    pub fn load_caller(&mut self) -> Result<usize, Trap> {
+        self.host_buf = self.context.caller().to_bytes().map_err(Error::BytesRepr)?;
+        Ok(self.host_buf.len())
+    }
+
     /// Load the uref known by the given name into the Wasm memory
     pub fn get_uref(&mut self, name_ptr: u32, name_size: u32, dest_ptr: u32) -> Result<(), Trap> {
         let name = self.string_from_mem(name_ptr, name_size)?;
@@ -261,6 +404,12 @@ where
         self.context.add_uref(name, key).map_err(Into::into)
     }
 
+    /// Removes the name read from Wasm memory from the named keys of the current context.
+    pub fn remove_uref(&mut self, name_ptr: u32, name_size: u32) -> Result<(), Trap> {
+        let name = self.string_from_mem(name_ptr, name_size)?;
+        self.context.remove_uref(&name).map_err(Into::into)
+    }
+
     pub fn set_mem_from_buf(&mut self, dest_ptr: u32) -> Result<(), Trap> {
         self.memory
             .set(dest_ptr, &self.host_buf)
@@ -298,6 +447,32 @@ where
         }
     }
 
+    /// Calls contract living under a `key` like `call_contract`, but a failure of the callee
+    /// is returned to the caller as a `CallError` and all of the callee's effects are rolled
+    /// back. Only errors of the global state abort the execution of the caller.
+    pub fn try_call_contract(
+        &mut self,
+        key: Key,
+        args_bytes: Vec<u8>,
+        urefs_bytes: Vec<u8>,
+    ) -> Result<usize, Error> {
+        let state = self.context.state();
+        let checkpoint = state.borrow_mut().checkpoint();
+        let outcome: Result<Vec<u8>, CallError> =
+            match self.call_contract(key, args_bytes, urefs_bytes) {
+                Ok(_) => {
+                    state.borrow_mut().release(checkpoint);
+                    Ok(std::mem::replace(&mut self.host_buf, Vec::new()))
+                }
+                Err(error) => {
+                    state.borrow_mut().rollback(checkpoint);
+                    Err(call_error(&error).ok_or(error)?)
+                }
+            };
+        self.host_buf = outcome.to_bytes()?;
+        Ok(self.host_buf.len())
+    }
+
     /// Calls contract living under a `key`, with supplied `args` and extra `urefs`.
     pub fn call_contract(
         &mut self,
@@ -305,33 +480,16 @@ where
         args_bytes: Vec<u8>,
         urefs_bytes: Vec<u8>,
     ) -> Result<usize, Error> {
-        let (args, module, mut refs, protocol_version) = {
-            match self.context.read_gs(&key)? {
-                None => Err(Error::KeyNotFound(key)),
-                Some(value) => {
-                    if let Value::Contract(contract) = value {
-                        let args: Vec<Vec<u8>> = deserialize(&args_bytes)?;
-                        let module = parity_wasm::deserialize_buffer(contract.bytes())?;
-
-                        Ok((
-                            args,
-                            module,
-                            contract.urefs_lookup().clone(),
-                            contract.protocol_version(),
-                        ))
-                    } else {
-                        Err(Error::FunctionNotFound(format!(
-                            "Value at {:?} is not a contract",
-                            key
-                        )))
-                    }
-                }
-            }
-        }?;
+        let value = self.context.read_gs(&key)?;
+        let (contract, module, validated) = load_contract(key, value, self.module_cache)?;
+        let args: Vec<Vec<u8>> = deserialize(&args_bytes)?;
+        let mut refs = contract.urefs_lookup().clone();
+        let protocol_version = contract.protocol_version();
 
         let extra_urefs = self.context.deserialize_keys(&urefs_bytes)?;
         let result = sub_call(
             module,
+            &validated,
             args,
             &mut refs,
             key,
@@ -365,6 +523,37 @@ where
         Ok(new_hash)
     }
 
+    /// Tries to store a function, represented as bytes from the Wasm memory, into the GlobalState
+    /// as a contract which can be upgraded by the holder of the returned admin URef.
+    pub fn store_upgradable_function(
+        &mut self,
+        fn_bytes: Vec<u8>,
+        urefs: BTreeMap<String, Key>,
+    ) -> Result<([u8; 32], Key), Error> {
+        self.context.store_upgradable_contract(fn_bytes, urefs)
+    }
+
+    /// Replaces the body of the contract under the key read from `key_ptr` with the function
+    /// exported under the given name by the current module. The admin URef of the contract,
+    /// with WRITE rights, has to be passed at `admin_ptr`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn upgrade_contract(
+        &mut self,
+        key_ptr: u32,
+        key_size: u32,
+        admin_ptr: u32,
+        admin_size: u32,
+        name_ptr: u32,
+        name_size: u32,
+    ) -> Result<(), Trap> {
+        let contract_key = self.key_from_mem(key_ptr, key_size)?;
+        let admin = self.key_from_mem(admin_ptr, admin_size)?;
+        let fn_bytes = self.get_function_by_name(name_ptr, name_size)?;
+        self.context
+            .upgrade_contract(contract_key, admin, fn_bytes)
+            .map_err(Into::into)
+    }
+
     /// Writes function address (`hash_bytes`) into the Wasm memory (at `dest_ptr` pointer).
     fn function_address(&mut self, hash_bytes: [u8; 32], dest_ptr: u32) -> Result<(), Trap> {
         self.memory
@@ -419,6 +608,44 @@ where
         Ok(self.host_buf.len())
     }
 
+    /// Transfers motes from the main purse of the current account to the main purse
+    /// of the target account, creating the target account if it doesn't exist.
+    pub fn transfer_to_account(
+        &mut self,
+        target_ptr: u32,
+        target_size: u32,
+        amount_ptr: u32,
+        amount_size: u32,
+    ) -> Result<TransferResult, Trap> {
+        let target: PublicKey = {
+            let bytes = self.bytes_from_mem(target_ptr, target_size as usize)?;
+            deserialize(&bytes).map_err(Error::BytesRepr)?
+        };
+        let amount: U512 = {
+            let bytes = self.bytes_from_mem(amount_ptr, amount_size as usize)?;
+            deserialize(&bytes).map_err(Error::BytesRepr)?
+        };
+        match self.context.transfer_to_account(target.value(), amount) {
+            Err(Error::InsufficientFunds) => Ok(TransferResult::InsufficientFunds),
+            result => result.map_err(Into::into),
+        }
+    }
+
+    /// Records an event with the topic and payload read from Wasm memory.
+    /// The payload is opaque to the host and is taken as is.
+    pub fn emit_event(
+        &mut self,
+        topic_ptr: u32,
+        topic_size: u32,
+        payload_ptr: u32,
+        payload_size: u32,
+    ) -> Result<(), Trap> {
+        let topic = self.string_from_mem(topic_ptr, topic_size)?;
+        let payload = self.bytes_from_mem(payload_ptr, payload_size as usize)?;
+        self.context.emit_event(Event::new(topic, payload));
+        Ok(())
+    }
+
     /// Writes the seed associated with the [`RuntimeContext`] to the given destination
     /// in runtime memory.
     fn write_seed(&mut self, dest_ptr: u32) -> Result<(), Trap> {
@@ -433,15 +660,38 @@ fn as_usize(u: u32) -> usize {
     u as usize
 }
 
-impl<'a, R: StateReader<Key, Value>> Externals for Runtime<'a, R>
+impl<'a, R: StateReader<Key, Value>> Runtime<'a, R>
 where
     R::Error: Into<Error>,
 {
-    fn invoke_index(
-        &mut self,
-        index: usize,
-        args: RuntimeArgs,
-    ) -> Result<Option<RuntimeValue>, Trap> {
+    /// Decodes the arguments of a call to the host function `index` for the trace.
+    fn trace_args(&self, index: usize, args: &RuntimeArgs) -> Vec<String> {
+        let decoded_args = trace::decoded_args(index);
+        if decoded_args.is_empty() {
+            return args
+                .as_ref()
+                .iter()
+                .map(|arg| format!("{:?}", arg))
+                .collect();
+        }
+        decoded_args
+            .iter()
+            .map(|(ptr_index, kind)| {
+                let ptr: Result<u32, Trap> = args.nth_checked(*ptr_index);
+                let size: Result<u32, Trap> = args.nth_checked(*ptr_index + 1);
+                match (ptr, size) {
+                    (Ok(ptr), Ok(size)) => match self.bytes_from_mem(ptr, size as usize) {
+                        Ok(bytes) => kind.decode(&bytes),
+                        Err(error) => format!("<{:?}>", error),
+                    },
+                    _ => String::from("<missing>"),
+                }
+            })
+            .collect()
+    }
+
+    fn host_call(&mut self, index: usize, args: RuntimeArgs) -> Result<Option<RuntimeValue>, Trap> {
+        self.charge_host_call(index, &args)?;
         match index {
             READ_FUNC_INDEX => {
                 // args(0) = pointer to key in Wasm memory
@@ -554,6 +804,29 @@ where
                 Ok(Some(RuntimeValue::I32(size as i32)))
             }
 
+            TRY_CALL_CONTRACT_FUNC_INDEX => {
+                // args(0) = pointer to key where contract is at in global state
+                // args(1) = size of key
+                // args(2) = pointer to function arguments in Wasm memory
+                // args(3) = size of arguments
+                // args(4) = pointer to extra supplied urefs
+                // args(5) = size of extra urefs
+                let (key_ptr, key_size, args_ptr, args_size, extra_urefs_ptr, extra_urefs_size) =
+                    Args::parse(args)?;
+
+                // We have to explicitly tell rustc what type we expect as it cannot infer it otherwise.
+                let _args_size_u32: u32 = args_size;
+                let _extra_urefs_size_u32: u32 = extra_urefs_size;
+
+                let key_contract: Key = self.key_from_mem(key_ptr, key_size)?;
+                let args_bytes: Vec<u8> = self.bytes_from_mem(args_ptr, args_size as usize)?;
+                let urefs_bytes =
+                    self.bytes_from_mem(extra_urefs_ptr, extra_urefs_size as usize)?;
+
+                let size = self.try_call_contract(key_contract, args_bytes, urefs_bytes)?;
+                Ok(Some(RuntimeValue::I32(size as i32)))
+            }
+
             GET_CALL_RESULT_FUNC_INDEX => {
                 // args(0) = pointer to destination in Wasm memory
                 let dest_ptr = Args::parse(args)?;
@@ -587,9 +860,20 @@ where
                 Ok(None)
             }
 
+            REMOVE_UREF_FUNC_INDEX => {
+                // args(0) = pointer to uref name in Wasm memory
+                // args(1) = size of uref name
+                let (name_ptr, name_size) = Args::parse(args)?;
+                self.remove_uref(name_ptr, name_size)?;
+                Ok(None)
+            }
+
             GAS_FUNC_INDEX => {
                 let gas: u32 = Args::parse(args)?;
                 self.gas(u64::from(gas))?;
+                if let Some(profiler) = &self.profiler {
+                    profiler.record_wasm(u64::from(gas));
+                }
                 Ok(None)
             }
 
@@ -614,6 +898,47 @@ where
                 Ok(None)
             }
 
+            STORE_UPGRADABLE_FN_INDEX => {
+                // args(0) = pointer to function name in Wasm memory
+                // args(1) = size of the name
+                // args(2) = pointer to additional unforgable names
+                //           to be saved with the function body
+                // args(3) = size of the additional unforgable names
+                // args(4) = pointer to a Wasm memory where we will save
+                //           hash of the new function
+                // args(5) = pointer to a Wasm memory where we will save
+                //           the admin URef of the new function
+                let (name_ptr, name_size, urefs_ptr, urefs_size, hash_ptr, admin_ptr) =
+                    Args::parse(args)?;
+                let fn_bytes = self.get_function_by_name(name_ptr, name_size)?;
+                let uref_bytes = self
+                    .memory
+                    .get(urefs_ptr, urefs_size as usize)
+                    .map_err(Error::Interpreter)?;
+                let urefs = deserialize(&uref_bytes).map_err(Error::BytesRepr)?;
+                let (contract_hash, admin) = self.store_upgradable_function(fn_bytes, urefs)?;
+                self.function_address(contract_hash, hash_ptr)?;
+                self.memory
+                    .set(admin_ptr, &admin.to_bytes().map_err(Error::BytesRepr)?)
+                    .map_err(Error::Interpreter)?;
+                Ok(None)
+            }
+
+            UPGRADE_CONTRACT_FUNC_INDEX => {
+                // args(0) = pointer to the key of the contract in Wasm memory
+                // args(1) = size of the key
+                // args(2) = pointer to the admin URef of the contract in Wasm memory
+                // args(3) = size of the admin URef
+                // args(4) = pointer to the name of the new function in Wasm memory
+                // args(5) = size of the name
+                let (key_ptr, key_size, admin_ptr, admin_size, name_ptr, name_size) =
+                    Args::parse(args)?;
+                self.upgrade_contract(
+                    key_ptr, key_size, admin_ptr, admin_size, name_ptr, name_size,
+                )?;
+                Ok(None)
+            }
+
             PROTOCOL_VERSION_FUNC_INDEX => Ok(Some(self.context.protocol_version().into())),
 
             SEED_FN_INDEX => {
@@ -634,27 +959,182 @@ where
                 }
             }
 
+            TRANSFER_TO_ACCOUNT_FUNC_INDEX => {
+                // args(0) = pointer to the public key of the target account in Wasm memory
+                // args(1) = size of the public key
+                // args(2) = pointer to the amount in Wasm memory
+                // args(3) = size of the amount
+                let (target_ptr, target_size, amount_ptr, amount_size) = Args::parse(args)?;
+                let result =
+                    self.transfer_to_account(target_ptr, target_size, amount_ptr, amount_size)?;
+                Ok(Some(RuntimeValue::I32(result as i32)))
+            }
+
+            EMIT_EVENT_FUNC_INDEX => {
+                // args(0) = pointer to the topic of the event in Wasm memory
+                // args(1) = size of the topic
+                // args(2) = pointer to the payload of the event in Wasm memory
+                // args(3) = size of the payload
+                let (topic_ptr, topic_size, payload_ptr, payload_size) = Args::parse(args)?;
+                self.emit_event(topic_ptr, topic_size, payload_ptr, payload_size)?;
+                Ok(None)
+            }
+
+            GET_BLOCKTIME_FUNC_INDEX => Ok(Some(self.context.block_context().time.into())),
+
+            GET_BLOCK_HEIGHT_FUNC_INDEX => Ok(Some(self.context.block_context().height.into())),
+
+            LOAD_CALLER_FUNC_INDEX => {
+                let size = self.load_caller()?;
+                Ok(Some(RuntimeValue::I32(size as i32)))
+            }
+
+            GET_CALLER_FUNC_INDEX => {
+                // args(0) = pointer to destination in Wasm memory
+                let dest_ptr = Args::parse(args)?;
+                self.set_mem_from_buf(dest_ptr)?;
+                Ok(None)
+            }
+
+            GET_PHASE_FUNC_INDEX => Ok(Some(RuntimeValue::I32(self.context.phase() as i32))),
+
+            REVERT_FUNC_INDEX => {
+                // args(0) = status defined by the contract
+                let status: u32 = Args::parse(args)?;
+                Err(Error::Revert(status).into())
+            }
+
             _ => panic!("unknown function index"),
         }
     }
 }
 
+impl<'a, R: StateReader<Key, Value>> Externals for Runtime<'a, R>
+where
+    R::Error: Into<Error>,
+{
+    fn invoke_index(
+        &mut self,
+        index: usize,
+        args: RuntimeArgs,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let host_calls = match self.trace.clone() {
+            // Gas is charged in every basic block, so its calls would crowd out all the others.
+            Some(host_calls) if index != GAS_FUNC_INDEX => host_calls,
+            _ => return self.host_call(index, args),
+        };
+        if host_calls.borrow().calls.len() >= MAX_TRACE_CALLS {
+            host_calls.borrow_mut().truncated = true;
+            return self.host_call(index, args);
+        }
+        // The call is recorded before it runs, so that it precedes the host calls
+        // of the contracts it calls.
+        let position = host_calls.borrow().calls.len();
+        host_calls.borrow_mut().calls.push(HostCall {
+            function: trace::function_name(index),
+            args: self.trace_args(index, &args),
+            result: String::new(),
+            gas_before: self.context.gas_counter(),
+            gas_after: 0,
+            depth: self.depth,
+        });
+        let result = self.host_call(index, args);
+        let mut host_calls = host_calls.borrow_mut();
+        let call = &mut host_calls.calls[position];
+        call.result = match &result {
+            Ok(Some(value)) => format!("{:?}", value),
+            Ok(None) => String::from("()"),
+            Err(trap) => format!("{:?}", trap),
+        };
+        call.gas_after = self.context.gas_counter();
+        result
+    }
+}
+
 fn instance_and_memory(
-    parity_module: Module,
+    module: &wasmi::Module,
     protocol_version: u64,
 ) -> Result<(ModuleRef, MemoryRef), Error> {
-    let module = wasmi::Module::from_parity_wasm_module(parity_module)?;
     let resolver = create_module_resolver(protocol_version)?;
     let mut imports = ImportsBuilder::new();
     imports.push_resolver("env", &resolver);
-    let instance = ModuleInstance::new(&module, &imports)?.assert_no_start();
+    let instance = ModuleInstance::new(module, &imports)?.assert_no_start();
 
     let memory = resolver.memory_ref()?;
     Ok((instance, memory))
 }
 
+/// Reads the value under `key` on behalf of the system, so without checking access rights.
+fn read_value<R: StateReader<Key, Value>>(
+    tc: &RefCell<TrackingCopy<R>>,
+    key: Key,
+) -> Result<Option<Value>, Error>
+where
+    R::Error: Into<Error>,
+{
+    let validated_key = Validated::new(key, Validated::valid)?;
+    tc.borrow_mut().get(&validated_key).map_err(Into::into)
+}
+
+/// Reads the account stored under `Key::Account(account_addr)`.
+fn load_account<R: StateReader<Key, Value>>(
+    tc: &RefCell<TrackingCopy<R>>,
+    account_addr: [u8; 32],
+) -> Result<Account, Error>
+where
+    R::Error: Into<Error>,
+{
+    let key = Key::Account(account_addr);
+    match read_value(tc, key)? {
+        None => Err(Error::KeyNotFound(key)),
+        Some(Value::Account(account)) => Ok(account),
+        Some(other) => Err(Error::TypeMismatch(TypeMismatch::new(
+            "Account".to_string(),
+            other.type_string(),
+        ))),
+    }
+}
+
+/// Returns the contract in `value`, read from `key`, together with its module and the
+/// `wasmi` module validated from it. Stored contracts are already preprocessed, so the
+/// module is only deserialized.
+fn load_contract(
+    key: Key,
+    value: Option<Value>,
+    module_cache: &ModuleCache,
+) -> Result<(Contract, Module, Arc<wasmi::Module>), Error> {
+    match value {
+        None => Err(Error::KeyNotFound(key)),
+        Some(Value::Contract(contract)) => {
+            let (module, validated) = module_cache.get_or_insert_validated(
+                ModuleKind::Stored,
+                contract.bytes(),
+                contract.protocol_version(),
+                || parity_wasm::deserialize_buffer(contract.bytes()).map_err(Error::from),
+            )?;
+            Ok((contract, module, validated))
+        }
+        Some(_) => Err(Error::FunctionNotFound(format!(
+            "Value at {:?} is not a contract",
+            key
+        ))),
+    }
+}
+
+/// Deserializes the arguments passed to a deploy or a view, which can be left empty.
+fn deserialize_args(args: &[u8]) -> Result<Vec<Vec<u8>>, Error> {
+    if args.is_empty() {
+        Ok(Vec::new())
+    } else {
+        // TODO: figure out how this works with the cost model
+        // https://casperlabs.atlassian.net/browse/EE-239
+        Ok(deserialize(args)?)
+    }
+}
+
 fn sub_call<R: StateReader<Key, Value>>(
     parity_module: Module,
+    module: &wasmi::Module,
     args: Vec<Vec<u8>>,
     refs: &mut BTreeMap<String, Key>,
     key: Key,
@@ -667,15 +1147,26 @@ fn sub_call<R: StateReader<Key, Value>>(
 where
     R::Error: Into<Error>,
 {
-    let (instance, memory) = instance_and_memory(parity_module.clone(), protocol_version)?;
+    let (instance, memory) = instance_and_memory(module, protocol_version)?;
 
     let known_urefs = vec_key_rights_to_map(refs.values().cloned().chain(extra_urefs));
     let rng = ChaChaRng::from_rng(current_runtime.context.rng()).map_err(Error::Rng)?;
+    let host_costs = HostCosts::from_version(protocol_version)
+        .ok_or(ResolverError::UnknownProtocolVersion(protocol_version))?;
+    let profiler = current_runtime
+        .profiler
+        .as_ref()
+        .map(|profiler| profiler.callee(&key, &parity_module, instance.clone()));
     let mut runtime = Runtime {
         memory,
         module: parity_module,
         result: Vec::new(),
         host_buf: Vec::new(),
+        host_costs,
+        module_cache: current_runtime.module_cache,
+        trace: current_runtime.trace.clone(),
+        depth: current_runtime.depth + 1,
+        profiler,
         context: RuntimeContext::new(
             current_runtime.context.state(),
             refs,
@@ -688,13 +1179,22 @@ where
             current_runtime.context.fn_store_id(),
             rng,
             protocol_version,
+            current_runtime.context.base_key(),
+            current_runtime.context.block_context(),
+            current_runtime.context.phase(),
+            current_runtime.context.is_read_only(),
         ),
     };
 
     let result = instance.invoke_export("call", &[], &mut runtime);
 
+    // Gas used by the callee is charged even if the call fails.
+    current_runtime
+        .context
+        .set_gas_counter(runtime.context.gas_counter());
+
     match result {
-        Ok(_) => Ok(runtime.result),
+        Ok(_) => {}
         Err(e) => {
             if let Some(host_error) = e.as_host_error() {
                 // If the "error" was in fact a trap caused by calling `ret` then
@@ -705,12 +1205,21 @@ where
                     let ret_urefs_map: HashMap<URefAddr, HashSet<AccessRights>> =
                         vec_key_rights_to_map(ret_urefs.clone());
                     current_runtime.context.add_urefs(ret_urefs_map);
-                    return Ok(runtime.result);
+                } else {
+                    return Err(interpreter_error(e));
                 }
+            } else {
+                return Err(interpreter_error(e));
             }
-            Err(Error::Interpreter(e))
         }
     }
+
+    // Events emitted by the callee follow the ones that the caller emitted before the call.
+    // Events of a failed call are dropped together with its effects.
+    current_runtime
+        .context
+        .extend_events(runtime.context.take_events());
+    Ok(runtime.result)
 }
 
 /// Groups vector of keys by their address and accumulates access rights per key.
@@ -735,14 +1244,36 @@ pub fn vec_key_rights_to_map<I: IntoIterator<Item = Key>>(
 pub fn create_rng(account_addr: &[u8; 32], timestamp: u64, nonce: u64) -> ChaChaRng {
     let mut seed: [u8; 32] = [0u8; 32];
     let mut data: Vec<u8> = Vec::new();
-    let hasher = VarBlake2b::new(32).unwrap();
+    let mut hasher = VarBlake2b::new(32).unwrap();
     data.extend(account_addr);
     data.extend_from_slice(&timestamp.to_le_bytes());
     data.extend_from_slice(&nonce.to_le_bytes());
+    hasher.input(&data);
     hasher.variable_result(|hash| seed.clone_from_slice(hash));
     ChaChaRng::from_seed(seed)
 }
 
+/// Creates the rng of a deploy executed under `protocol_version`.
+///
+/// Protocol version 1 seeds it with the hash of no data at all, so it is the same for
+/// every deploy. This is kept so that the blocks of that version replay to the same
+/// state. Later versions seed it from the deploy with `create_rng`.
+pub fn create_deploy_rng(
+    account_addr: &[u8; 32],
+    timestamp: u64,
+    nonce: u64,
+    protocol_version: u64,
+) -> ChaChaRng {
+    if protocol_version >= 2 {
+        create_rng(account_addr, timestamp, nonce)
+    } else {
+        let mut seed: [u8; 32] = [0u8; 32];
+        let hasher = VarBlake2b::new(32).unwrap();
+        hasher.variable_result(|hash| seed.clone_from_slice(hash));
+        ChaChaRng::from_seed(seed)
+    }
+}
+
 #[macro_export]
 macro_rules! on_fail_charge {
     ($fn:expr, $cost:expr) => {
@@ -763,12 +1294,33 @@ pub trait Executor<A> {
         parity_module: A,
         args: &[u8],
         account_addr: [u8; 32],
-        timestamp: u64,
-        nonce: u64,
+        rng: ChaChaRng,
         gas_limit: u64,
         protocol_version: u64,
+        block_context: BlockContext,
+        phase: Phase,
         tc: Rc<RefCell<TrackingCopy<R>>>,
-    ) -> (Result<ExecutionEffect, Error>, u64)
+        module_cache: &ModuleCache,
+        trace: Option<Rc<RefCell<Trace>>>,
+        profile: Option<Rc<RefCell<GasProfile>>>,
+    ) -> (Result<(ExecutionEffect, Vec<Event>), Error>, u64)
+    where
+        R::Error: Into<Error>;
+
+    /// Calls the contract stored under `contract_key` on behalf of the account under
+    /// `account_addr` in a context which rejects any change of the global state.
+    /// Returns the value passed to `ret` together with the gas used.
+    #[allow(clippy::too_many_arguments)]
+    fn call_view<R: StateReader<Key, Value>>(
+        &self,
+        contract_key: Key,
+        args: &[u8],
+        account_addr: [u8; 32],
+        gas_limit: u64,
+        block_context: BlockContext,
+        tc: Rc<RefCell<TrackingCopy<R>>>,
+        module_cache: &ModuleCache,
+    ) -> (Result<Vec<u8>, Error>, u64)
     where
         R::Error: Into<Error>;
 }
@@ -781,44 +1333,37 @@ impl Executor<Module> for WasmiExecutor {
         parity_module: Module,
         args: &[u8],
         account_addr: [u8; 32],
-        timestamp: u64,
-        nonce: u64,
+        rng: ChaChaRng,
         gas_limit: u64,
         protocol_version: u64,
+        block_context: BlockContext,
+        phase: Phase,
         tc: Rc<RefCell<TrackingCopy<R>>>,
-    ) -> (Result<ExecutionEffect, Error>, u64)
+        module_cache: &ModuleCache,
+        trace: Option<Rc<RefCell<Trace>>>,
+        profile: Option<Rc<RefCell<GasProfile>>>,
+    ) -> (Result<(ExecutionEffect, Vec<Event>), Error>, u64)
     where
         R::Error: Into<Error>,
     {
         let acct_key = Key::Account(account_addr);
-        let (instance, memory) = on_fail_charge!(
-            instance_and_memory(parity_module.clone(), protocol_version),
+        let module = on_fail_charge!(
+            wasmi::Module::from_parity_wasm_module(parity_module.clone()),
             0
         );
-        #[allow(unreachable_code)]
-        let validated_key = on_fail_charge!(Validated::new(acct_key, Validated::valid), 0);
-        let value = on_fail_charge! {
-            match tc.borrow_mut().get(&validated_key) {
-                Ok(None) => Err(Error::KeyNotFound(acct_key)),
-                Err(error) => Err(error.into()),
-                Ok(Some(value)) => Ok(value)
-            },
+        let (instance, memory) = on_fail_charge!(instance_and_memory(&module, protocol_version), 0);
+        let host_costs = on_fail_charge!(
+            HostCosts::from_version(protocol_version)
+                .ok_or(ResolverError::UnknownProtocolVersion(protocol_version)),
             0
-        };
-        let account = value.as_account();
+        );
+        let account = on_fail_charge!(load_account(&tc, account_addr), 0);
         let mut uref_lookup_local = account.urefs_lookup().clone();
         let known_urefs: HashMap<URefAddr, HashSet<AccessRights>> =
             vec_key_rights_to_map(uref_lookup_local.values().cloned());
-        let rng = create_rng(&account_addr, timestamp, nonce);
         let gas_counter = 0u64;
         let fn_store_id = 0u32;
-        let arguments: Vec<Vec<u8>> = if args.is_empty() {
-            Vec::new()
-        } else {
-            // TODO: figure out how this works with the cost model
-            // https://casperlabs.atlassian.net/browse/EE-239
-            on_fail_charge!(deserialize(args), 0)
-        };
+        let arguments = on_fail_charge!(deserialize_args(args), 0);
         let context = RuntimeContext::new(
             tc,
             &mut uref_lookup_local,
@@ -831,14 +1376,127 @@ impl Executor<Module> for WasmiExecutor {
             fn_store_id,
             rng,
             protocol_version,
+            // Session code is called by the account which sent the deploy.
+            acct_key,
+            block_context,
+            phase,
+            false,
+        );
+        let profiler = profile
+            .map(|profile| Profiler::new(profile, Vec::new(), &parity_module, instance.clone()));
+        let mut runtime = Runtime::new(
+            memory,
+            parity_module,
+            host_costs,
+            module_cache,
+            trace,
+            profiler,
+            context,
         );
-        let mut runtime = Runtime::new(memory, parity_module, context);
         on_fail_charge!(
-            instance.invoke_export("call", &[], &mut runtime),
+            instance
+                .invoke_export("call", &[], &mut runtime)
+                .map_err(interpreter_error),
             runtime.context.gas_counter()
         );
 
-        (Ok(runtime.context.effect()), runtime.context.gas_counter())
+        let events = runtime.context.take_events();
+        (
+            Ok((runtime.context.effect(), events)),
+            runtime.context.gas_counter(),
+        )
+    }
+
+    fn call_view<R: StateReader<Key, Value>>(
+        &self,
+        contract_key: Key,
+        args: &[u8],
+        account_addr: [u8; 32],
+        gas_limit: u64,
+        block_context: BlockContext,
+        tc: Rc<RefCell<TrackingCopy<R>>>,
+        module_cache: &ModuleCache,
+    ) -> (Result<Vec<u8>, Error>, u64)
+    where
+        R::Error: Into<Error>,
+    {
+        match contract_key {
+            Key::Hash(_) => {}
+            Key::URef(_, rights) if rights.is_readable() => {}
+            Key::URef(..) => {
+                let error = Error::InvalidAccess {
+                    required: AccessRights::READ,
+                };
+                return (Err(error), 0);
+            }
+            Key::Account(_) | Key::Local { .. } => {
+                let message = format!("Value at {:?} is not a contract", contract_key);
+                return (Err(Error::FunctionNotFound(message)), 0);
+            }
+        }
+        let acct_key = Key::Account(account_addr);
+        let account = on_fail_charge!(load_account(&tc, account_addr), 0);
+        let value = on_fail_charge!(read_value(&tc, contract_key), 0);
+        let (contract, parity_module, module) =
+            on_fail_charge!(load_contract(contract_key, value, module_cache), 0);
+        let protocol_version = contract.protocol_version();
+        let (instance, memory) = on_fail_charge!(instance_and_memory(&module, protocol_version), 0);
+        let host_costs = on_fail_charge!(
+            HostCosts::from_version(protocol_version)
+                .ok_or(ResolverError::UnknownProtocolVersion(protocol_version)),
+            0
+        );
+        let arguments = on_fail_charge!(deserialize_args(args), 0);
+        let mut refs = contract.urefs_lookup().clone();
+        let known_urefs = vec_key_rights_to_map(refs.values().cloned());
+        // Nothing can be stored by a view, so the rng only has to be deterministic.
+        let rng = create_rng(&account_addr, block_context.time, account.nonce());
+        let context = RuntimeContext::new(
+            Rc::clone(&tc),
+            &mut refs,
+            known_urefs,
+            arguments,
+            &account,
+            contract_key,
+            gas_limit,
+            0,
+            0,
+            rng,
+            protocol_version,
+            acct_key,
+            block_context,
+            Phase::Session,
+            true,
+        );
+        let mut runtime = Runtime::new(
+            memory,
+            parity_module,
+            host_costs,
+            module_cache,
+            None,
+            None,
+            context,
+        );
+        let result = instance.invoke_export("call", &[], &mut runtime);
+        let cost = runtime.context.gas_counter();
+        match result {
+            // The contract finished without calling `ret`, so it returned nothing.
+            Ok(_) => (Ok(runtime.result), cost),
+            Err(error) => {
+                let returned = match error
+                    .as_host_error()
+                    .and_then(|host_error| host_error.downcast_ref::<Error>())
+                {
+                    Some(Error::Ret(_)) => true,
+                    _ => false,
+                };
+                if returned {
+                    (Ok(runtime.result), cost)
+                } else {
+                    (Err(interpreter_error(error)), cost)
+                }
+            }
+        }
     }
 }
 
@@ -854,6 +1512,63 @@ pub fn key_to_tuple(key: Key) -> Option<([u8; 32], AccessRights)> {
     }
 }
 
+#[cfg(test)]
+mod interpreter_error_tests {
+    use super::{call_error, interpreter_error, CallError, Error, InterpreterError, Key};
+
+    #[test]
+    fn revert_status_is_extracted() {
+        let error = InterpreterError::Host(Box::new(Error::Revert(3)));
+        match interpreter_error(error) {
+            Error::Revert(3) => (),
+            other => panic!("Expected Revert(3), got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn overflow_is_extracted() {
+        let error = InterpreterError::Host(Box::new(Error::Overflow));
+        match interpreter_error(error) {
+            Error::Overflow => (),
+            other => panic!("Expected Overflow, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn gas_limit_is_extracted() {
+        let error = InterpreterError::Host(Box::new(Error::GasLimit));
+        match interpreter_error(error) {
+            Error::GasLimit => (),
+            other => panic!("Expected GasLimit, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn other_host_errors_are_kept() {
+        let error = InterpreterError::Host(Box::new(Error::InsufficientFunds));
+        match interpreter_error(error) {
+            Error::Interpreter(_) => (),
+            other => panic!("Expected an interpreter error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn call_errors_of_nested_calls() {
+        // A contract called by the callee ran out of gas.
+        let inner = InterpreterError::Host(Box::new(Error::GasLimit));
+        let outer = InterpreterError::Host(Box::new(Error::Interpreter(inner)));
+        assert_eq!(
+            call_error(&Error::Interpreter(outer)),
+            Some(CallError::OutOfGas)
+        );
+        assert_eq!(call_error(&Error::Revert(2)), Some(CallError::Revert(2)));
+        assert_eq!(
+            call_error(&Error::KeyNotFound(Key::Hash([0u8; 32]))),
+            Some(CallError::Failed)
+        );
+    }
+}
+
 #[cfg(test)]
 mod on_fail_charge_macro_tests {
     struct Counter {
@@ -904,3 +1619,33 @@ mod on_fail_charge_macro_tests {
         assert_eq!(cntr.counter, expected_value) // test that lambda executed
     }
 }
+
+#[cfg(test)]
+mod create_rng_tests {
+    use rand::RngCore;
+
+    use super::{create_deploy_rng, create_rng};
+
+    fn first_word(account_addr: &[u8; 32], timestamp: u64, nonce: u64) -> u64 {
+        create_rng(account_addr, timestamp, nonce).next_u64()
+    }
+
+    #[test]
+    fn rng_depends_on_its_input() {
+        let word = first_word(&[0u8; 32], 0, 0);
+        assert_eq!(word, first_word(&[0u8; 32], 0, 0));
+        assert_ne!(word, first_word(&[1u8; 32], 0, 0));
+        assert_ne!(word, first_word(&[0u8; 32], 1, 0));
+        assert_ne!(word, first_word(&[0u8; 32], 0, 1));
+    }
+
+    #[test]
+    fn deploy_rng_depends_on_the_protocol_version() {
+        let v1_word = create_deploy_rng(&[1u8; 32], 1, 1, 1).next_u64();
+        assert_eq!(v1_word, create_deploy_rng(&[0u8; 32], 0, 0, 1).next_u64());
+        assert_eq!(
+            create_deploy_rng(&[1u8; 32], 1, 1, 2).next_u64(),
+            first_word(&[1u8; 32], 1, 1)
+        );
+    }
+}
@@ -18,3 +18,82 @@ pub const STORE_FN_INDEX: usize = 16;
 pub const PROTOCOL_VERSION_FUNC_INDEX: usize = 17;
 pub const SEED_FN_INDEX: usize = 18;
 pub const IS_VALID_FN_INDEX: usize = 19;
+pub const TRANSFER_TO_ACCOUNT_FUNC_INDEX: usize = 20;
+pub const EMIT_EVENT_FUNC_INDEX: usize = 21;
+pub const STORE_UPGRADABLE_FN_INDEX: usize = 22;
+pub const UPGRADE_CONTRACT_FUNC_INDEX: usize = 23;
+pub const REMOVE_UREF_FUNC_INDEX: usize = 24;
+pub const GET_BLOCKTIME_FUNC_INDEX: usize = 25;
+pub const GET_BLOCK_HEIGHT_FUNC_INDEX: usize = 26;
+pub const LOAD_CALLER_FUNC_INDEX: usize = 27;
+pub const GET_CALLER_FUNC_INDEX: usize = 28;
+pub const GET_PHASE_FUNC_INDEX: usize = 29;
+pub const REVERT_FUNC_INDEX: usize = 30;
+pub const TRY_CALL_CONTRACT_FUNC_INDEX: usize = 31;
+
+/// Names under which contracts import the host functions, with their indices.
+const FUNCTIONS: [(&str, usize); 32] = [
+    ("write", WRITE_FUNC_INDEX),
+    ("read_value", READ_FUNC_INDEX),
+    ("add", ADD_FUNC_INDEX),
+    ("new_uref", NEW_FUNC_INDEX),
+    ("get_read", GET_READ_FUNC_INDEX),
+    ("serialize_function", SER_FN_FUNC_INDEX),
+    ("get_function", GET_FN_FUNC_INDEX),
+    ("load_arg", LOAD_ARG_FUNC_INDEX),
+    ("get_arg", GET_ARG_FUNC_INDEX),
+    ("ret", RET_FUNC_INDEX),
+    ("get_call_result", GET_CALL_RESULT_FUNC_INDEX),
+    ("call_contract", CALL_CONTRACT_FUNC_INDEX),
+    ("get_uref", GET_UREF_FUNC_INDEX),
+    ("gas", GAS_FUNC_INDEX),
+    ("has_uref_name", HAS_UREF_FUNC_INDEX),
+    ("add_uref", ADD_UREF_FUNC_INDEX),
+    ("store_function", STORE_FN_INDEX),
+    ("protocol_version", PROTOCOL_VERSION_FUNC_INDEX),
+    ("seed", SEED_FN_INDEX),
+    ("is_valid", IS_VALID_FN_INDEX),
+    ("transfer_to_account", TRANSFER_TO_ACCOUNT_FUNC_INDEX),
+    ("emit_event", EMIT_EVENT_FUNC_INDEX),
+    ("store_upgradable_function", STORE_UPGRADABLE_FN_INDEX),
+    ("upgrade_contract", UPGRADE_CONTRACT_FUNC_INDEX),
+    ("remove_uref", REMOVE_UREF_FUNC_INDEX),
+    ("get_blocktime", GET_BLOCKTIME_FUNC_INDEX),
+    ("get_block_height", GET_BLOCK_HEIGHT_FUNC_INDEX),
+    ("load_caller", LOAD_CALLER_FUNC_INDEX),
+    ("get_caller", GET_CALLER_FUNC_INDEX),
+    ("get_phase", GET_PHASE_FUNC_INDEX),
+    ("revert", REVERT_FUNC_INDEX),
+    ("try_call_contract", TRY_CALL_CONTRACT_FUNC_INDEX),
+];
+
+/// Name under which the host function `index` is imported by contracts.
+pub fn function_name(index: usize) -> Option<&'static str> {
+    FUNCTIONS
+        .iter()
+        .find(|(_, function_index)| *function_index == index)
+        .map(|(name, _)| *name)
+}
+
+/// Index of the host function imported by contracts under `name`.
+pub fn function_index(name: &str) -> Option<usize> {
+    FUNCTIONS
+        .iter()
+        .find(|(function_name, _)| *function_name == name)
+        .map(|(_, index)| *index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{function_index, function_name, FUNCTIONS};
+
+    #[test]
+    fn every_function_has_its_own_index() {
+        for (index, (name, function_index)) in FUNCTIONS.iter().enumerate() {
+            assert_eq!(index, *function_index);
+            assert_eq!(function_name(index), Some(*name));
+        }
+        assert_eq!(function_name(FUNCTIONS.len()), None);
+        assert_eq!(function_index("unknown"), None);
+    }
+}
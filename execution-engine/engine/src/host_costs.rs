@@ -0,0 +1,97 @@
+use wasm_prep::wasm_costs::WasmCosts;
+
+use functions::{
+    ADD_FUNC_INDEX, ADD_UREF_FUNC_INDEX, CALL_CONTRACT_FUNC_INDEX, GAS_FUNC_INDEX,
+    HAS_UREF_FUNC_INDEX, NEW_FUNC_INDEX, READ_FUNC_INDEX, REMOVE_UREF_FUNC_INDEX,
+    SER_FN_FUNC_INDEX, STORE_FN_INDEX, STORE_UPGRADABLE_FN_INDEX, TRANSFER_TO_ACCOUNT_FUNC_INDEX,
+    TRY_CALL_CONTRACT_FUNC_INDEX, UPGRADE_CONTRACT_FUNC_INDEX, WRITE_FUNC_INDEX,
+};
+
+/// Costs of calling host functions, charged before the host function runs.
+/// The cost of a call is its base cost plus `memcpy` for every byte copied
+/// between Wasm memory and the host. Bytes which a deploy adds to the global
+/// state are charged separately, with `storage` per byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HostCosts {
+    /// Base cost of host functions which only use the runtime context.
+    pub regular: u32,
+    /// Base cost of host functions which read from the global state.
+    pub read: u32,
+    /// Base cost of host functions which write to the global state.
+    pub write: u32,
+    /// Base cost of calling a contract, on top of the gas used by the contract itself.
+    pub call: u32,
+    /// Base cost of storing a new contract or upgrading an existing one.
+    pub store: u32,
+    /// Memory copy cost, per byte
+    pub memcpy: u32,
+    /// Cost of every byte added to the global state
+    pub storage: u32,
+}
+
+impl HostCosts {
+    pub fn from_version(protocol_version: u64) -> Option<HostCosts> {
+        let wasm_costs = WasmCosts::from_version(protocol_version)?;
+        match protocol_version {
+            1 | 2 => Some(HostCosts {
+                regular: 10,
+                read: 100,
+                write: 200,
+                call: 1_000,
+                store: 5_000,
+                memcpy: wasm_costs.memcpy,
+                storage: 10,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Base cost of the host function with the given `index`.
+    pub fn base_cost(&self, index: usize) -> u32 {
+        match index {
+            // Gas is charged by the `gas` function itself, so charging for it would never end.
+            GAS_FUNC_INDEX => 0,
+            READ_FUNC_INDEX | HAS_UREF_FUNC_INDEX | SER_FN_FUNC_INDEX => self.read,
+            WRITE_FUNC_INDEX
+            | ADD_FUNC_INDEX
+            | NEW_FUNC_INDEX
+            | ADD_UREF_FUNC_INDEX
+            | REMOVE_UREF_FUNC_INDEX
+            | TRANSFER_TO_ACCOUNT_FUNC_INDEX => self.write,
+            CALL_CONTRACT_FUNC_INDEX | TRY_CALL_CONTRACT_FUNC_INDEX => self.call,
+            STORE_FN_INDEX | STORE_UPGRADABLE_FN_INDEX | UPGRADE_CONTRACT_FUNC_INDEX => self.store,
+            _ => self.regular,
+        }
+    }
+
+    /// Cost of a call to the host function with the given `index`,
+    /// which copies `bytes` between Wasm memory and the host.
+    pub fn cost(&self, index: usize, bytes: u64) -> u64 {
+        if index == GAS_FUNC_INDEX {
+            return 0;
+        }
+        u64::from(self.base_cost(index)).saturating_add(bytes.saturating_mul(self.memcpy.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HostCosts;
+    use functions::{GAS_FUNC_INDEX, READ_FUNC_INDEX, WRITE_FUNC_INDEX};
+
+    #[test]
+    fn unknown_protocol_version_has_no_costs() {
+        assert!(HostCosts::from_version(0).is_none());
+    }
+
+    #[test]
+    fn cost_includes_copied_bytes() {
+        let costs = HostCosts::from_version(1).unwrap();
+        assert_eq!(
+            costs.cost(WRITE_FUNC_INDEX, 100),
+            u64::from(costs.write) + 100 * u64::from(costs.memcpy)
+        );
+        assert_eq!(costs.cost(READ_FUNC_INDEX, 0), u64::from(costs.read));
+        assert_eq!(costs.cost(GAS_FUNC_INDEX, 100), 0);
+    }
+}
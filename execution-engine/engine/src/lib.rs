@@ -6,9 +6,11 @@ extern crate core;
 
 // third-party dependencies
 extern crate blake2;
+extern crate crossbeam_utils;
 extern crate failure;
 extern crate itertools;
 extern crate linked_hash_map;
+extern crate num_cpus;
 extern crate parity_wasm;
 extern crate parking_lot;
 extern crate pwasm_utils;
@@ -28,16 +30,23 @@ extern crate wasm_prep;
 extern crate matches;
 #[cfg(test)]
 extern crate proptest;
+#[cfg(test)]
+extern crate wabt;
 
 pub mod args;
 pub mod byte_size;
 pub mod engine_state;
 pub mod execution;
 pub mod functions;
+pub mod host_costs;
 pub mod meter;
+pub mod module_cache;
+pub mod profile;
 pub mod resolvers;
 pub mod runtime_context;
+pub mod trace;
 pub mod tracking_copy;
+pub mod transfer;
 
 mod utils;
 
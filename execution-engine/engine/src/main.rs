@@ -10,17 +10,23 @@ extern crate shared;
 extern crate storage;
 extern crate wasm_prep;
 
+use std::cell::RefCell;
 use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::prelude::*;
 use std::iter::Iterator;
+use std::rc::Rc;
 
 use clap::{App, Arg, ArgMatches};
 
+use execution_engine::engine_state::block_context::BlockContext;
 use execution_engine::engine_state::error::RootNotFound;
 use execution_engine::engine_state::execution_result::ExecutionResult;
 use execution_engine::engine_state::EngineState;
 use execution_engine::execution::WasmiExecutor;
+use execution_engine::host_costs::HostCosts;
+use execution_engine::profile::GasProfile;
+use execution_engine::trace::Trace;
 use shared::init::mocked_account;
 use shared::logging;
 use shared::logging::log_level::LogLevel;
@@ -44,6 +50,15 @@ const ARG_LOG_LEVEL: &str = "loglevel";
 const ARG_LOG_LEVEL_VALUE: &str = "LOGLEVEL";
 const ARG_LOG_LEVEL_HELP: &str = "[ fatal | error | warning | info | debug ]";
 
+// trace
+const ARG_TRACE: &str = "trace";
+const ARG_TRACE_HELP: &str = "Logs the host calls made by each deploy as JSON";
+
+// profile
+const ARG_PROFILE: &str = "profile";
+const ARG_PROFILE_VALUE: &str = "FILE";
+const ARG_PROFILE_HELP: &str = "Writes the gas used by each function to FILE as folded stacks";
+
 // defaults
 const DEFAULT_ADDRESS: &str = "00000000000000000000000000000000";
 const DEFAULT_GAS_LIMIT: &str = "18446744073709551615";
@@ -116,8 +131,9 @@ fn main() {
 
     // TODO: move to arg parser
     let timestamp: u64 = 100_000;
-    let nonce: u64 = 1;
+    let mut nonce: u64 = 1;
     let protocol_version: u64 = 1;
+    let block_context = BlockContext::new(timestamp, 0, [0u8; 32]);
 
     // let path = std::path::Path::new("./tmp/");
     // TODO: Better error handling?
@@ -135,9 +151,34 @@ fn main() {
             protocol_version
         )
     });
-    let wasmi_preprocessor: WasmiPreprocessor = WasmiPreprocessor::new(wasm_costs);
+    let host_costs = HostCosts::from_version(protocol_version).unwrap_or_else(|| {
+        panic!(
+            "Host cost table wasn't defined for protocol version: {}",
+            protocol_version
+        )
+    });
+    log_cost_table(protocol_version, &wasm_costs, &host_costs);
+    let profile_path = matches.value_of(ARG_PROFILE);
+    let wasmi_preprocessor: WasmiPreprocessor = if profile_path.is_some() {
+        WasmiPreprocessor::with_profiling(wasm_costs)
+    } else {
+        WasmiPreprocessor::new(wasm_costs)
+    };
+
+    let trace_enabled = matches.is_present(ARG_TRACE);
+    let mut folded_stacks = String::new();
 
     for wasm_bytes in wasm_files.iter() {
+        let trace = if trace_enabled {
+            Some(Rc::new(RefCell::new(Trace::default())))
+        } else {
+            None
+        };
+        let profile = if profile_path.is_some() {
+            Some(Rc::new(RefCell::new(GasProfile::default())))
+        } else {
+            None
+        };
         let result = engine_state.run_deploy(
             &wasm_bytes.bytes,
             &[], // TODO: consume args from CLI
@@ -147,8 +188,11 @@ fn main() {
             state_hash,
             gas_limit,
             protocol_version,
+            block_context,
             &wasmi_executor,
             &wasmi_preprocessor,
+            trace.clone(),
+            profile.clone(),
         );
 
         let mut log_level = LogLevel::Info;
@@ -164,13 +208,16 @@ fn main() {
                 error_message = format!("root {:?} not found", hash);
                 properties.insert(String::from("root-hash"), format!("{:?}", hash));
             }
-            Ok(ExecutionResult {
-                result: Ok(effects),
+            Ok(ExecutionResult::Success {
+                effect,
                 cost,
+                storage_cost,
+                ..
             }) => {
                 properties.insert("gas-cost".to_string(), format!("{:?}", cost));
+                properties.insert("storage-cost".to_string(), format!("{:?}", storage_cost));
 
-                match engine_state.apply_effect(state_hash, effects.1) {
+                match engine_state.apply_effect(state_hash, effect.1, protocol_version) {
                     Ok(CommitResult::RootNotFound) => {
                         log_level = LogLevel::Warning;
                         error_message = format!("root {:?} not found", state_hash);
@@ -184,8 +231,13 @@ fn main() {
                         log_level = LogLevel::Warning;
                         error_message = format!("type mismatch: {:?} ", type_mismatch);
                     }
+                    Ok(CommitResult::Overflow) => {
+                        log_level = LogLevel::Warning;
+                        error_message = String::from("arithmetic overflow");
+                    }
                     Ok(CommitResult::Success(new_root_hash)) => {
                         state_hash = new_root_hash; // we need to keep updating the post state hash after each deploy
+                        nonce += 1;
                         properties.insert(
                             String::from("post-state-hash"),
                             format!("{:?}", new_root_hash),
@@ -197,13 +249,24 @@ fn main() {
                     }
                 }
             }
-            Ok(ExecutionResult {
-                result: Err(error),
+            Ok(ExecutionResult::Failure {
+                error,
+                effect,
                 cost,
             }) => {
                 log_level = LogLevel::Error;
                 properties.insert("gas-cost".to_string(), format!("{:?}", cost));
                 error_message = format!("{:?}", error);
+
+                // A charged failure still consumes the nonce of the account.
+                if !effect.1.is_empty() {
+                    if let Ok(CommitResult::Success(new_root_hash)) =
+                        engine_state.apply_effect(state_hash, effect.1, protocol_version)
+                    {
+                        state_hash = new_root_hash;
+                        nonce += 1;
+                    }
+                }
             }
         }
 
@@ -214,18 +277,71 @@ fn main() {
             properties.insert(String::from("error"), error_message);
         }
 
-        let message_format: String = if success {
+        let mut message_format: String = if success {
             String::from("{wasm-path} success: {success} gas_cost: {gas-cost}")
         } else {
             String::from("{wasm-path} error: {error} gas_cost: {gas-cost}")
         };
 
+        if let Some(trace) = trace {
+            properties.insert(String::from("trace"), trace.borrow().to_json());
+            message_format.push_str(" trace: {trace}");
+        }
+
         logging::log_details(log_level, message_format, properties);
+
+        if let Some(profile) = profile {
+            // Stacks of each deploy start with the path of its Wasm file.
+            let deploy_frame = wasm_bytes
+                .path
+                .replace(|c: char| c == ';' || c.is_whitespace(), "_");
+            for line in profile.borrow().to_folded().lines() {
+                folded_stacks.push_str(&format!("{};{}\n", deploy_frame, line));
+            }
+        }
+    }
+
+    if let Some(path) = profile_path {
+        std::fs::write(path, folded_stacks).expect("Cannot write gas profile");
     }
 
     logging::log_info(SERVER_STOP_MESSAGE);
 }
 
+/// Logs the cost table used to execute the deploys
+fn log_cost_table(protocol_version: u64, wasm_costs: &WasmCosts, host_costs: &HostCosts) {
+    let costs: Vec<(&str, u32)> = vec![
+        ("regular", wasm_costs.regular),
+        ("div", wasm_costs.div),
+        ("mul", wasm_costs.mul),
+        ("mem", wasm_costs.mem),
+        ("initial-mem", wasm_costs.initial_mem),
+        ("max-mem", wasm_costs.max_mem),
+        ("grow-mem", wasm_costs.grow_mem),
+        ("memcpy", wasm_costs.memcpy),
+        ("max-stack-height", wasm_costs.max_stack_height),
+        ("opcodes-mul", wasm_costs.opcodes_mul),
+        ("opcodes-div", wasm_costs.opcodes_div),
+        ("host-regular", host_costs.regular),
+        ("host-read", host_costs.read),
+        ("host-write", host_costs.write),
+        ("host-call", host_costs.call),
+        ("host-store", host_costs.store),
+        ("host-storage", host_costs.storage),
+    ];
+    let mut properties: BTreeMap<String, String> = BTreeMap::new();
+    properties.insert(
+        String::from("protocol-version"),
+        format!("{}", protocol_version),
+    );
+    let mut message_format = String::from("cost table for protocol version {protocol-version}:");
+    for (name, cost) in costs {
+        message_format.push_str(&format!(" {}: {{{}}}", name, name));
+        properties.insert(name.to_string(), format!("{}", cost));
+    }
+    logging::log_details(LogLevel::Info, message_format, properties);
+}
+
 /// Sets panic hook for logging panic info
 fn set_panic_hook() {
     let hook: Box<dyn Fn(&std::panic::PanicInfo) + 'static + Sync + Send> =
@@ -273,6 +389,21 @@ fn get_args() -> ArgMatches<'static> {
                 .required(true)
                 .index(1),
         )
+        .arg(
+            Arg::with_name(ARG_TRACE)
+                .required(false)
+                .long(ARG_TRACE)
+                .takes_value(false)
+                .help(ARG_TRACE_HELP),
+        )
+        .arg(
+            Arg::with_name(ARG_PROFILE)
+                .required(false)
+                .long(ARG_PROFILE)
+                .takes_value(true)
+                .value_name(ARG_PROFILE_VALUE)
+                .help(ARG_PROFILE_HELP),
+        )
         .arg(
             Arg::with_name(ARG_LOG_LEVEL)
                 .required(false)
@@ -0,0 +1,246 @@
+use std::sync::Arc;
+
+use linked_hash_map::LinkedHashMap;
+use parity_wasm::elements::Module;
+use parking_lot::Mutex;
+use wasmi;
+
+use shared::newtypes::Blake2bHash;
+
+/// Default limit of the total size of the code cached by `ModuleCache`, in bytes.
+pub const DEFAULT_MAX_CACHE_SIZE: usize = 64 * 1024 * 1024;
+
+/// Where the code of a cached module comes from. The same bytes are turned into
+/// different modules depending on their kind, so the kind is part of the cache key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModuleKind {
+    /// Code of a deploy, which is preprocessed before it is executed.
+    Deploy,
+    /// Code of a stored contract, which was preprocessed by the deploy that stored it
+    /// and is only deserialized.
+    Stored,
+}
+
+type CacheKey = (ModuleKind, Blake2bHash, u64);
+
+struct CacheEntry {
+    module: Module,
+    /// The `wasmi` module validated from `module`, once it was asked for.
+    validated: Option<Arc<wasmi::Module>>,
+    code_size: usize,
+}
+
+struct Entries {
+    modules: LinkedHashMap<CacheKey, CacheEntry>,
+    current_size: usize,
+}
+
+/// Keeps deserialized and instrumented Wasm modules, so that the code of popular contracts
+/// isn't deserialized and preprocessed again on every call.
+/// Modules are keyed by their kind, the hash of their code bytes and the protocol version,
+/// as preprocessing depends on the costs of that version. Above `max_size` bytes of
+/// cached code, least-recently-used modules are evicted.
+///
+/// The cache is shared by all deploys executed by an `EngineState`. Next to the parity-wasm
+/// modules it keeps the `wasmi` modules validated from them, so that the modules of stored
+/// contracts aren't validated and compiled again on every call. Module instances can't be
+/// shared across threads, so they are still created on every call.
+pub struct ModuleCache {
+    max_size: usize,
+    entries: Mutex<Entries>,
+}
+
+impl ModuleCache {
+    pub fn new(max_size: usize) -> ModuleCache {
+        ModuleCache {
+            max_size,
+            entries: Mutex::new(Entries {
+                modules: LinkedHashMap::new(),
+                current_size: 0,
+            }),
+        }
+    }
+
+    /// Returns the module of the given `kind` built from `code` for `protocol_version`,
+    /// building it with `build` and caching it if it isn't in the cache yet.
+    pub fn get_or_insert_with<F, E>(
+        &self,
+        kind: ModuleKind,
+        code: &[u8],
+        protocol_version: u64,
+        build: F,
+    ) -> Result<Module, E>
+    where
+        F: FnOnce() -> Result<Module, E>,
+    {
+        let key = (kind, Blake2bHash::new(code), protocol_version);
+        if let Some(entry) = self.entries.lock().modules.get_refresh(&key) {
+            return Ok(entry.module.clone());
+        }
+        // The lock isn't held while building the module, so that other deploys
+        // can use the cache in the meantime.
+        let module = build()?;
+        self.insert(key, module.clone(), None, code.len());
+        Ok(module)
+    }
+
+    /// Like `get_or_insert_with`, but also returns the `wasmi` module validated from the
+    /// module, validating it and caching the result if it isn't in the cache yet.
+    pub fn get_or_insert_validated<F, E>(
+        &self,
+        kind: ModuleKind,
+        code: &[u8],
+        protocol_version: u64,
+        build: F,
+    ) -> Result<(Module, Arc<wasmi::Module>), E>
+    where
+        F: FnOnce() -> Result<Module, E>,
+        E: From<wasmi::Error>,
+    {
+        let key = (kind, Blake2bHash::new(code), protocol_version);
+        let cached = self
+            .entries
+            .lock()
+            .modules
+            .get_refresh(&key)
+            .map(|entry| (entry.module.clone(), entry.validated.clone()));
+        let module = match cached {
+            Some((module, Some(validated))) => return Ok((module, validated)),
+            Some((module, None)) => module,
+            None => build()?,
+        };
+        let validated = Arc::new(wasmi::Module::from_parity_wasm_module(module.clone())?);
+        self.insert(
+            key,
+            module.clone(),
+            Some(Arc::clone(&validated)),
+            code.len(),
+        );
+        Ok((module, validated))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().modules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.lock().modules.is_empty()
+    }
+
+    fn insert(
+        &self,
+        key: CacheKey,
+        module: Module,
+        validated: Option<Arc<wasmi::Module>>,
+        code_size: usize,
+    ) {
+        if code_size > self.max_size {
+            return;
+        }
+        let mut entries = self.entries.lock();
+        let entry = CacheEntry {
+            module,
+            validated,
+            code_size,
+        };
+        if let Some(replaced) = entries.modules.insert(key, entry) {
+            entries.current_size -= replaced.code_size;
+        }
+        entries.current_size += code_size;
+        while entries.current_size > self.max_size {
+            match entries.modules.pop_front() {
+                Some((_, evicted)) => entries.current_size -= evicted.code_size,
+                None => break,
+            }
+        }
+    }
+}
+
+impl Default for ModuleCache {
+    fn default() -> ModuleCache {
+        ModuleCache::new(DEFAULT_MAX_CACHE_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use parity_wasm::elements::Module;
+    use wasmi;
+
+    use super::{ModuleCache, ModuleKind};
+
+    fn build(built: &mut usize) -> Result<Module, ()> {
+        *built += 1;
+        Ok(Module::default())
+    }
+
+    #[test]
+    fn module_is_built_once_per_code_and_protocol_version() {
+        let cache = ModuleCache::new(1024);
+        let mut built = 0;
+        let _ = cache.get_or_insert_with(ModuleKind::Deploy, &[1u8; 10], 1, || build(&mut built));
+        let _ = cache.get_or_insert_with(ModuleKind::Deploy, &[1u8; 10], 1, || build(&mut built));
+        assert_eq!(built, 1);
+        let _ = cache.get_or_insert_with(ModuleKind::Deploy, &[1u8; 10], 2, || build(&mut built));
+        let _ = cache.get_or_insert_with(ModuleKind::Deploy, &[2u8; 10], 1, || build(&mut built));
+        assert_eq!(built, 3);
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn modules_of_different_kinds_are_cached_separately() {
+        let cache = ModuleCache::new(1024);
+        let mut built = 0;
+        let _ = cache.get_or_insert_with(ModuleKind::Deploy, &[1u8; 10], 1, || build(&mut built));
+        let _ = cache.get_or_insert_with(ModuleKind::Stored, &[1u8; 10], 1, || build(&mut built));
+        assert_eq!(built, 2);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn validated_module_is_cached() {
+        let cache = ModuleCache::new(1024);
+        let mut built = 0;
+        let mut validated = Vec::new();
+        for _ in 0..2 {
+            let (_, module) = cache
+                .get_or_insert_validated(ModuleKind::Stored, &[1u8; 10], 1, || {
+                    built += 1;
+                    Ok::<_, wasmi::Error>(Module::default())
+                })
+                .unwrap();
+            validated.push(module);
+        }
+        assert_eq!(built, 1);
+        assert!(Arc::ptr_eq(&validated[0], &validated[1]));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn failures_are_not_cached() {
+        let cache = ModuleCache::new(1024);
+        let result = cache.get_or_insert_with(ModuleKind::Deploy, &[1u8; 10], 1, || {
+            Err::<Module, _>("invalid")
+        });
+        assert!(result.is_err());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn least_recently_used_modules_are_evicted() {
+        let cache = ModuleCache::new(25);
+        let mut built = 0;
+        let _ = cache.get_or_insert_with(ModuleKind::Deploy, &[1u8; 10], 1, || build(&mut built));
+        let _ = cache.get_or_insert_with(ModuleKind::Deploy, &[2u8; 10], 1, || build(&mut built));
+        // refreshes the first module, so the second one is evicted
+        let _ = cache.get_or_insert_with(ModuleKind::Deploy, &[1u8; 10], 1, || build(&mut built));
+        let _ = cache.get_or_insert_with(ModuleKind::Deploy, &[3u8; 10], 1, || build(&mut built));
+        assert_eq!(cache.len(), 2);
+        let _ = cache.get_or_insert_with(ModuleKind::Deploy, &[1u8; 10], 1, || build(&mut built));
+        assert_eq!(built, 3);
+        let _ = cache.get_or_insert_with(ModuleKind::Deploy, &[2u8; 10], 1, || build(&mut built));
+        assert_eq!(built, 4);
+    }
+}
@@ -0,0 +1,152 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use parity_wasm::elements::Module;
+use wasmi::{ModuleRef, RuntimeValue};
+
+use common::key::Key;
+use wasm_prep::profiling::{self, PROFILED_FUNCTION_GLOBAL};
+
+/// Gas used by a deploy, attributed to stacks of frames: the contracts and Wasm functions
+/// being executed and the host functions they called.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GasProfile {
+    stacks: BTreeMap<Vec<String>, u64>,
+}
+
+impl GasProfile {
+    pub fn add(&mut self, stack: Vec<String>, gas: u64) {
+        let total = self.stacks.entry(stack).or_insert(0);
+        *total = total.saturating_add(gas);
+    }
+
+    pub fn total(&self) -> u64 {
+        self.stacks
+            .values()
+            .fold(0u64, |sum, gas| sum.saturating_add(*gas))
+    }
+
+    /// Returns the profile in the folded stacks format used by flame graph tools:
+    /// one line per stack, with frames separated by semicolons, followed by the gas.
+    pub fn to_folded(&self) -> String {
+        self.stacks
+            .iter()
+            .map(|(stack, gas)| format!("{} {}\n", stack.join(";"), gas))
+            .collect()
+    }
+}
+
+/// Attributes the gas used by a single Wasm instance to its functions.
+pub struct Profiler {
+    profile: Rc<RefCell<GasProfile>>,
+    // Frames of the callers of the instance
+    frames: Vec<String>,
+    instance: ModuleRef,
+    function_names: BTreeMap<u32, String>,
+}
+
+impl Profiler {
+    pub fn new(
+        profile: Rc<RefCell<GasProfile>>,
+        frames: Vec<String>,
+        module: &Module,
+        instance: ModuleRef,
+    ) -> Profiler {
+        Profiler {
+            profile,
+            frames,
+            instance,
+            function_names: profiling::function_names(module),
+        }
+    }
+
+    /// Name of the Wasm function being executed. Modules which weren't instrumented
+    /// for profiling are treated as a single function.
+    fn current_function(&self) -> String {
+        let index = self
+            .instance
+            .export_by_name(PROFILED_FUNCTION_GLOBAL)
+            .and_then(|export| export.as_global().map(|global| global.get()));
+        match index {
+            Some(RuntimeValue::I32(index)) if index >= 0 => {
+                let index = index as u32;
+                self.function_names
+                    .get(&index)
+                    .map(|name| sanitize(name))
+                    .unwrap_or_else(|| format!("function[{}]", index))
+            }
+            _ => String::from("wasm"),
+        }
+    }
+
+    fn stack(&self) -> Vec<String> {
+        let mut stack = self.frames.clone();
+        stack.push(self.current_function());
+        stack
+    }
+
+    /// Attributes `gas` charged by the injected gas counter to the current function.
+    pub fn record_wasm(&self, gas: u64) {
+        self.profile.borrow_mut().add(self.stack(), gas);
+    }
+
+    /// Attributes `gas` charged for a call to `host_function` to the current function.
+    pub fn record_host(&self, host_function: &str, gas: u64) {
+        let mut stack = self.stack();
+        stack.push(format!("host:{}", host_function));
+        self.profile.borrow_mut().add(stack, gas);
+    }
+
+    /// Returns the profiler of the contract under `key`, called from the current function.
+    pub fn callee(&self, key: &Key, module: &Module, instance: ModuleRef) -> Profiler {
+        let mut frames = self.stack();
+        frames.push(key_frame(key));
+        Profiler::new(Rc::clone(&self.profile), frames, module, instance)
+    }
+}
+
+/// Frame names can't contain the separators of the folded stacks format.
+fn sanitize(name: &str) -> String {
+    name.replace(|c: char| c == ';' || c.is_whitespace(), "_")
+}
+
+fn key_frame(key: &Key) -> String {
+    let (kind, addr) = match key {
+        Key::Account(addr) => ("account", addr),
+        Key::Hash(addr) => ("hash", addr),
+        Key::URef(addr, _) => ("uref", addr),
+        Key::Local { key_hash, .. } => ("local", key_hash),
+    };
+    let hex: String = addr.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("{}:{}", kind, hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{key_frame, sanitize, GasProfile};
+    use common::key::Key;
+
+    #[test]
+    fn folded_stacks() {
+        let mut profile = GasProfile::default();
+        let stack = |frames: &[&str]| frames.iter().map(|frame| frame.to_string()).collect();
+        profile.add(stack(&["session", "call"]), 10);
+        profile.add(stack(&["session", "call", "host:write"]), 200);
+        profile.add(stack(&["session", "call"]), 5);
+        assert_eq!(profile.total(), 215);
+        assert_eq!(
+            profile.to_folded(),
+            "session;call 15\nsession;call;host:write 200\n"
+        );
+    }
+
+    #[test]
+    fn frames_have_no_separators() {
+        assert_eq!(sanitize("a b;c"), "a_b_c");
+        assert_eq!(
+            key_frame(&Key::Hash([255u8; 32])),
+            format!("hash:{}", "ff".repeat(32))
+        );
+    }
+}
@@ -2,19 +2,26 @@ pub mod error;
 pub mod memory_resolver;
 mod resolver_v1;
 
+use wasm_prep::wasm_costs::WasmCosts;
 use wasmi::ModuleImportResolver;
 
 use self::error::ResolverError;
 use resolvers::memory_resolver::MemoryResolver;
 
 /// Creates a module resolver for given protocol version.
+/// Memory limits of the modules are taken from the Wasm costs of that version.
 ///
 /// * `protocol_version` Version of the protocol. Can't be lower than 1.
 pub fn create_module_resolver(
     protocol_version: u64,
 ) -> Result<impl ModuleImportResolver + MemoryResolver, ResolverError> {
+    let wasm_costs = WasmCosts::from_version(protocol_version)
+        .ok_or(ResolverError::UnknownProtocolVersion(protocol_version))?;
     match protocol_version {
-        1 => Ok(resolver_v1::RuntimeModuleImportResolver::default()),
+        1 | 2 => Ok(resolver_v1::RuntimeModuleImportResolver::new(
+            wasm_costs.initial_mem,
+            wasm_costs.max_mem,
+        )),
         _ => Err(ResolverError::UnknownProtocolVersion(protocol_version)),
     }
 }
@@ -28,3 +35,55 @@ fn resolve_invalid_module() {
 fn protocol_version_1_always_resolves() {
     assert!(create_module_resolver(1).is_ok());
 }
+
+#[test]
+fn protocol_version_2_resolves() {
+    assert!(create_module_resolver(2).is_ok());
+}
+
+#[cfg(test)]
+fn instantiate(wat: &str) -> Result<::wasmi::ModuleRef, ::wasmi::Error> {
+    let module = ::wasmi::Module::from_buffer(::wabt::wat2wasm(wat).unwrap())?;
+    let resolver = create_module_resolver(1).unwrap();
+    let imports = ::wasmi::ImportsBuilder::new().with_resolver("env", &resolver);
+    Ok(::wasmi::ModuleInstance::new(&module, &imports)?.assert_no_start())
+}
+
+#[test]
+fn memory_above_limit_is_rejected() {
+    let max_mem = WasmCosts::from_version(1).unwrap().max_mem;
+    let initial = format!(
+        r#"(module (import "env" "memory" (memory {})))"#,
+        max_mem + 1
+    );
+    assert!(instantiate(&initial).is_err());
+    let maximum = format!(
+        r#"(module (import "env" "memory" (memory 1 {})))"#,
+        max_mem + 1
+    );
+    assert!(instantiate(&maximum).is_err());
+}
+
+#[test]
+fn growing_memory_past_limit_returns_minus_one() {
+    let max_mem = WasmCosts::from_version(1).unwrap().max_mem;
+    let instance = instantiate(
+        r#"(module
+            (import "env" "memory" (memory 1))
+            (func (export "grow") (param i32) (result i32)
+                (grow_memory (get_local 0))))"#,
+    )
+    .unwrap();
+    let grow = |pages: u32| {
+        instance
+            .invoke_export(
+                "grow",
+                &[::wasmi::RuntimeValue::I32(pages as i32)],
+                &mut ::wasmi::NopExternals,
+            )
+            .unwrap()
+    };
+    // returns the previous number of pages
+    assert_eq!(grow(max_mem - 1), Some(::wasmi::RuntimeValue::I32(1)));
+    assert_eq!(grow(1), Some(::wasmi::RuntimeValue::I32(-1)));
+}
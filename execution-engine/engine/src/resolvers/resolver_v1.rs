@@ -12,14 +12,19 @@ use functions::*;
 
 pub struct RuntimeModuleImportResolver {
     memory: RefCell<Option<MemoryRef>>,
+    initial_memory: u32,
     max_memory: u32,
 }
 
-impl Default for RuntimeModuleImportResolver {
-    fn default() -> Self {
+impl RuntimeModuleImportResolver {
+    /// Creates a resolver which allows modules to start with at most `initial_memory`
+    /// pages of memory and grow it up to `max_memory` pages. As required by Wasm,
+    /// `memory.grow` past `max_memory` doesn't trap but returns -1.
+    pub fn new(initial_memory: u32, max_memory: u32) -> Self {
         RuntimeModuleImportResolver {
             memory: RefCell::new(None),
-            max_memory: 64,
+            initial_memory,
+            max_memory,
         }
     }
 }
@@ -40,95 +45,51 @@ impl ModuleImportResolver for RuntimeModuleImportResolver {
         field_name: &str,
         _signature: &Signature,
     ) -> Result<FuncRef, InterpreterError> {
-        let func_ref = match field_name {
-            "read_value" => FuncInstance::alloc_host(
-                Signature::new(&[ValueType::I32; 2][..], Some(ValueType::I32)),
-                READ_FUNC_INDEX,
-            ),
-            "serialize_function" => FuncInstance::alloc_host(
-                Signature::new(&[ValueType::I32; 2][..], Some(ValueType::I32)),
-                SER_FN_FUNC_INDEX,
-            ),
-            "write" => FuncInstance::alloc_host(
-                Signature::new(&[ValueType::I32; 4][..], None),
-                WRITE_FUNC_INDEX,
-            ),
-            "get_read" => FuncInstance::alloc_host(
-                Signature::new(&[ValueType::I32; 1][..], None),
-                GET_READ_FUNC_INDEX,
-            ),
-            "get_function" => FuncInstance::alloc_host(
-                Signature::new(&[ValueType::I32; 1][..], None),
-                GET_FN_FUNC_INDEX,
-            ),
-            "add" => FuncInstance::alloc_host(
-                Signature::new(&[ValueType::I32; 4][..], None),
-                ADD_FUNC_INDEX,
-            ),
-            "new_uref" => FuncInstance::alloc_host(
-                Signature::new(&[ValueType::I32; 3][..], None),
-                NEW_FUNC_INDEX,
-            ),
-            "load_arg" => FuncInstance::alloc_host(
-                Signature::new(&[ValueType::I32; 1][..], Some(ValueType::I32)),
-                LOAD_ARG_FUNC_INDEX,
-            ),
-            "get_arg" => FuncInstance::alloc_host(
-                Signature::new(&[ValueType::I32; 1][..], None),
-                GET_ARG_FUNC_INDEX,
-            ),
-            "ret" => FuncInstance::alloc_host(
-                Signature::new(&[ValueType::I32; 4][..], None),
-                RET_FUNC_INDEX,
-            ),
-            "call_contract" => FuncInstance::alloc_host(
-                Signature::new(&[ValueType::I32; 6][..], Some(ValueType::I32)),
-                CALL_CONTRACT_FUNC_INDEX,
-            ),
-            "get_call_result" => FuncInstance::alloc_host(
-                Signature::new(&[ValueType::I32; 1][..], None),
-                GET_CALL_RESULT_FUNC_INDEX,
-            ),
-            "get_uref" => FuncInstance::alloc_host(
-                Signature::new(&[ValueType::I32; 3][..], None),
-                GET_UREF_FUNC_INDEX,
-            ),
-            "has_uref_name" => FuncInstance::alloc_host(
-                Signature::new(&[ValueType::I32; 2][..], Some(ValueType::I32)),
-                HAS_UREF_FUNC_INDEX,
-            ),
-            "add_uref" => FuncInstance::alloc_host(
-                Signature::new(&[ValueType::I32; 4][..], None),
-                ADD_UREF_FUNC_INDEX,
-            ),
-            "gas" => FuncInstance::alloc_host(
-                Signature::new(&[ValueType::I32; 1][..], None),
-                GAS_FUNC_INDEX,
-            ),
-            "store_function" => FuncInstance::alloc_host(
-                Signature::new(&[ValueType::I32; 5][..], None),
-                STORE_FN_INDEX,
-            ),
-            "protocol_version" => FuncInstance::alloc_host(
-                Signature::new(vec![], Some(ValueType::I64)),
-                PROTOCOL_VERSION_FUNC_INDEX,
-            ),
-            "is_valid" => FuncInstance::alloc_host(
-                Signature::new(&[ValueType::I32; 2][..], Some(ValueType::I32)),
-                IS_VALID_FN_INDEX,
-            ),
-            "seed" => FuncInstance::alloc_host(
-                Signature::new(&[ValueType::I32; 1][..], None),
-                SEED_FN_INDEX,
-            ),
-            _ => {
-                return Err(InterpreterError::Function(format!(
-                    "host module doesn't export function with name {}",
-                    field_name
-                )));
+        let index = function_index(field_name).ok_or_else(|| {
+            InterpreterError::Function(format!(
+                "host module doesn't export function with name {}",
+                field_name
+            ))
+        })?;
+        let signature = match index {
+            READ_FUNC_INDEX | SER_FN_FUNC_INDEX | HAS_UREF_FUNC_INDEX | IS_VALID_FN_INDEX => {
+                Signature::new(&[ValueType::I32; 2][..], Some(ValueType::I32))
+            }
+            WRITE_FUNC_INDEX
+            | ADD_FUNC_INDEX
+            | RET_FUNC_INDEX
+            | ADD_UREF_FUNC_INDEX
+            | EMIT_EVENT_FUNC_INDEX => Signature::new(&[ValueType::I32; 4][..], None),
+            GET_READ_FUNC_INDEX
+            | GET_FN_FUNC_INDEX
+            | GET_ARG_FUNC_INDEX
+            | GET_CALL_RESULT_FUNC_INDEX
+            | GAS_FUNC_INDEX
+            | SEED_FN_INDEX
+            | GET_CALLER_FUNC_INDEX
+            | REVERT_FUNC_INDEX => Signature::new(&[ValueType::I32; 1][..], None),
+            NEW_FUNC_INDEX | GET_UREF_FUNC_INDEX => Signature::new(&[ValueType::I32; 3][..], None),
+            LOAD_ARG_FUNC_INDEX => Signature::new(&[ValueType::I32; 1][..], Some(ValueType::I32)),
+            CALL_CONTRACT_FUNC_INDEX | TRY_CALL_CONTRACT_FUNC_INDEX => {
+                Signature::new(&[ValueType::I32; 6][..], Some(ValueType::I32))
+            }
+            STORE_FN_INDEX => Signature::new(&[ValueType::I32; 5][..], None),
+            PROTOCOL_VERSION_FUNC_INDEX
+            | GET_BLOCKTIME_FUNC_INDEX
+            | GET_BLOCK_HEIGHT_FUNC_INDEX => Signature::new(vec![], Some(ValueType::I64)),
+            TRANSFER_TO_ACCOUNT_FUNC_INDEX => {
+                Signature::new(&[ValueType::I32; 4][..], Some(ValueType::I32))
+            }
+            STORE_UPGRADABLE_FN_INDEX | UPGRADE_CONTRACT_FUNC_INDEX => {
+                Signature::new(&[ValueType::I32; 6][..], None)
+            }
+            REMOVE_UREF_FUNC_INDEX => Signature::new(&[ValueType::I32; 2][..], None),
+            LOAD_CALLER_FUNC_INDEX | GET_PHASE_FUNC_INDEX => {
+                Signature::new(vec![], Some(ValueType::I32))
             }
+            _ => unreachable!("every host function has a signature"),
         };
-        Ok(func_ref)
+        Ok(FuncInstance::alloc_host(signature, index))
     }
 
     fn resolve_memory(
@@ -137,16 +98,25 @@ impl ModuleImportResolver for RuntimeModuleImportResolver {
         descriptor: &MemoryDescriptor,
     ) -> Result<MemoryRef, InterpreterError> {
         if field_name == "memory" {
-            let effective_max = descriptor.maximum().unwrap_or(self.max_memory + 1);
-            if descriptor.initial() > self.max_memory || effective_max > self.max_memory {
-                Err(InterpreterError::Instantiation(
-                    "Module requested too much memory".to_owned(),
-                ))
+            // Memory of modules which don't declare a maximum still can't grow
+            // beyond the limit; `memory.grow` past it returns -1.
+            let effective_max = descriptor.maximum().unwrap_or(self.max_memory);
+            if descriptor.initial() > self.initial_memory {
+                Err(InterpreterError::Instantiation(format!(
+                    "Module requested {} initial memory pages, but at most {} are allowed",
+                    descriptor.initial(),
+                    self.initial_memory
+                )))
+            } else if effective_max > self.max_memory {
+                Err(InterpreterError::Instantiation(format!(
+                    "Module requested up to {} memory pages, but at most {} are allowed",
+                    effective_max, self.max_memory
+                )))
             } else {
                 // Note: each "page" is 64 KiB
                 let mem = MemoryInstance::alloc(
                     Pages(descriptor.initial() as usize),
-                    descriptor.maximum().map(|x| Pages(x as usize)),
+                    Some(Pages(effective_max as usize)),
                 )?;
                 *self.memory.borrow_mut() = Some(mem.clone());
                 Ok(mem)
@@ -8,15 +8,21 @@ use rand::RngCore;
 use rand_chacha::ChaChaRng;
 
 use common::bytesrepr::{deserialize, ToBytes};
+use common::contract_api::{Phase, TransferResult};
 use common::key::{AccessRights, Key, LOCAL_SEED_SIZE};
-use common::value::account::Account;
-use common::value::Value;
-use shared::newtypes::{Blake2bHash, Validated};
+use common::value::account::{Account, PurseId, PURSE_ID_SIZE};
+use common::value::{Contract, Value, U512};
+use shared::newtypes::Validated;
+use shared::transform::TypeMismatch;
 use storage::global_state::StateReader;
 
+use engine_state::block_context::BlockContext;
+use engine_state::event::Event;
 use engine_state::execution_effect::ExecutionEffect;
 use execution::Error;
 use tracking_copy::{AddResult, TrackingCopy};
+use transfer;
+use utils::local_seed;
 use URefAddr;
 
 /// Holds information specific to the deployed contract.
@@ -36,6 +42,14 @@ pub struct RuntimeContext<'a, R> {
     fn_store_id: u32,
     rng: ChaChaRng,
     protocol_version: u64,
+    // Events emitted so far, including the ones from nested calls
+    events: Vec<Event>,
+    // Key of the account or contract which called the entity we are currently running
+    caller: Key,
+    block_context: BlockContext,
+    phase: Phase,
+    // Rejects all changes of the global state, for view calls
+    read_only: bool,
 }
 
 impl<'a, R: StateReader<Key, Value>> RuntimeContext<'a, R>
@@ -55,6 +69,10 @@ where
         fn_store_id: u32,
         rng: ChaChaRng,
         protocol_version: u64,
+        caller: Key,
+        block_context: BlockContext,
+        phase: Phase,
+        read_only: bool,
     ) -> Self {
         RuntimeContext {
             state,
@@ -68,6 +86,11 @@ where
             fn_store_id,
             rng,
             protocol_version,
+            events: Vec::new(),
+            caller,
+            block_context,
+            phase,
+            read_only,
         }
     }
 
@@ -124,18 +147,45 @@ where
     }
 
     pub fn seed(&self) -> [u8; LOCAL_SEED_SIZE] {
-        match self.base_key {
-            Key::Account(bytes) => bytes,
-            Key::Hash(bytes) => bytes,
-            Key::URef(bytes, _) => bytes,
-            Key::Local { seed, key_hash } => Blake2bHash::new(&[seed, key_hash].concat()).into(),
-        }
+        local_seed(self.base_key)
     }
 
     pub fn protocol_version(&self) -> u64 {
         self.protocol_version
     }
 
+    pub fn caller(&self) -> Key {
+        self.caller
+    }
+
+    pub fn block_context(&self) -> BlockContext {
+        self.block_context
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn emit_event(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    pub fn extend_events(&mut self, events: Vec<Event>) {
+        self.events.extend(events);
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    pub fn take_events(&mut self) -> Vec<Event> {
+        std::mem::replace(&mut self.events, Vec::new())
+    }
+
     /// Generates new function address.
     /// Function address is deterministic. It is a hash of public key, nonce and `fn_store_id`,
     /// which is a counter that is being incremented after every function generation.
@@ -166,6 +216,45 @@ where
         Ok(key)
     }
 
+    /// Transfers `amount` from the main purse of the current account to the main purse
+    /// of the account under `Key::Account(target)`. The main purse of a newly created
+    /// account gets a fresh id drawn from the context's rng. No rng bytes are drawn when
+    /// `target` already exists, so urefs created afterwards don't depend on it.
+    ///
+    /// Only code running in the context of the account itself can transfer its funds:
+    /// a stored contract called by the account has no access to its main purse.
+    pub fn transfer_to_account(
+        &mut self,
+        target: [u8; 32],
+        amount: U512,
+    ) -> Result<TransferResult, Error> {
+        self.validate_not_read_only()?;
+        if !self.is_account_context() {
+            return Err(Error::TransferNotPermitted);
+        }
+        let rng = &mut self.rng;
+        transfer::transfer_to_account(
+            &mut self.state.borrow_mut(),
+            self.account,
+            target,
+            amount,
+            || {
+                let mut new_purse_id = [0u8; PURSE_ID_SIZE];
+                rng.fill_bytes(&mut new_purse_id);
+                PurseId::new(new_purse_id)
+            },
+        )
+    }
+
+    /// Tests whether the code is executed in the context of the account itself, rather
+    /// than in the context of a stored contract.
+    fn is_account_context(&self) -> bool {
+        match self.base_key {
+            Key::Account(addr) => addr[..] == *self.account.pub_key(),
+            _ => false,
+        }
+    }
+
     /// Adds `key` to the map of named keys of current context.
     pub fn add_uref(&mut self, name: String, key: Key) -> Result<(), Error> {
         let base_key = self.base_key();
@@ -175,6 +264,25 @@ where
         Ok(())
     }
 
+    /// Removes `name` from the map of named keys of current context.
+    /// Removing a name which isn't there is a no-op.
+    pub fn remove_uref(&mut self, name: &str) -> Result<(), Error> {
+        let base_key = self.base_key();
+        let validated_key = Validated::new(base_key, |key| self.validate_addable(&key))?;
+        let names = std::iter::once(name.to_string()).collect();
+        match self.state.borrow_mut().remove_keys(validated_key, names) {
+            Err(storage_error) => return Err(storage_error.into()),
+            Ok(AddResult::Success) => {}
+            Ok(AddResult::KeyNotFound(key)) => return Err(Error::KeyNotFound(key)),
+            Ok(AddResult::TypeMismatch(type_mismatch)) => {
+                return Err(Error::TypeMismatch(type_mismatch))
+            }
+            Ok(AddResult::Overflow) => return Err(Error::Overflow),
+        }
+        self.uref_lookup.remove(name);
+        Ok(())
+    }
+
     pub fn read_gs(&mut self, key: &Key) -> Result<Option<Value>, Error> {
         let validated_key = Validated::new(*key, |key| {
             self.validate_readable(&key).and(self.validate_key(&key))
@@ -197,6 +305,7 @@ where
     }
 
     pub fn store_contract(&mut self, contract: Value) -> Result<[u8; 32], Error> {
+        self.validate_not_read_only()?;
         let new_hash = self.new_function_address()?;
         let validated_value = Validated::new(contract, |cntr| self.validate_keys(&cntr))?;
         let validated_key = Validated::new(Key::Hash(new_hash), Validated::valid)?;
@@ -206,6 +315,63 @@ where
         Ok(new_hash)
     }
 
+    /// Stores an upgradable contract under a new `Key::Hash` and returns its hash together
+    /// with a fresh admin URef. The admin URef holds the key of the contract.
+    pub fn store_upgradable_contract(
+        &mut self,
+        bytes: Vec<u8>,
+        known_urefs: BTreeMap<String, Key>,
+    ) -> Result<([u8; 32], Key), Error> {
+        let new_hash = self.new_function_address()?;
+        let mut admin_addr = [0u8; 32];
+        self.rng.fill_bytes(&mut admin_addr);
+        let admin = Key::URef(admin_addr, AccessRights::READ_ADD_WRITE);
+        self.insert_uref(Validated::new(admin, Validated::valid)?);
+        self.write_gs(admin, Value::Key(Key::Hash(new_hash)))?;
+        let contract = Contract::upgradable(bytes, known_urefs, self.protocol_version, admin_addr);
+        let validated_value =
+            Validated::new(Value::Contract(contract), |cntr| self.validate_keys(&cntr))?;
+        let validated_key = Validated::new(Key::Hash(new_hash), Validated::valid)?;
+        self.state
+            .borrow_mut()
+            .write(validated_key, validated_value);
+        Ok((new_hash, admin))
+    }
+
+    /// Replaces the body of the upgradable contract under `contract_key` with `bytes`,
+    /// keeping its known urefs. `admin` has to be the contract's admin URef with WRITE rights.
+    pub fn upgrade_contract(
+        &mut self,
+        contract_key: Key,
+        admin: Key,
+        bytes: Vec<u8>,
+    ) -> Result<(), Error> {
+        let contract = match (contract_key, self.read_gs(&contract_key)?) {
+            (Key::Hash(_), Some(Value::Contract(contract))) => contract,
+            (_, Some(Value::Contract(_))) => return Err(Error::UpgradeNotPermitted(contract_key)),
+            (_, Some(other)) => {
+                return Err(Error::TypeMismatch(TypeMismatch::new(
+                    "Contract".to_string(),
+                    other.type_string(),
+                )))
+            }
+            (_, None) => return Err(Error::KeyNotFound(contract_key)),
+        };
+        match (contract.admin(), admin) {
+            (Some(admin_addr), Key::URef(addr, _)) if admin_addr == addr => {}
+            _ => return Err(Error::UpgradeNotPermitted(contract_key)),
+        }
+        self.validate_writeable(&admin)
+            .and(self.validate_key(&admin))?;
+        let contract = contract.upgrade(bytes, self.protocol_version);
+        let validated_key = Validated::new(contract_key, Validated::valid)?;
+        let validated_value = Validated::new(Value::Contract(contract), Validated::valid)?;
+        self.state
+            .borrow_mut()
+            .write(validated_key, validated_value);
+        Ok(())
+    }
+
     pub fn insert_named_uref(&mut self, name: String, key: Validated<Key>) {
         self.insert_uref(key.clone());
         self.uref_lookup.insert(name, *key);
@@ -235,9 +401,23 @@ where
             | Value::ByteArray(_)
             | Value::ListInt32(_)
             | Value::String(_)
-            | Value::ListString(_) => Ok(()),
+            | Value::ListString(_)
+            | Value::Bool(_)
+            | Value::UInt64(_)
+            | Value::Unit => Ok(()),
             Value::NamedKey(_, key) => self.validate_key(&key),
             Value::Key(key) => self.validate_key(&key),
+            Value::ListKey(keys) => keys.iter().try_for_each(|key| self.validate_key(key)),
+            Value::Option(option) => option
+                .as_ref()
+                .map_or(Ok(()), |value| self.validate_keys(value)),
+            Value::Tuple(values) | Value::List(values) => values
+                .iter()
+                .try_for_each(|value| self.validate_keys(value)),
+            Value::Map(map) => map.iter().try_for_each(|(k, v)| {
+                self.validate_keys(k)?;
+                self.validate_keys(v)
+            }),
             Value::Account(account) => {
                 // This should never happen as accounts can't be created by contracts.
                 // I am putting this here for the sake of completness.
@@ -309,6 +489,18 @@ where
         }
     }
 
+    /// Contracts called as views can't write anything, not even under keys which aren't
+    /// checked for WRITE rights, such as the hashes of new contracts.
+    fn validate_not_read_only(&self) -> Result<(), Error> {
+        if self.read_only {
+            Err(Error::InvalidAccess {
+                required: AccessRights::WRITE,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
     // Tests whether reading from the `key` is valid.
     pub fn is_readable(&self, key: &Key) -> bool {
         match key {
@@ -321,6 +513,9 @@ where
 
     /// Tests whether addition to `key` is valid.
     pub fn is_addable(&self, key: &Key) -> bool {
+        if self.read_only {
+            return false;
+        }
         match key {
             Key::Account(_) | Key::Hash(_) => &self.base_key() == key,
             Key::URef(_, rights) => rights.is_addable(),
@@ -330,6 +525,9 @@ where
 
     // Test whether writing to `key` is valid.
     pub fn is_writeable(&self, key: &Key) -> bool {
+        if self.read_only {
+            return false;
+        }
         match key {
             Key::Account(_) | Key::Hash(_) => false,
             Key::URef(_, rights) => rights.is_writeable(),
@@ -351,6 +549,7 @@ where
             Ok(AddResult::Success) => Ok(()),
             Ok(AddResult::KeyNotFound(key)) => Err(Error::KeyNotFound(key)),
             Ok(AddResult::TypeMismatch(type_mismatch)) => Err(Error::TypeMismatch(type_mismatch)),
+            Ok(AddResult::Overflow) => Err(Error::Overflow),
         }
     }
 }
@@ -366,13 +565,15 @@ mod tests {
     use rand_chacha::ChaChaRng;
 
     use common::key::{AccessRights, Key, LOCAL_SEED_SIZE};
-    use common::value::{self, Account, Contract, Value};
-    use shared::transform::Transform;
+    use common::value::{self, Account, Contract, Value, U512};
+    use shared::transform::{Arithmetic, Transform};
     use storage::global_state::in_memory::InMemoryGlobalState;
     use storage::global_state::{CommitResult, History};
 
-    use super::{Error, RuntimeContext, URefAddr, Validated};
-    use common::value::account::{AssociatedKeys, PublicKey, Weight};
+    use super::{Error, Event, RuntimeContext, URefAddr, Validated};
+    use common::contract_api::Phase;
+    use common::value::account::{ActionThresholds, AssociatedKeys, PublicKey, PurseId, Weight};
+    use engine_state::block_context::BlockContext;
     use execution::{create_rng, vec_key_rights_to_map};
     use shared::newtypes::Blake2bHash;
     use tracking_copy::TrackingCopy;
@@ -385,7 +586,7 @@ mod tests {
         let mut m = HashMap::new();
         m.insert(init_key, transform);
         let commit_result = hist
-            .commit(root_hash, m)
+            .commit(root_hash, m, Arithmetic::Wrapping)
             .expect("Creation of mocked account should be a success.");
 
         let new_hash = match commit_result {
@@ -403,7 +604,14 @@ mod tests {
 
     fn mock_account(addr: [u8; 32]) -> (Key, value::Account) {
         let associated_keys = AssociatedKeys::new(PublicKey::new(addr), Weight::new(1));
-        let account = value::account::Account::new(addr, 0, BTreeMap::new(), associated_keys);
+        let account = value::account::Account::new(
+            addr,
+            0,
+            BTreeMap::new(),
+            associated_keys,
+            PurseId::new([0u8; 32]),
+            ActionThresholds::default(),
+        );
         let key = Key::Account(addr);
 
         (key, account)
@@ -457,6 +665,10 @@ mod tests {
             0,
             rng,
             1,
+            base_key,
+            BlockContext::default(),
+            Phase::Session,
+            false,
         )
     }
 
@@ -521,6 +733,28 @@ mod tests {
         assert_forged_reference(query_result);
     }
 
+    #[test]
+    fn validate_keys_nested_in_map() {
+        let mut rng = rand::thread_rng();
+        let known = random_uref_key(&mut rng, AccessRights::READ);
+        let forged = random_uref_key(&mut rng, AccessRights::READ);
+        let known_urefs = vec_key_rights_to_map(vec![known]);
+        let map_value = |key: Key| {
+            let mut map = BTreeMap::new();
+            map.insert(
+                Value::String("urefs".to_owned()),
+                Value::List(vec![Value::Key(known), Value::Key(key)]),
+            );
+            Value::Map(map)
+        };
+
+        test(known_urefs.clone(), |rc| {
+            rc.validate_keys(&map_value(known))
+        })
+        .expect("map with valid URefs should be valid");
+        assert_forged_reference(test(known_urefs, |rc| rc.validate_keys(&map_value(forged))));
+    }
+
     #[test]
     fn store_contract_with_uref_valid() {
         let mut rng = rand::thread_rng();
@@ -746,6 +980,10 @@ mod tests {
             0,
             chacha_rng,
             1,
+            account_key,
+            BlockContext::default(),
+            Phase::Session,
+            false,
         );
 
         let uref_name = "NewURef".to_owned();
@@ -796,6 +1034,10 @@ mod tests {
             0,
             chacha_rng,
             1,
+            account_key,
+            BlockContext::default(),
+            Phase::Session,
+            false,
         );
 
         let uref_name = "NewURef".to_owned();
@@ -806,6 +1048,62 @@ mod tests {
         assert_invalid_access(result, AccessRights::ADD);
     }
 
+    #[test]
+    fn read_only_context_rejects_changes() {
+        let base_acc_addr = [0u8; 32];
+        let (account_key, account) = mock_account(base_acc_addr);
+        let mut rng = rand::thread_rng();
+        let uref = random_uref_key(&mut rng, AccessRights::READ_ADD_WRITE);
+        let tc = Rc::new(RefCell::new(mock_tc(account_key, &account)));
+        tc.borrow_mut().write(
+            Validated::new(uref, Validated::valid).unwrap(),
+            Validated::new(Value::Int32(1), Validated::valid).unwrap(),
+        );
+
+        let mut uref_map = BTreeMap::new();
+        let known_urefs = vec_key_rights_to_map(vec![uref]);
+        let chacha_rng = create_rng(&base_acc_addr, 0, 0);
+        let mut runtime_context = RuntimeContext::new(
+            Rc::clone(&tc),
+            &mut uref_map,
+            known_urefs,
+            Vec::new(),
+            &account,
+            account_key,
+            0,
+            0,
+            0,
+            chacha_rng,
+            1,
+            account_key,
+            BlockContext::default(),
+            Phase::Session,
+            true,
+        );
+
+        assert_eq!(
+            runtime_context.read_gs(&uref).unwrap(),
+            Some(Value::Int32(1))
+        );
+        assert_invalid_access(
+            runtime_context.write_gs(uref, Value::Int32(2)),
+            AccessRights::WRITE,
+        );
+        assert_invalid_access(
+            runtime_context.add_gs(uref, Value::Int32(2)),
+            AccessRights::ADD,
+        );
+        assert_invalid_access(
+            runtime_context.new_uref(Value::Int32(2)),
+            AccessRights::WRITE,
+        );
+        let contract: Value = Contract::new(Vec::new(), BTreeMap::new(), 1).into();
+        assert_invalid_access(
+            runtime_context.store_contract(contract),
+            AccessRights::WRITE,
+        );
+    }
+
     #[test]
     fn uref_key_readable_valid() {
         let mut rng = rand::thread_rng();
@@ -941,4 +1239,123 @@ mod tests {
         let query_result = test(known_urefs, query);
         assert!(query_result.is_err())
     }
+
+    #[test]
+    fn upgrade_contract_with_admin_uref() {
+        let mut rng = rand::thread_rng();
+        let uref = random_uref_key(&mut rng, AccessRights::READ_WRITE);
+        let known_urefs = vec_key_rights_to_map(vec![uref]);
+        let contract_urefs: BTreeMap<String, Key> = once(("ValidURef".to_owned(), uref)).collect();
+
+        let query_result = test(known_urefs, |mut rc| {
+            let (contract_addr, admin) =
+                rc.store_upgradable_contract(vec![1], contract_urefs.clone())?;
+            let contract_key = Key::Hash(contract_addr);
+            rc.upgrade_contract(contract_key, admin, vec![2])?;
+            rc.read_gs(&contract_key)
+        });
+
+        match query_result {
+            Ok(Some(Value::Contract(contract))) => {
+                assert_eq!(contract.bytes(), &[2u8]);
+                assert_eq!(contract.urefs_lookup(), &contract_urefs);
+            }
+            other => panic!("Upgraded contract should be found: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn upgrade_contract_with_read_only_admin_uref() {
+        let query_result = test(HashMap::new(), |mut rc| {
+            let (contract_addr, admin) = rc.store_upgradable_contract(vec![1], BTreeMap::new())?;
+            let read_only_admin = match admin {
+                Key::URef(addr, _) => Key::URef(addr, AccessRights::READ),
+                other => panic!("Admin should be a URef: {:?}", other),
+            };
+            rc.upgrade_contract(Key::Hash(contract_addr), read_only_admin, vec![2])
+        });
+
+        assert_invalid_access(query_result, AccessRights::WRITE);
+    }
+
+    #[test]
+    fn upgrade_immutable_contract() {
+        let mut rng = rand::thread_rng();
+        let uref = random_uref_key(&mut rng, AccessRights::READ_WRITE);
+        let known_urefs = vec_key_rights_to_map(vec![uref]);
+        let contract: Value = Contract::new(vec![1], BTreeMap::new(), 1).into();
+
+        let query_result = test(known_urefs, |mut rc| {
+            let contract_addr = rc.store_contract(contract.clone())?;
+            rc.upgrade_contract(Key::Hash(contract_addr), uref, vec![2])
+        });
+
+        match query_result {
+            Err(Error::UpgradeNotPermitted(_)) => {}
+            other => panic!("Upgrade should not be permitted: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn remove_uref_from_account() {
+        let mut rng = rand::thread_rng();
+        let uref = random_uref_key(&mut rng, AccessRights::READ_WRITE);
+        let known_urefs = vec_key_rights_to_map(vec![uref]);
+        let query_result = test(known_urefs, |mut rc| {
+            rc.add_uref("ToRemove".to_string(), uref)?;
+            rc.add_uref("ToKeep".to_string(), uref)?;
+            rc.remove_uref("ToRemove")?;
+            assert!(!rc.contains_uref("ToRemove"));
+            assert!(rc.contains_uref("ToKeep"));
+            let base_key = rc.base_key();
+            rc.read_gs(&base_key)
+        });
+        match query_result {
+            Ok(Some(Value::Account(account))) => {
+                let names: Vec<&String> = account.urefs_lookup().keys().collect();
+                assert_eq!(names, vec!["ToKeep"]);
+            }
+            other => panic!("Expected an account: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn events_are_kept_in_order() {
+        let known_urefs = HashMap::new();
+        let query_result = test(known_urefs, |mut rc| {
+            rc.emit_event(Event::new("first".to_string(), vec![1]));
+            rc.extend_events(vec![
+                Event::new("second".to_string(), vec![2]),
+                Event::new("third".to_string(), vec![3]),
+            ]);
+            let topics: Vec<String> = rc.events().iter().map(|e| e.topic.clone()).collect();
+            assert_eq!(topics, vec!["first", "second", "third"]);
+            let events = rc.take_events();
+            assert_eq!(events.len(), 3);
+            assert!(rc.events().is_empty());
+            Ok(())
+        });
+        assert!(query_result.is_ok());
+    }
+
+    #[test]
+    fn transfer_from_contract_context_not_permitted() {
+        let (account_key, account) = mock_account([0u8; 32]);
+        let mut uref_map = BTreeMap::new();
+        let rng = create_rng(&[0u8; 32], 0, 0);
+        let contract_key = Key::Hash([1u8; 32]);
+        let mut rc =
+            mock_runtime_context(&account, contract_key, &mut uref_map, HashMap::new(), rng);
+        let result = rc.transfer_to_account([2u8; 32], U512::from(1));
+        assert_matches!(result, Err(Error::TransferNotPermitted));
+        assert!(rc.effect().1.is_empty());
+
+        let mut uref_map = BTreeMap::new();
+        let rng = create_rng(&[0u8; 32], 0, 0);
+        let mut rc =
+            mock_runtime_context(&account, account_key, &mut uref_map, HashMap::new(), rng);
+        // The mocked account has no purse, but the transfer gets past the context check.
+        let result = rc.transfer_to_account([2u8; 32], U512::from(1));
+        assert_matches!(result, Err(Error::KeyNotFound(_)));
+    }
 }
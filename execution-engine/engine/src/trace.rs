@@ -0,0 +1,175 @@
+use common::bytesrepr::{deserialize, FromBytes};
+use common::key::Key;
+use common::value::Value;
+
+use functions::{self, *};
+
+/// A call to a host function made while executing a deploy with tracing enabled.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HostCall {
+    pub function: &'static str,
+    /// Arguments of the call, decoded from Wasm memory where possible.
+    pub args: Vec<String>,
+    pub result: String,
+    pub gas_before: u64,
+    pub gas_after: u64,
+    /// Number of contract calls between the deploy and the caller of the host function.
+    pub depth: usize,
+}
+
+/// Maximum number of host calls recorded in a `Trace`.
+pub const MAX_TRACE_CALLS: usize = 10_000;
+
+/// Host calls of a deploy, in the order in which they were made. Calls of the `gas`
+/// function aren't recorded, and neither are the calls made once the trace holds
+/// `MAX_TRACE_CALLS` calls, in which case it is `truncated`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Trace {
+    pub calls: Vec<HostCall>,
+    pub truncated: bool,
+}
+
+impl Trace {
+    pub fn to_json(&self) -> String {
+        let calls: Vec<String> = self
+            .calls
+            .iter()
+            .map(|call| {
+                let args: Vec<String> = call.args.iter().map(|arg| json_string(arg)).collect();
+                format!(
+                    "{{\"function\":{},\"args\":[{}],\"result\":{},\"gas_before\":{},\"gas_after\":{},\"depth\":{}}}",
+                    json_string(call.function),
+                    args.join(","),
+                    json_string(&call.result),
+                    call.gas_before,
+                    call.gas_after,
+                    call.depth
+                )
+            })
+            .collect();
+        format!(
+            "{{\"calls\":[{}],\"truncated\":{}}}",
+            calls.join(","),
+            self.truncated
+        )
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// How an argument of a host function, passed as a pointer and a size, is decoded for the trace.
+#[derive(Clone, Copy, Debug)]
+pub enum ArgKind {
+    Key,
+    Keys,
+    Value,
+    Name,
+    Bytes,
+}
+
+impl ArgKind {
+    pub fn decode(self, bytes: &[u8]) -> String {
+        match self {
+            ArgKind::Key => debug_string::<Key>(bytes),
+            ArgKind::Keys => debug_string::<Vec<Key>>(bytes),
+            ArgKind::Value => debug_string::<Value>(bytes),
+            ArgKind::Name => debug_string::<String>(bytes),
+            ArgKind::Bytes => bytes.iter().map(|byte| format!("{:02x}", byte)).collect(),
+        }
+    }
+}
+
+fn debug_string<T: FromBytes + std::fmt::Debug>(bytes: &[u8]) -> String {
+    match deserialize::<T>(bytes) {
+        Ok(value) => format!("{:?}", value),
+        Err(error) => format!("<{:?}>", error),
+    }
+}
+
+/// Arguments of the host function `index` which are decoded for the trace, given as
+/// positions of the pointers; the size of each argument follows its pointer.
+/// Arguments of other host functions are traced as they are.
+pub fn decoded_args(index: usize) -> &'static [(usize, ArgKind)] {
+    match index {
+        READ_FUNC_INDEX => &[(0, ArgKind::Key)],
+        WRITE_FUNC_INDEX | ADD_FUNC_INDEX => &[(0, ArgKind::Key), (2, ArgKind::Value)],
+        NEW_FUNC_INDEX => &[(1, ArgKind::Value)],
+        SER_FN_FUNC_INDEX
+        | GET_UREF_FUNC_INDEX
+        | HAS_UREF_FUNC_INDEX
+        | REMOVE_UREF_FUNC_INDEX
+        | STORE_FN_INDEX
+        | STORE_UPGRADABLE_FN_INDEX => &[(0, ArgKind::Name)],
+        ADD_UREF_FUNC_INDEX => &[(0, ArgKind::Name), (2, ArgKind::Key)],
+        RET_FUNC_INDEX => &[(0, ArgKind::Bytes), (2, ArgKind::Keys)],
+        CALL_CONTRACT_FUNC_INDEX | TRY_CALL_CONTRACT_FUNC_INDEX => {
+            &[(0, ArgKind::Key), (2, ArgKind::Bytes), (4, ArgKind::Keys)]
+        }
+        UPGRADE_CONTRACT_FUNC_INDEX => &[(0, ArgKind::Key), (2, ArgKind::Key), (4, ArgKind::Name)],
+        IS_VALID_FN_INDEX => &[(0, ArgKind::Value)],
+        TRANSFER_TO_ACCOUNT_FUNC_INDEX => &[(0, ArgKind::Bytes), (2, ArgKind::Bytes)],
+        EMIT_EVENT_FUNC_INDEX => &[(0, ArgKind::Name), (2, ArgKind::Bytes)],
+        _ => &[],
+    }
+}
+
+/// Name under which the host function `index` is imported by contracts.
+pub fn function_name(index: usize) -> &'static str {
+    functions::function_name(index).unwrap_or("unknown")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArgKind, HostCall, Trace};
+    use common::bytesrepr::ToBytes;
+    use common::key::Key;
+
+    #[test]
+    fn args_are_decoded() {
+        let key = Key::Hash([1u8; 32]);
+        assert_eq!(
+            ArgKind::Key.decode(&key.to_bytes().unwrap()),
+            format!("{:?}", key)
+        );
+        assert_eq!(
+            ArgKind::Name.decode(&"purse".to_string().to_bytes().unwrap()),
+            "\"purse\""
+        );
+        assert_eq!(ArgKind::Bytes.decode(&[0u8, 255]), "00ff");
+    }
+
+    #[test]
+    fn trace_to_json() {
+        let trace = Trace {
+            calls: vec![HostCall {
+                function: "get_uref",
+                args: vec!["\"purse\"".to_string()],
+                result: "()".to_string(),
+                gas_before: 10,
+                gas_after: 25,
+                depth: 1,
+            }],
+            truncated: false,
+        };
+        assert_eq!(
+            trace.to_json(),
+            r#"{"calls":[{"function":"get_uref","args":["\"purse\""],"result":"()","gas_before":10,"gas_after":25,"depth":1}],"truncated":false}"#
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use linked_hash_map::LinkedHashMap;
 use parking_lot::Mutex;
@@ -6,18 +6,25 @@ use parking_lot::Mutex;
 use common::key::Key;
 use common::value::Value;
 use shared::newtypes::Validated;
-use shared::transform::{self, Transform, TypeMismatch};
+use shared::transform::{self, Arithmetic, Transform, TypeMismatch};
 use storage::global_state::StateReader;
 
 use engine_state::execution_effect::ExecutionEffect;
 use engine_state::op::Op;
 use meter::heap_meter::HeapSize;
 use meter::Meter;
-use utils::add;
+use utils::{add, add_transform, local_key, local_seed};
+
+/// Path segment which follows the key held by a `Value::Key` or `Value::NamedKey`.
+pub const DEREF_SEGMENT: &str = "*";
+/// Prefix of path segments which step into local storage, followed by the hex encoded
+/// serialized local key.
+pub const LOCAL_SEGMENT_PREFIX: &str = "local:";
 
 #[derive(Debug)]
 pub enum QueryResult {
-    Success(Value),
+    /// The value found, and the keys traversed to reach it.
+    Success(Value, Vec<Key>),
     ValueNotFound(String),
 }
 
@@ -81,11 +88,30 @@ impl<M: Meter<Key, Value>> TrackingCopyCache<M> {
     }
 }
 
+/// State of a single key before it was first mutated after a checkpoint.
+struct JournalEntry {
+    cached: Option<Value>,
+    op: Option<Op>,
+    transform: Option<Transform>,
+}
+
+/// A point of the execution to which `TrackingCopy` can be rolled back.
+/// Checkpoints nest; rolling back or releasing a checkpoint does the same
+/// to all the checkpoints taken after it.
+#[derive(Debug)]
+#[must_use]
+pub struct Checkpoint(usize);
+
 pub struct TrackingCopy<R> {
     reader: R,
     cache: TrackingCopyCache<HeapSize>,
     ops: HashMap<Key, Op>,
     fns: HashMap<Key, Transform>,
+    // One entry per open checkpoint, holding the previous state of keys
+    // mutated since that checkpoint was taken.
+    journal: Vec<HashMap<Key, JournalEntry>>,
+    // Arithmetic of the numeric additions, which depends on the protocol version.
+    arithmetic: Arithmetic,
 }
 
 #[derive(Debug)]
@@ -93,6 +119,7 @@ pub enum AddResult {
     Success,
     KeyNotFound(Key),
     TypeMismatch(TypeMismatch),
+    Overflow,
 }
 
 impl<R: StateReader<Key, Value>> TrackingCopy<R> {
@@ -102,6 +129,62 @@ impl<R: StateReader<Key, Value>> TrackingCopy<R> {
             cache: TrackingCopyCache::new(1024 * 16, HeapSize), //TODO: Should `max_cache_size` be fraction of Wasm memory limit?
             ops: HashMap::new(),
             fns: HashMap::new(),
+            journal: Vec::new(),
+            arithmetic: Arithmetic::Wrapping,
+        }
+    }
+
+    /// Sets the arithmetic with which `add` updates numeric values.
+    pub fn set_arithmetic(&mut self, arithmetic: Arithmetic) {
+        self.arithmetic = arithmetic;
+    }
+
+    /// Starts tracking mutations which can be undone with `rollback`.
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        self.journal.push(HashMap::new());
+        Checkpoint(self.journal.len() - 1)
+    }
+
+    /// Discards all the mutations tracked since `checkpoint` was taken.
+    /// Cached reads stay valid, as they hold values from the underlying state, and so do
+    /// the `Op::Read`s of keys which were read but not mutated since then: the execution
+    /// which follows the rollback can still depend on what was read.
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        while self.journal.len() > checkpoint.0 {
+            let entries = self.journal.pop().unwrap_or_default();
+            for (key, entry) in entries {
+                restore(&mut self.cache.muts_cached, key, entry.cached);
+                restore(&mut self.ops, key, entry.op);
+                restore(&mut self.fns, key, entry.transform);
+            }
+        }
+    }
+
+    /// Keeps the mutations tracked since `checkpoint` was taken. They can still be
+    /// rolled back together with an enclosing checkpoint.
+    pub fn release(&mut self, checkpoint: Checkpoint) {
+        while self.journal.len() > checkpoint.0 {
+            let entries = self.journal.pop().unwrap_or_default();
+            if let Some(parent) = self.journal.last_mut() {
+                for (key, entry) in entries {
+                    // the parent already holds an older state of the key
+                    parent.entry(key).or_insert(entry);
+                }
+            }
+        }
+    }
+
+    /// Records the state of `key` before its first mutation since the last checkpoint.
+    fn record(&mut self, key: Key) {
+        if let Some(entries) = self.journal.last_mut() {
+            if !entries.contains_key(&key) {
+                let entry = JournalEntry {
+                    cached: self.cache.muts_cached.get(&key).cloned(),
+                    op: self.ops.get(&key).cloned(),
+                    transform: self.fns.get(&key).cloned(),
+                };
+                entries.insert(key, entry);
+            }
         }
     }
 
@@ -117,10 +200,15 @@ impl<R: StateReader<Key, Value>> TrackingCopy<R> {
         }
     }
 
+    /// Reads the value of `key` in the underlying state, ignoring the tracked mutations.
+    pub fn get_original(&self, key: &Key) -> Result<Option<Value>, R::Error> {
+        self.reader.read(key)
+    }
+
     pub fn read(&mut self, k: &Validated<Key>) -> Result<Option<Value>, R::Error> {
         if let Some(value) = self.get(k)? {
             add(&mut self.ops, **k, Op::Read);
-            add(&mut self.fns, **k, Transform::Identity);
+            add_transform(&mut self.fns, **k, Transform::Identity, self.arithmetic);
             Ok(Some(value))
         } else {
             Ok(None)
@@ -129,9 +217,15 @@ impl<R: StateReader<Key, Value>> TrackingCopy<R> {
 
     pub fn write(&mut self, k: Validated<Key>, v: Validated<Value>) {
         let v_local = v.into_raw();
+        self.record(*k);
         self.cache.insert_write(*k, v_local.clone());
         add(&mut self.ops, *k, Op::Write);
-        add(&mut self.fns, *k, Transform::Write(v_local));
+        add_transform(
+            &mut self.fns,
+            *k,
+            Transform::Write(v_local),
+            self.arithmetic,
+        );
     }
 
     /// Ok(None) represents missing key to which we want to "add" some value.
@@ -143,6 +237,7 @@ impl<R: StateReader<Key, Value>> TrackingCopy<R> {
             Some(curr) => {
                 let t = match v.into_raw() {
                     Value::Int32(i) => Transform::AddInt32(i),
+                    Value::UInt64(i) => Transform::AddUInt64(i),
                     Value::UInt128(i) => Transform::AddUInt128(i),
                     Value::UInt256(i) => Transform::AddUInt256(i),
                     Value::UInt512(i) => Transform::AddUInt512(i),
@@ -158,16 +253,49 @@ impl<R: StateReader<Key, Value>> TrackingCopy<R> {
                         )))
                     }
                 };
-                match t.clone().apply(curr) {
+                match t.clone().apply_with(curr, self.arithmetic) {
                     Ok(new_value) => {
+                        self.record(*k);
                         self.cache.insert_write(*k, new_value);
                         add(&mut self.ops, *k, Op::Add);
-                        add(&mut self.fns, *k, t);
+                        add_transform(&mut self.fns, *k, t, self.arithmetic);
+                        Ok(AddResult::Success)
+                    }
+                    Err(transform::Error::TypeMismatch(type_mismatch)) => {
+                        Ok(AddResult::TypeMismatch(type_mismatch))
+                    }
+                    Err(transform::Error::Overflow) => Ok(AddResult::Overflow),
+                }
+            }
+        }
+    }
+
+    /// Removes named keys `names` from the account or contract living under `k`.
+    /// Removal doesn't commute with additions of named keys, so it is recorded as a write.
+    pub fn remove_keys(
+        &mut self,
+        k: Validated<Key>,
+        names: BTreeSet<String>,
+    ) -> Result<AddResult, R::Error> {
+        match self.get(&k)? {
+            None => Ok(AddResult::KeyNotFound(*k)),
+            Some(curr) => {
+                let t = Transform::RemoveKeys {
+                    removed: names,
+                    added: BTreeMap::new(),
+                };
+                match t.clone().apply_with(curr, self.arithmetic) {
+                    Ok(new_value) => {
+                        self.record(*k);
+                        self.cache.insert_write(*k, new_value);
+                        add(&mut self.ops, *k, Op::Write);
+                        add_transform(&mut self.fns, *k, t, self.arithmetic);
                         Ok(AddResult::Success)
                     }
                     Err(transform::Error::TypeMismatch(type_mismatch)) => {
                         Ok(AddResult::TypeMismatch(type_mismatch))
                     }
+                    Err(transform::Error::Overflow) => Ok(AddResult::Overflow),
                 }
             }
         }
@@ -177,6 +305,13 @@ impl<R: StateReader<Key, Value>> TrackingCopy<R> {
         ExecutionEffect(self.ops.clone(), self.fns.clone())
     }
 
+    /// Follows `path` from the value stored under `base_key`. Besides the names of the
+    /// `known_urefs` of accounts and contracts, a path can contain [`DEREF_SEGMENT`], which
+    /// follows the key held by a `Value::Key` or `Value::NamedKey`, and
+    /// [`LOCAL_SEGMENT_PREFIX`] followed by a hex encoded serialized local key, which steps
+    /// into the local storage of an account or contract. Names of `known_urefs` take
+    /// precedence over the latter. On success, all keys read are returned in order,
+    /// starting with `base_key`.
     pub fn query(&mut self, base_key: Key, path: &[String]) -> Result<QueryResult, R::Error> {
         let validated_key = Validated::new(base_key, Validated::valid)?;
         match self.read(&validated_key)? {
@@ -187,6 +322,7 @@ impl<R: StateReader<Key, Value>> TrackingCopy<R> {
                 0 as usize,
             ))),
             Some(base_value) => {
+                let mut keys = vec![base_key];
                 let result = path.iter().enumerate().try_fold(
                     base_value,
                     // We encode the two possible short-circuit conditions with
@@ -195,34 +331,44 @@ impl<R: StateReader<Key, Value>> TrackingCopy<R> {
                     // a storage-related error. The information in the Ok(_) case is used
                     // to build an informative error message about why the query was not successful.
                     |curr_value, (i, name)| -> Result<Value, Result<(usize, String), R::Error>> {
-                        match curr_value {
+                        // The value at hand was read from the last key traversed.
+                        let curr_key = keys[keys.len() - 1];
+                        let next_key = match curr_value {
                             Value::Account(account) => {
-                                if let Some(key) = account.urefs_lookup().get(name) {
-                                    let validated_key = Validated::new(*key, Validated::valid)?;
-                                    self.read_key_or_stop(validated_key, i)
-                                } else {
-                                    Err(Ok((i, format!("Name {} not found in Account at path:", name))))
+                                match account.urefs_lookup().get(name) {
+                                    Some(key) => *key,
+                                    None => match local_segment_key(curr_key, name) {
+                                        Some(key) => key,
+                                        None => return Err(Ok((i, format!("Name {} not found in Account at path:", name)))),
+                                    },
                                 }
                             }
 
                             Value::Contract(contract) => {
-                                if let Some(key) = contract.urefs_lookup().get(name) {
-                                    let validated_key = Validated::new(*key, Validated::valid)?;
-                                    self.read_key_or_stop(validated_key, i)
-                                } else {
-                                    Err(Ok((i, format!("Name {} not found in Contract at path:", name))))
+                                match contract.urefs_lookup().get(name) {
+                                    Some(key) => *key,
+                                    None => match local_segment_key(curr_key, name) {
+                                        Some(key) => key,
+                                        None => return Err(Ok((i, format!("Name {} not found in Contract at path:", name)))),
+                                    },
                                 }
                             }
 
-                            other => Err(
-                                Ok((i, format!("Name {} cannot be followed from value {:?} because it is neither an account nor contract. Value found at path:", name, other)))
+                            Value::Key(key) | Value::NamedKey(_, key) if name == DEREF_SEGMENT => key,
+
+                            other => return Err(
+                                Ok((i, format!("Name {} cannot be followed from value {:?} because it is neither an account, a contract nor a key. Value found at path:", name, other)))
                                 ),
-                        }
+                        };
+                        let validated_key = Validated::new(next_key, Validated::valid)?;
+                        let value = self.read_key_or_stop(validated_key, i)?;
+                        keys.push(next_key);
+                        Ok(value)
                     },
                 );
 
                 match result {
-                    Ok(value) => Ok(QueryResult::Success(value)),
+                    Ok(value) => Ok(QueryResult::Success(value, keys)),
                     Err(Ok((i, s))) => Ok(QueryResult::ValueNotFound(
                         self.error_path_msg(base_key, path, s, i),
                     )),
@@ -264,6 +410,38 @@ impl<R: StateReader<Key, Value>> TrackingCopy<R> {
     }
 }
 
+/// Global state key of the local storage entry addressed by `segment` in the context of
+/// `base_key`, if `segment` is a local segment.
+fn local_segment_key(base_key: Key, segment: &str) -> Option<Key> {
+    if !segment.starts_with(LOCAL_SEGMENT_PREFIX) {
+        return None;
+    }
+    let key_bytes = decode_hex(&segment[LOCAL_SEGMENT_PREFIX.len()..])?;
+    Some(local_key(local_seed(base_key), &key_bytes))
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Puts back the `previous` value of `key`, removing it when there was none.
+fn restore<V>(map: &mut HashMap<Key, V>, key: Key, previous: Option<V>) {
+    match previous {
+        Some(value) => {
+            map.insert(key, value);
+        }
+        None => {
+            map.remove(&key);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::cell::Cell;
@@ -274,6 +452,7 @@ mod tests {
     use proptest::collection::vec;
     use proptest::prelude::*;
 
+    use common::bytesrepr::ToBytes;
     use common::gens::*;
     use common::key::{AccessRights, Key};
     use common::value::{Account, Contract, Value};
@@ -281,10 +460,13 @@ mod tests {
     use storage::global_state::in_memory::InMemoryGlobalState;
     use storage::global_state::StateReader;
 
-    use super::{AddResult, QueryResult, Validated};
-    use common::value::account::{AssociatedKeys, PublicKey, Weight, KEY_SIZE};
+    use super::{AddResult, QueryResult, Validated, DEREF_SEGMENT, LOCAL_SEGMENT_PREFIX};
+    use common::value::account::{
+        ActionThresholds, AssociatedKeys, PublicKey, PurseId, Weight, KEY_SIZE,
+    };
     use engine_state::op::Op;
     use tracking_copy::TrackingCopy;
+    use utils::{local_key, local_seed};
 
     struct CountingDb {
         count: Rc<Cell<i32>>,
@@ -456,8 +638,14 @@ mod tests {
     fn tracking_copy_add_named_key() {
         // DB now holds an `Account` so that we can test adding a `NamedKey`
         let associated_keys = AssociatedKeys::new(PublicKey::new([0u8; KEY_SIZE]), Weight::new(1));
-        let account =
-            common::value::Account::new([0u8; KEY_SIZE], 0u64, BTreeMap::new(), associated_keys);
+        let account = common::value::Account::new(
+            [0u8; KEY_SIZE],
+            0u64,
+            BTreeMap::new(),
+            associated_keys,
+            PurseId::new([0u8; 32]),
+            ActionThresholds::default(),
+        );
         let db = CountingDb::new_init(Value::Account(account));
         let mut tc = TrackingCopy::new(db);
         let k = Key::Hash([0u8; 32]);
@@ -510,6 +698,139 @@ mod tests {
         assert_eq!(tc.ops.get(&k), Some(&Op::Add));
     }
 
+    #[test]
+    fn tracking_copy_remove_named_key() {
+        let u1 = Key::URef([1u8; 32], AccessRights::READ_WRITE);
+        let u2 = Key::URef([2u8; 32], AccessRights::READ_WRITE);
+        let known_urefs: BTreeMap<String, Key> =
+            vec![("test".to_string(), u1), ("test2".to_string(), u2)]
+                .into_iter()
+                .collect();
+        let contract = Contract::new(Vec::new(), known_urefs, 1);
+        let db = CountingDb::new_init(Value::Contract(contract));
+        let mut tc = TrackingCopy::new(db);
+        let k = Key::Hash([0u8; 32]);
+
+        // adding a named key and then removing another one is merged into one transform
+        let add = tc.add(
+            Validated::new(k, Validated::valid).unwrap(),
+            Validated::new(Value::NamedKey("test3".to_string(), u1), Validated::valid).unwrap(),
+        );
+        assert_matches!(add, Ok(AddResult::Success));
+        let remove = tc.remove_keys(
+            Validated::new(k, Validated::valid).unwrap(),
+            iter::once("test".to_string()).collect(),
+        );
+        assert_matches!(remove, Ok(AddResult::Success));
+
+        let expected_transform = Transform::RemoveKeys {
+            removed: iter::once("test".to_string()).collect(),
+            added: iter::once(("test3".to_string(), u1)).collect(),
+        };
+        assert_eq!(tc.fns.len(), 1);
+        assert_eq!(tc.fns.get(&k), Some(&expected_transform));
+        // removal doesn't commute with additions
+        assert_eq!(tc.ops.get(&k), Some(&Op::Write));
+
+        // the cached value reflects both operations
+        match tc.get(&Validated::new(k, Validated::valid).unwrap()) {
+            Ok(Some(Value::Contract(contract))) => {
+                let names: Vec<&String> = contract.urefs_lookup().keys().collect();
+                assert_eq!(names, vec!["test2", "test3"]);
+            }
+            other => panic!("Expected a contract: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn tracking_copy_rollback_checkpoint() {
+        let db = CountingDb::new_init(Value::Int32(1));
+        let mut tc = TrackingCopy::new(db);
+        let k1 = Key::Hash([0u8; 32]);
+        let k2 = Key::Hash([1u8; 32]);
+
+        let _ = tc.read(&Validated::new(k1, Validated::valid).unwrap());
+        let checkpoint = tc.checkpoint();
+        tc.write(
+            Validated::new(k1, Validated::valid).unwrap(),
+            Validated::new(Value::Int32(2), Validated::valid).unwrap(),
+        );
+        tc.write(
+            Validated::new(k2, Validated::valid).unwrap(),
+            Validated::new(Value::Int32(3), Validated::valid).unwrap(),
+        );
+        tc.rollback(checkpoint);
+
+        // only the read made before the checkpoint is left
+        assert_eq!(tc.fns.len(), 1);
+        assert_eq!(tc.fns.get(&k1), Some(&Transform::Identity));
+        assert_eq!(tc.ops.len(), 1);
+        assert_eq!(tc.ops.get(&k1), Some(&Op::Read));
+        assert!(tc.cache.muts_cached.is_empty());
+        let value = tc.get(&Validated::new(k1, Validated::valid).unwrap());
+        assert_matches!(value, Ok(Some(Value::Int32(1))));
+    }
+
+    #[test]
+    fn tracking_copy_rollback_keeps_reads() {
+        let db = CountingDb::new_init(Value::Int32(1));
+        let mut tc = TrackingCopy::new(db);
+        let k1 = Key::Hash([0u8; 32]);
+        let k2 = Key::Hash([1u8; 32]);
+
+        let checkpoint = tc.checkpoint();
+        let _ = tc.read(&Validated::new(k1, Validated::valid).unwrap());
+        let _ = tc.read(&Validated::new(k2, Validated::valid).unwrap());
+        tc.write(
+            Validated::new(k2, Validated::valid).unwrap(),
+            Validated::new(Value::Int32(2), Validated::valid).unwrap(),
+        );
+        tc.rollback(checkpoint);
+
+        // both keys were read from the underlying state before anything was rolled back
+        assert_eq!(tc.ops.get(&k1), Some(&Op::Read));
+        assert_eq!(tc.fns.get(&k1), Some(&Transform::Identity));
+        assert_eq!(tc.ops.get(&k2), Some(&Op::Read));
+        assert_eq!(tc.fns.get(&k2), Some(&Transform::Identity));
+        assert!(tc.cache.muts_cached.is_empty());
+    }
+
+    #[test]
+    fn tracking_copy_nested_checkpoints() {
+        let db = CountingDb::new_init(Value::Int32(1));
+        let mut tc = TrackingCopy::new(db);
+        let k = Key::Hash([0u8; 32]);
+        let write = |tc: &mut TrackingCopy<CountingDb>, i: i32| {
+            tc.write(
+                Validated::new(k, Validated::valid).unwrap(),
+                Validated::new(Value::Int32(i), Validated::valid).unwrap(),
+            )
+        };
+
+        write(&mut tc, 2);
+        let outer = tc.checkpoint();
+        write(&mut tc, 3);
+        let inner = tc.checkpoint();
+        write(&mut tc, 4);
+        // the innermost write is kept, but still belongs to `outer`
+        tc.release(inner);
+        assert_eq!(tc.fns.get(&k), Some(&Transform::Write(Value::Int32(4))));
+        tc.rollback(outer);
+
+        assert_eq!(tc.fns.get(&k), Some(&Transform::Write(Value::Int32(2))));
+        assert_eq!(tc.ops.get(&k), Some(&Op::Write));
+        assert_eq!(tc.cache.muts_cached.get(&k), Some(&Value::Int32(2)));
+
+        // rolling back a checkpoint also rolls back the checkpoints nested in it
+        let outer = tc.checkpoint();
+        write(&mut tc, 5);
+        let _inner = tc.checkpoint();
+        write(&mut tc, 6);
+        tc.rollback(outer);
+        assert_eq!(tc.cache.muts_cached.get(&k), Some(&Value::Int32(2)));
+        assert!(tc.journal.is_empty());
+    }
+
     #[test]
     fn tracking_copy_rw() {
         let counter = Rc::new(Cell::new(0));
@@ -575,14 +896,71 @@ mod tests {
         assert_eq!(tc.ops.get(&k), Some(&Op::Write));
     }
 
+    #[test]
+    fn query_follows_key_values_and_local_keys() {
+        let local_key_bytes = String::from("counter").to_bytes().unwrap();
+        let contract_key = Key::Hash([1u8; 32]);
+        let local_key = local_key(local_seed(contract_key), &local_key_bytes);
+        let named_key_key = Key::URef([2u8; 32], AccessRights::READ);
+        let key_key = Key::URef([3u8; 32], AccessRights::READ);
+        let known_urefs = iter::once(("named".to_string(), named_key_key)).collect();
+        let contract: Value = Contract::new(vec![], known_urefs, 1).into();
+        let gs = InMemoryGlobalState::from_pairs(&[
+            (contract_key, contract),
+            (named_key_key, Value::NamedKey("key".to_string(), key_key)),
+            (key_key, Value::Key(local_key)),
+            (local_key, Value::Int32(7)),
+        ])
+        .unwrap();
+        let mut tc = TrackingCopy::new(gs);
+
+        let path = vec![
+            "named".to_string(),
+            DEREF_SEGMENT.to_string(),
+            DEREF_SEGMENT.to_string(),
+        ];
+        match tc.query(contract_key, &path) {
+            Ok(QueryResult::Success(value, keys)) => {
+                assert_eq!(value, Value::Int32(7));
+                assert_eq!(keys, vec![contract_key, named_key_key, key_key, local_key]);
+            }
+            other => panic!("Query failed when it should not have: {:?}", other),
+        }
+
+        let hex: String = local_key_bytes
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        let path = vec![format!("{}{}", LOCAL_SEGMENT_PREFIX, hex)];
+        match tc.query(contract_key, &path) {
+            Ok(QueryResult::Success(value, keys)) => {
+                assert_eq!(value, Value::Int32(7));
+                assert_eq!(keys, vec![contract_key, local_key]);
+            }
+            other => panic!("Query failed when it should not have: {:?}", other),
+        }
+
+        let invalid_local = vec![format!("{}zz", LOCAL_SEGMENT_PREFIX)];
+        assert_matches!(
+            tc.query(contract_key, &invalid_local),
+            Ok(QueryResult::ValueNotFound(_))
+        );
+        // Only values which are keys can be dereferenced.
+        assert_matches!(
+            tc.query(local_key, &[DEREF_SEGMENT.to_string()]),
+            Ok(QueryResult::ValueNotFound(_))
+        );
+    }
+
     proptest! {
         #[test]
         fn query_empty_path(k in key_arb(), missing_key in key_arb(), v in value_arb()) {
             let gs = InMemoryGlobalState::from_pairs(&[(k, v.to_owned())]).unwrap();
             let mut tc = TrackingCopy::new(gs);
             let empty_path = Vec::new();
-            if let Ok(QueryResult::Success(result)) = tc.query(k, &empty_path) {
+            if let Ok(QueryResult::Success(result, keys)) = tc.query(k, &empty_path) {
                 assert_eq!(v, result);
+                assert_eq!(keys, vec![k]);
             } else {
                 panic!("Query failed when it should not have!");
             }
@@ -613,8 +991,9 @@ mod tests {
             ]).unwrap();
             let mut tc = TrackingCopy::new(gs);
             let path = vec!(name.clone());
-            if let Ok(QueryResult::Success(result)) = tc.query(contract_key, &path) {
+            if let Ok(QueryResult::Success(result, keys)) = tc.query(contract_key, &path) {
                 assert_eq!(v, result);
+                assert_eq!(keys, vec![contract_key, k]);
             } else {
                 panic!("Query failed when it should not have!");
             }
@@ -643,6 +1022,8 @@ mod tests {
                 nonce,
                 known_urefs,
                 associated_keys,
+                PurseId::new([0u8; 32]),
+                ActionThresholds::default(),
             );
             let account_key = Key::Account(address);
 
@@ -652,8 +1033,9 @@ mod tests {
             ]).unwrap();
             let mut tc = TrackingCopy::new(gs);
             let path = vec!(name.clone());
-            if let Ok(QueryResult::Success(result)) = tc.query(account_key, &path) {
+            if let Ok(QueryResult::Success(result, keys)) = tc.query(account_key, &path) {
                 assert_eq!(v, result);
+                assert_eq!(keys, vec![account_key, k]);
             } else {
                 panic!("Query failed when it should not have!");
             }
@@ -691,6 +1073,8 @@ mod tests {
                 nonce,
                 account_known_urefs,
                 associated_keys,
+                PurseId::new([0u8; 32]),
+                ActionThresholds::default(),
             );
             let account_key = Key::Account(address);
 
@@ -701,8 +1085,9 @@ mod tests {
             ]).unwrap();
             let mut tc = TrackingCopy::new(gs);
             let path = vec!(contract_name, state_name);
-            if let Ok(QueryResult::Success(result)) = tc.query(account_key, &path) {
+            if let Ok(QueryResult::Success(result, keys)) = tc.query(account_key, &path) {
                 assert_eq!(v, result);
+                assert_eq!(keys, vec![account_key, contract_key, k]);
             } else {
                 panic!("Query failed when it should not have!");
             }
@@ -0,0 +1,182 @@
+use common::contract_api::TransferResult;
+use common::key::Key;
+use common::value::account::{Account, PurseId};
+use common::value::{Value, U512};
+use shared::newtypes::Validated;
+use shared::transform::TypeMismatch;
+use storage::global_state::StateReader;
+
+use execution::Error;
+use tracking_copy::{AddResult, TrackingCopy};
+
+/// Moves `amount` from the main purse of `source` to the main purse of the account
+/// living under `Key::Account(target)`.
+///
+/// If there is no account under that key yet, a new one is created. Its main purse
+/// is identified by the id returned by `new_purse_id`, which is only called in that
+/// case, and holds the transferred amount.
+///
+/// Transfers are performed by the system, so keys used here are not validated
+/// against the known urefs of the caller.
+pub fn transfer_to_account<R, F>(
+    tracking_copy: &mut TrackingCopy<R>,
+    source: &Account,
+    target: [u8; 32],
+    amount: U512,
+    new_purse_id: F,
+) -> Result<TransferResult, Error>
+where
+    R: StateReader<Key, Value>,
+    R::Error: Into<Error>,
+    F: FnOnce() -> PurseId,
+{
+    let source_balance_key = Validated::new(source.purse_id().balance_key(), Validated::valid)?;
+    let source_balance = match tracking_copy
+        .get(&source_balance_key)
+        .map_err(Into::<Error>::into)?
+    {
+        Some(Value::UInt512(balance)) => balance,
+        Some(other) => {
+            return Err(Error::TypeMismatch(TypeMismatch::new(
+                "UInt512".to_string(),
+                other.type_string(),
+            )))
+        }
+        None => return Err(Error::KeyNotFound(*source_balance_key)),
+    };
+    let new_source_balance = source_balance
+        .checked_sub(amount)
+        .ok_or(Error::InsufficientFunds)?;
+    tracking_copy.write(
+        source_balance_key,
+        Validated::new(Value::UInt512(new_source_balance), Validated::valid)?,
+    );
+
+    let target_key = Validated::new(Key::Account(target), Validated::valid)?;
+    match tracking_copy
+        .get(&target_key)
+        .map_err(Into::<Error>::into)?
+    {
+        Some(Value::Account(account)) => {
+            let target_balance_key =
+                Validated::new(account.purse_id().balance_key(), Validated::valid)?;
+            let amount = Validated::new(Value::UInt512(amount), Validated::valid)?;
+            match tracking_copy
+                .add(target_balance_key, amount)
+                .map_err(Into::<Error>::into)?
+            {
+                AddResult::Success => Ok(TransferResult::TransferredToExistingAccount),
+                AddResult::KeyNotFound(key) => Err(Error::KeyNotFound(key)),
+                AddResult::TypeMismatch(type_mismatch) => Err(Error::TypeMismatch(type_mismatch)),
+                AddResult::Overflow => Err(Error::Overflow),
+            }
+        }
+        Some(other) => Err(Error::TypeMismatch(TypeMismatch::new(
+            "Account".to_string(),
+            other.type_string(),
+        ))),
+        None => {
+            let new_purse_id = new_purse_id();
+            let target_balance_key = Validated::new(new_purse_id.balance_key(), Validated::valid)?;
+            tracking_copy.write(
+                target_balance_key,
+                Validated::new(Value::UInt512(amount), Validated::valid)?,
+            );
+            let account = Account::create(target, new_purse_id);
+            tracking_copy.write(
+                target_key,
+                Validated::new(Value::Account(account), Validated::valid)?,
+            );
+            Ok(TransferResult::TransferredToNewAccount)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::contract_api::TransferResult;
+    use common::key::Key;
+    use common::value::account::{Account, PurseId};
+    use common::value::{Value, U512};
+    use shared::init::{mocked_account, MOCKED_ACCOUNT_BALANCE};
+    use shared::newtypes::Validated;
+    use storage::global_state::in_memory::InMemoryGlobalState;
+
+    use super::transfer_to_account;
+    use execution::Error;
+    use tracking_copy::TrackingCopy;
+
+    const SOURCE: [u8; 32] = [1u8; 32];
+    const TARGET: [u8; 32] = [2u8; 32];
+
+    fn setup(pairs: &[(Key, Value)]) -> (TrackingCopy<InMemoryGlobalState>, Account) {
+        let gs = InMemoryGlobalState::from_pairs(pairs).unwrap();
+        let mut tc = TrackingCopy::new(gs);
+        let key = Validated::new(Key::Account(SOURCE), Validated::valid).unwrap();
+        let account = match tc.get(&key).unwrap() {
+            Some(Value::Account(account)) => account,
+            _ => panic!("Source account should exist"),
+        };
+        (tc, account)
+    }
+
+    fn balance(tc: &mut TrackingCopy<InMemoryGlobalState>, purse_id: PurseId) -> Value {
+        let key = Validated::new(purse_id.balance_key(), Validated::valid).unwrap();
+        tc.get(&key).unwrap().expect("Purse should exist")
+    }
+
+    #[test]
+    fn transfer_creates_new_account() {
+        let (mut tc, source) = setup(&mocked_account(SOURCE));
+        let new_purse_id = PurseId::new([3u8; 32]);
+        let result =
+            transfer_to_account(&mut tc, &source, TARGET, U512::from(10), || new_purse_id).unwrap();
+        assert_eq!(result, TransferResult::TransferredToNewAccount);
+
+        let key = Validated::new(Key::Account(TARGET), Validated::valid).unwrap();
+        let expected_account = Account::create(TARGET, new_purse_id);
+        assert_eq!(
+            tc.get(&key).unwrap(),
+            Some(Value::Account(expected_account))
+        );
+        assert_eq!(
+            balance(&mut tc, new_purse_id),
+            Value::UInt512(U512::from(10))
+        );
+        assert_eq!(
+            balance(&mut tc, source.purse_id()),
+            Value::UInt512(U512::from(MOCKED_ACCOUNT_BALANCE - 10))
+        );
+    }
+
+    #[test]
+    fn transfer_to_existing_account() {
+        let mut pairs = mocked_account(SOURCE);
+        pairs.extend(mocked_account(TARGET));
+        let (mut tc, source) = setup(&pairs);
+        let result = transfer_to_account(&mut tc, &source, TARGET, U512::from(10), || {
+            panic!("No purse should be created for an existing account")
+        })
+        .unwrap();
+        assert_eq!(result, TransferResult::TransferredToExistingAccount);
+
+        let target_purse_id = match &pairs[2].1 {
+            Value::Account(account) => account.purse_id(),
+            _ => panic!("Target account should exist"),
+        };
+        assert_eq!(
+            balance(&mut tc, target_purse_id),
+            Value::UInt512(U512::from(MOCKED_ACCOUNT_BALANCE + 10))
+        );
+    }
+
+    #[test]
+    fn transfer_with_insufficient_funds() {
+        let (mut tc, source) = setup(&mocked_account(SOURCE));
+        let amount = U512::from(MOCKED_ACCOUNT_BALANCE) + U512::from(1);
+        let result =
+            transfer_to_account(&mut tc, &source, TARGET, amount, || PurseId::new([3u8; 32]));
+        assert_matches!(result, Err(Error::InsufficientFunds));
+        assert!(tc.effect().1.is_empty());
+    }
+}
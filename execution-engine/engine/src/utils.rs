@@ -2,6 +2,10 @@ use std::collections::HashMap;
 use std::hash::Hash;
 use std::ops::Add;
 
+use common::key::{Key, LOCAL_SEED_SIZE};
+use shared::newtypes::Blake2bHash;
+use shared::transform::{Arithmetic, Transform};
+
 pub fn add<K, V>(map: &mut HashMap<K, V>, k: K, v: V)
 where
     K: Eq + Hash,
@@ -17,3 +21,33 @@ where
         }
     }
 }
+
+/// Combines `transform` with the transform of `key` in `map`, following `arithmetic`.
+pub fn add_transform(
+    map: &mut HashMap<Key, Transform>,
+    key: Key,
+    transform: Transform,
+    arithmetic: Arithmetic,
+) {
+    let combined = match map.remove(&key) {
+        None => transform,
+        Some(current) => current.add_with(transform, arithmetic),
+    };
+    map.insert(key, combined);
+}
+
+/// Seed of the local keys of the context with the given base `key`.
+pub fn local_seed(key: Key) -> [u8; LOCAL_SEED_SIZE] {
+    match key {
+        Key::Account(bytes) => bytes,
+        Key::Hash(bytes) => bytes,
+        Key::URef(bytes, _) => bytes,
+        Key::Local { seed, key_hash } => Blake2bHash::new(&[seed, key_hash].concat()).into(),
+    }
+}
+
+/// Global state key of the local key serialized as `key_bytes`, under `seed`.
+pub fn local_key(seed: [u8; LOCAL_SEED_SIZE], key_bytes: &[u8]) -> Key {
+    let key_hash = Blake2bHash::new(key_bytes).into();
+    Key::Local { seed, key_hash }
+}
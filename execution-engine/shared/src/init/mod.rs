@@ -1,8 +1,13 @@
 use std::collections::btree_map::BTreeMap;
 
 use common::key::Key;
-use common::value::account::{AssociatedKeys, PublicKey, Weight};
-use common::value::{Account, Value};
+use common::value::account::{ActionThresholds, AssociatedKeys, PublicKey, PurseId, Weight};
+use common::value::{Account, Value, U512};
+
+use crate::newtypes::Blake2bHash;
+
+/// Balance of the main purse of the mocked account.
+pub const MOCKED_ACCOUNT_BALANCE: u64 = 1_000_000_000;
 
 pub fn mocked_account(account_addr: [u8; 32]) -> Vec<(Key, Value)> {
     let associated_keys = {
@@ -12,6 +17,24 @@ pub fn mocked_account(account_addr: [u8; 32]) -> Vec<(Key, Value)> {
             .unwrap();
         associated_keys
     };
-    let account = Account::new(account_addr, 0, BTreeMap::new(), associated_keys);
-    vec![(Key::Account(account_addr), Value::Account(account))]
+    let purse_id = {
+        let mut id = [0u8; 32];
+        id.copy_from_slice(&Blake2bHash::new(&account_addr).to_vec());
+        PurseId::new(id)
+    };
+    let account = Account::new(
+        account_addr,
+        0,
+        BTreeMap::new(),
+        associated_keys,
+        purse_id,
+        ActionThresholds::default(),
+    );
+    vec![
+        (Key::Account(account_addr), Value::Account(account)),
+        (
+            purse_id.balance_key(),
+            Value::UInt512(U512::from(MOCKED_ACCOUNT_BALANCE)),
+        ),
+    ]
 }
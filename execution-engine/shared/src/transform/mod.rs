@@ -1,11 +1,11 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::TryFrom;
 use std::fmt;
 use std::ops::Add;
 
 use common::key::Key;
 use common::value::{Value, U128, U256, U512};
-use num::traits::{ToPrimitive, WrappingAdd, WrappingSub};
+use num::traits::{CheckedAdd, CheckedSub, ToPrimitive, WrappingAdd, WrappingSub};
 
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct TypeMismatch {
@@ -28,6 +28,28 @@ impl TypeMismatch {
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub enum Error {
     TypeMismatch(TypeMismatch),
+    Overflow,
+}
+
+/// How numeric additions treat results which don't fit in their type.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Arithmetic {
+    /// Results wrap around.
+    Wrapping,
+    /// Results which don't fit are an `Overflow` error.
+    Checked,
+}
+
+impl Arithmetic {
+    /// Protocol version 1 wraps around, later versions are checked. Requests which don't
+    /// set a protocol version keep the original wrapping semantics.
+    pub fn from_version(protocol_version: u64) -> Arithmetic {
+        if protocol_version >= 2 {
+            Arithmetic::Checked
+        } else {
+            Arithmetic::Wrapping
+        }
+    }
 }
 
 impl From<TypeMismatch> for Error {
@@ -41,10 +63,17 @@ pub enum Transform {
     Identity,
     Write(Value),
     AddInt32(i32),
+    AddUInt64(u64),
     AddUInt128(U128),
     AddUInt256(U256),
     AddUInt512(U512),
     AddKeys(BTreeMap<String, Key>),
+    /// Removes named keys from an account or a contract and then inserts `added` ones.
+    /// Keeping both sets allows `RemoveKeys` to be combined with `AddKeys`.
+    RemoveKeys {
+        removed: BTreeSet<String>,
+        added: BTreeMap<String, Key>,
+    },
     Failure(Error),
 }
 
@@ -73,6 +102,7 @@ use self::Transform::*;
 
 from_try_from_impl!(Value, Write);
 from_try_from_impl!(i32, AddInt32);
+from_try_from_impl!(u64, AddUInt64);
 from_try_from_impl!(U128, AddUInt128);
 from_try_from_impl!(U256, AddUInt256);
 from_try_from_impl!(U512, AddUInt512);
@@ -96,10 +126,38 @@ where
     }
 }
 
-/// Attempts to add `i` to `v`, assuming `v` is of type `expected`
-fn wrapping_addition<T>(i: T, v: Value, expected: &str) -> Result<Value, Error>
+/// Attempts to add `j` to `i`, failing if the result doesn't fit in `T`
+fn i32_checked_addition<T>(i: T, j: i32) -> Result<T, Error>
 where
-    T: Into<Value> + TryFrom<Value, Error = String> + WrappingAdd,
+    T: CheckedAdd + CheckedSub + From<u32>,
+{
+    let result = if j > 0 {
+        // NOTE: This value is greater than 0 so conversion is safe.
+        let j_unsigned = j.to_u32().unwrap();
+        i.checked_add(&j_unsigned.into())
+    } else {
+        // NOTE: The absolute value of any i32 fits in u32.
+        let j_abs = i64::from(j).abs().to_u32().unwrap();
+        i.checked_sub(&j_abs.into())
+    };
+    result.ok_or(Error::Overflow)
+}
+
+/// Attempts to add `j` to `i` following `arithmetic`
+fn i32_addition<T>(i: T, j: i32, arithmetic: Arithmetic) -> Result<T, Error>
+where
+    T: WrappingAdd + WrappingSub + CheckedAdd + CheckedSub + From<u32>,
+{
+    match arithmetic {
+        Arithmetic::Wrapping => Ok(i32_wrapping_addition(i, j)),
+        Arithmetic::Checked => i32_checked_addition(i, j),
+    }
+}
+
+/// Attempts to add `i` to `v` following `arithmetic`, assuming `v` is of type `expected`
+fn addition<T>(i: T, v: Value, expected: &str, arithmetic: Arithmetic) -> Result<Value, Error>
+where
+    T: Into<Value> + TryFrom<Value, Error = String> + WrappingAdd + CheckedAdd,
 {
     match T::try_from(v) {
         Err(v_type) => Err(TypeMismatch {
@@ -108,20 +166,35 @@ where
         }
         .into()),
 
-        Ok(j) => Ok(j.wrapping_add(&i).into()),
+        Ok(j) => match arithmetic {
+            Arithmetic::Wrapping => Ok(j.wrapping_add(&i).into()),
+            Arithmetic::Checked => j.checked_add(&i).map(Into::into).ok_or(Error::Overflow),
+        },
     }
 }
 
 impl Transform {
+    /// Applies the transform to `v`, with numeric additions wrapping around.
     pub fn apply(self, v: Value) -> Result<Value, Error> {
+        self.apply_with(v, Arithmetic::Wrapping)
+    }
+
+    /// Applies the transform to `v`, with numeric additions following `arithmetic`.
+    pub fn apply_with(self, v: Value, arithmetic: Arithmetic) -> Result<Value, Error> {
         match self {
             Identity => Ok(v),
             Write(w) => Ok(w),
             AddInt32(i) => match v {
-                Value::Int32(j) => Ok(Value::Int32(j.wrapping_add(i))),
-                Value::UInt128(j) => Ok(Value::UInt128(i32_wrapping_addition(j, i))),
-                Value::UInt256(j) => Ok(Value::UInt256(i32_wrapping_addition(j, i))),
-                Value::UInt512(j) => Ok(Value::UInt512(i32_wrapping_addition(j, i))),
+                Value::Int32(j) => match arithmetic {
+                    Arithmetic::Wrapping => Ok(Value::Int32(j.wrapping_add(i))),
+                    Arithmetic::Checked => {
+                        j.checked_add(i).map(Value::Int32).ok_or(Error::Overflow)
+                    }
+                },
+                Value::UInt64(j) => i32_addition(j, i, arithmetic).map(Value::UInt64),
+                Value::UInt128(j) => i32_addition(j, i, arithmetic).map(Value::UInt128),
+                Value::UInt256(j) => i32_addition(j, i, arithmetic).map(Value::UInt256),
+                Value::UInt512(j) => i32_addition(j, i, arithmetic).map(Value::UInt512),
                 other => {
                     let expected = String::from("Int32");
                     Err(TypeMismatch {
@@ -131,9 +204,10 @@ impl Transform {
                     .into())
                 }
             },
-            AddUInt128(i) => wrapping_addition(i, v, "UInt128"),
-            AddUInt256(i) => wrapping_addition(i, v, "UInt256"),
-            AddUInt512(i) => wrapping_addition(i, v, "UInt512"),
+            AddUInt64(i) => addition(i, v, "UInt64", arithmetic),
+            AddUInt128(i) => addition(i, v, "UInt128", arithmetic),
+            AddUInt256(i) => addition(i, v, "UInt256", arithmetic),
+            AddUInt512(i) => addition(i, v, "UInt512", arithmetic),
             AddKeys(mut keys) => match v {
                 Value::Contract(mut c) => {
                     c.insert_urefs(&mut keys);
@@ -152,21 +226,59 @@ impl Transform {
                     .into())
                 }
             },
+            RemoveKeys { removed, mut added } => match v {
+                Value::Contract(mut c) => {
+                    c.remove_urefs(&removed);
+                    c.insert_urefs(&mut added);
+                    Ok(c.into())
+                }
+                Value::Account(mut a) => {
+                    a.remove_urefs(&removed);
+                    a.insert_urefs(&mut added);
+                    Ok(Value::Account(a))
+                }
+                other => {
+                    let expected = String::from("Contract or Account");
+                    Err(TypeMismatch {
+                        expected,
+                        found: other.type_string(),
+                    }
+                    .into())
+                }
+            },
             Failure(error) => Err(error),
         }
     }
 }
 
+/// Combines the addition of `i` with an `AddInt32(j)` following `arithmetic`. Under checked
+/// arithmetic, a negative sum which `T` can't hold is kept as an `AddInt32`: it lies between
+/// `j` and zero, so it fits.
+fn i32_transform_addition<T>(i: T, j: i32, arithmetic: Arithmetic) -> Transform
+where
+    T: WrappingAdd + WrappingSub + CheckedAdd + CheckedSub + From<u32> + ToPrimitive + Copy,
+    T: Into<Transform>,
+{
+    match i32_addition(i, j, arithmetic) {
+        Ok(sum) => sum.into(),
+        Err(error) => match i.to_i64().and_then(|i| (i64::from(j) + i).to_i32()) {
+            Some(sum) if j < 0 => AddInt32(sum),
+            _ => Failure(error),
+        },
+    }
+}
+
 /// Combines numeric `Transform`s into a single `Transform`. This is
 /// done by unwrapping the `Transform` to obtain the underlying value,
-/// performing the wrapping addition then wrapping up as a `Transform`
-/// again.
-fn wrapped_transform_addition<T>(i: T, b: Transform, expected: &str) -> Transform
+/// performing the addition following `arithmetic` then wrapping up as a
+/// `Transform` again.
+fn transform_addition<T>(i: T, b: Transform, expected: &str, arithmetic: Arithmetic) -> Transform
 where
-    T: WrappingAdd + WrappingSub + From<u32> + Into<Transform> + TryFrom<Transform, Error = String>,
+    T: WrappingAdd + WrappingSub + CheckedAdd + CheckedSub + From<u32> + ToPrimitive + Copy,
+    T: Into<Transform> + TryFrom<Transform, Error = String>,
 {
     if let Transform::AddInt32(j) = b {
-        i32_wrapping_addition(i, j).into()
+        i32_transform_addition(i, j, arithmetic)
     } else {
         match T::try_from(b) {
             Err(b_type) => Failure(
@@ -177,7 +289,12 @@ where
                 .into(),
             ),
 
-            Ok(j) => i.wrapping_add(&j).into(),
+            Ok(j) => match arithmetic {
+                Arithmetic::Wrapping => i.wrapping_add(&j).into(),
+                Arithmetic::Checked => i
+                    .checked_add(&j)
+                    .map_or(Failure(Error::Overflow), Into::into),
+            },
         }
     }
 }
@@ -185,7 +302,17 @@ where
 impl Add for Transform {
     type Output = Transform;
 
+    /// Combines the transforms with numeric additions wrapping around.
     fn add(self, other: Transform) -> Transform {
+        self.add_with(other, Arithmetic::Wrapping)
+    }
+}
+
+impl Transform {
+    /// Combines this transform with `other`, which follows it, into a single transform.
+    /// Numeric additions are summed up following `arithmetic`, so that under checked
+    /// arithmetic the combined transform only overflows where applying the sum does.
+    pub fn add_with(self, other: Transform, arithmetic: Arithmetic) -> Transform {
         match (self, other) {
             (a, Identity) => a,
             (Identity, b) => b,
@@ -194,15 +321,22 @@ impl Add for Transform {
             (_, b @ Write(_)) => b,
             (Write(v), b) => {
                 // second transform changes value being written
-                match b.apply(v) {
+                match b.apply_with(v, arithmetic) {
                     Err(error) => Failure(error),
                     Ok(new_value) => Write(new_value),
                 }
             }
             (AddInt32(i), b) => match b {
-                AddInt32(j) => AddInt32(i.wrapping_add(j)),
-                AddUInt256(j) => AddUInt256(i32_wrapping_addition(j, i)),
-                AddUInt512(j) => AddUInt512(i32_wrapping_addition(j, i)),
+                AddInt32(j) => match arithmetic {
+                    Arithmetic::Wrapping => AddInt32(i.wrapping_add(j)),
+                    Arithmetic::Checked => {
+                        i.checked_add(j).map_or(Failure(Error::Overflow), AddInt32)
+                    }
+                },
+                AddUInt64(j) => i32_transform_addition(j, i, arithmetic),
+                AddUInt128(j) => i32_transform_addition(j, i, arithmetic),
+                AddUInt256(j) => i32_transform_addition(j, i, arithmetic),
+                AddUInt512(j) => i32_transform_addition(j, i, arithmetic),
                 other => Failure(
                     TypeMismatch {
                         expected: "AddInt32".to_owned(),
@@ -211,14 +345,25 @@ impl Add for Transform {
                     .into(),
                 ),
             },
-            (AddUInt128(i), b) => wrapped_transform_addition(i, b, "U128"),
-            (AddUInt256(i), b) => wrapped_transform_addition(i, b, "U256"),
-            (AddUInt512(i), b) => wrapped_transform_addition(i, b, "U512"),
+            (AddUInt64(i), b) => transform_addition(i, b, "U64", arithmetic),
+            (AddUInt128(i), b) => transform_addition(i, b, "U128", arithmetic),
+            (AddUInt256(i), b) => transform_addition(i, b, "U256", arithmetic),
+            (AddUInt512(i), b) => transform_addition(i, b, "U512", arithmetic),
             (AddKeys(mut ks1), b) => match b {
                 AddKeys(mut ks2) => {
                     ks1.append(&mut ks2);
                     AddKeys(ks1)
                 }
+                RemoveKeys { removed, mut added } => {
+                    for name in &removed {
+                        ks1.remove(name);
+                    }
+                    ks1.append(&mut added);
+                    RemoveKeys {
+                        removed,
+                        added: ks1,
+                    }
+                }
                 other => Failure(
                     TypeMismatch {
                         expected: "AddKeys".to_owned(),
@@ -227,6 +372,36 @@ impl Add for Transform {
                     .into(),
                 ),
             },
+            (
+                RemoveKeys {
+                    mut removed,
+                    mut added,
+                },
+                b,
+            ) => match b {
+                AddKeys(mut ks2) => {
+                    added.append(&mut ks2);
+                    RemoveKeys { removed, added }
+                }
+                RemoveKeys {
+                    removed: mut removed2,
+                    added: mut added2,
+                } => {
+                    for name in &removed2 {
+                        added.remove(name);
+                    }
+                    removed.append(&mut removed2);
+                    added.append(&mut added2);
+                    RemoveKeys { removed, added }
+                }
+                other => Failure(
+                    TypeMismatch {
+                        expected: "AddKeys or RemoveKeys".to_owned(),
+                        found: format!("{:?}", other),
+                    }
+                    .into(),
+                ),
+            },
         }
     }
 }
@@ -239,11 +414,15 @@ impl fmt::Display for Transform {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
     use num::{Bounded, Num};
 
-    use common::value::{Value, U128, U256, U512};
+    use common::key::{AccessRights, Key};
+    use common::value::account::{ActionThresholds, AssociatedKeys, PublicKey, PurseId, Weight};
+    use common::value::{Account, Value, U128, U256, U512};
 
-    use super::Transform;
+    use super::{Arithmetic, Error, Transform};
 
     #[test]
     fn i32_overflow() {
@@ -298,6 +477,11 @@ mod tests {
         assert_eq!(transform_underflow, max.into());
     }
 
+    #[test]
+    fn u64_overflow() {
+        uint_overflow_test::<u64>();
+    }
+
     #[test]
     fn u128_overflow() {
         uint_overflow_test::<U128>();
@@ -312,4 +496,170 @@ mod tests {
     fn u512_overflow() {
         uint_overflow_test::<U512>();
     }
+
+    #[test]
+    fn i32_checked_overflow() {
+        let max = std::i32::MAX;
+        let min = std::i32::MIN;
+
+        let apply_overflow = Transform::AddInt32(1).apply_with(max.into(), Arithmetic::Checked);
+        let apply_underflow = Transform::AddInt32(-1).apply_with(min.into(), Arithmetic::Checked);
+        let apply_in_range = Transform::AddInt32(min).apply_with(max.into(), Arithmetic::Checked);
+
+        assert_eq!(apply_overflow, Err(Error::Overflow));
+        assert_eq!(apply_underflow, Err(Error::Overflow));
+        assert_eq!(apply_in_range, Ok(Value::Int32(-1)));
+    }
+
+    fn uint_checked_overflow_test<T>()
+    where
+        T: Num + Bounded + Into<Value> + Into<Transform> + Copy,
+    {
+        let max = T::max_value();
+        let min = T::min_value();
+        let one = T::one();
+
+        let one_transform: Transform = one.into();
+
+        let apply_overflow = Transform::AddInt32(1).apply_with(max.into(), Arithmetic::Checked);
+        let apply_overflow_uint = one_transform.apply_with(max.into(), Arithmetic::Checked);
+        let apply_underflow = Transform::AddInt32(-1).apply_with(min.into(), Arithmetic::Checked);
+        let apply_min_i32 =
+            Transform::AddInt32(std::i32::MIN).apply_with(min.into(), Arithmetic::Checked);
+        let apply_in_range = Transform::AddInt32(-1).apply_with(max.into(), Arithmetic::Checked);
+
+        assert_eq!(apply_overflow, Err(Error::Overflow));
+        assert_eq!(apply_overflow_uint, Err(Error::Overflow));
+        assert_eq!(apply_underflow, Err(Error::Overflow));
+        assert_eq!(apply_min_i32, Err(Error::Overflow));
+        assert_eq!(apply_in_range, Ok((max - one).into()));
+    }
+
+    #[test]
+    fn uint_checked_overflow() {
+        uint_checked_overflow_test::<u64>();
+        uint_checked_overflow_test::<U128>();
+        uint_checked_overflow_test::<U256>();
+        uint_checked_overflow_test::<U512>();
+    }
+
+    #[test]
+    fn checked_combination_keeps_negative_sums() {
+        let combined =
+            Transform::AddUInt512(3.into()).add_with(Transform::AddInt32(-5), Arithmetic::Checked);
+        assert_eq!(combined, Transform::AddInt32(-2));
+        assert_eq!(
+            combined.apply_with(Value::UInt512(10.into()), Arithmetic::Checked),
+            Ok(Value::UInt512(8.into()))
+        );
+
+        let combined =
+            Transform::AddInt32(-5).add_with(Transform::AddUInt64(7), Arithmetic::Checked);
+        assert_eq!(combined, Transform::AddUInt64(2));
+    }
+
+    #[test]
+    fn checked_combination_overflow() {
+        let max = Transform::AddUInt64(std::u64::MAX);
+        let overflow = Transform::Failure(Error::Overflow);
+
+        assert_eq!(
+            max.clone()
+                .add_with(Transform::AddUInt64(1), Arithmetic::Checked),
+            overflow
+        );
+        assert_eq!(
+            max.clone()
+                .add_with(Transform::AddInt32(1), Arithmetic::Checked),
+            overflow
+        );
+        assert_eq!(
+            max.add_with(Transform::AddUInt64(1), Arithmetic::Wrapping),
+            Transform::AddUInt64(0)
+        );
+
+        let write = Transform::Write(Value::Int32(std::i32::MAX));
+        assert_eq!(
+            write.add_with(Transform::AddInt32(1), Arithmetic::Checked),
+            overflow
+        );
+    }
+
+    #[test]
+    fn arithmetic_of_protocol_versions() {
+        assert_eq!(Arithmetic::from_version(1), Arithmetic::Wrapping);
+        assert_eq!(Arithmetic::from_version(2), Arithmetic::Checked);
+    }
+
+    fn named_keys(names: &[&str]) -> BTreeMap<String, Key> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let key = Key::URef([i as u8; 32], AccessRights::READ);
+                (name.to_string(), key)
+            })
+            .collect()
+    }
+
+    fn names(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn remove_keys_from_account() {
+        let addr = [1u8; 32];
+        let account = Account::new(
+            addr,
+            0,
+            named_keys(&["a", "b"]),
+            AssociatedKeys::new(PublicKey::new(addr), Weight::new(1)),
+            PurseId::new([2u8; 32]),
+            ActionThresholds::default(),
+        );
+        let transform = Transform::RemoveKeys {
+            removed: names(&["a", "c"]),
+            added: BTreeMap::new(),
+        };
+        match transform.apply(Value::Account(account)) {
+            Ok(Value::Account(account)) => {
+                let remaining: Vec<&String> = account.urefs_lookup().keys().collect();
+                assert_eq!(remaining, vec!["b"]);
+            }
+            other => panic!("Expected an account: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn add_keys_then_remove_keys() {
+        let transform = Transform::AddKeys(named_keys(&["a", "b"]))
+            + Transform::RemoveKeys {
+                removed: names(&["a", "c"]),
+                added: BTreeMap::new(),
+            };
+        let mut expected_added = named_keys(&["a", "b"]);
+        expected_added.remove("a");
+        assert_eq!(
+            transform,
+            Transform::RemoveKeys {
+                removed: names(&["a", "c"]),
+                added: expected_added,
+            }
+        );
+    }
+
+    #[test]
+    fn remove_keys_then_add_keys() {
+        let transform = Transform::RemoveKeys {
+            removed: names(&["a"]),
+            added: BTreeMap::new(),
+        } + Transform::AddKeys(named_keys(&["a"]));
+        assert_eq!(
+            transform,
+            Transform::RemoveKeys {
+                removed: names(&["a"]),
+                added: named_keys(&["a"]),
+            }
+        );
+    }
 }
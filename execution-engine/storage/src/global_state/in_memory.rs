@@ -5,7 +5,7 @@ use std::sync::Arc;
 use common::key::Key;
 use common::value::Value;
 use shared::newtypes::Blake2bHash;
-use shared::transform::Transform;
+use shared::transform::{Arithmetic, Transform};
 
 use error;
 use global_state::StateReader;
@@ -123,12 +123,14 @@ impl History for InMemoryGlobalState {
         &mut self,
         prestate_hash: Blake2bHash,
         effects: HashMap<Key, Transform>,
+        arithmetic: Arithmetic,
     ) -> Result<CommitResult, Self::Error> {
         let commit_result = commit::<InMemoryEnvironment, InMemoryTrieStore, _, Self::Error>(
             &self.environment,
             &self.store,
             prestate_hash,
             effects,
+            arithmetic,
         )?;
         if let CommitResult::Success(root_hash) = commit_result {
             self.root_hash = root_hash;
@@ -218,7 +220,10 @@ mod tests {
             .map(|TestPair { key, value }| (key, Transform::Write(value)))
             .collect();
 
-        let updated_hash = match state.commit(root_hash, effects).unwrap() {
+        let updated_hash = match state
+            .commit(root_hash, effects, Arithmetic::Wrapping)
+            .unwrap()
+        {
             CommitResult::Success(hash) => hash,
             _ => panic!("commit failed"),
         };
@@ -245,7 +250,10 @@ mod tests {
             tmp
         };
 
-        let updated_hash = match state.commit(root_hash, effects).unwrap() {
+        let updated_hash = match state
+            .commit(root_hash, effects, Arithmetic::Wrapping)
+            .unwrap()
+        {
             CommitResult::Success(hash) => hash,
             _ => panic!("commit failed"),
         };
@@ -265,11 +273,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn checked_commit_fails_on_overflow() {
+        let mut state = create_test_state();
+        let root_hash = state.root_hash;
+        let key = TEST_PAIRS[0].key;
+        let effects: HashMap<Key, Transform> =
+            std::iter::once((key, Transform::AddInt32(std::i32::MAX))).collect();
+
+        match state
+            .commit(root_hash, effects.clone(), Arithmetic::Checked)
+            .unwrap()
+        {
+            CommitResult::Overflow => {}
+            other => panic!("commit should have overflowed: {:?}", other),
+        }
+        assert_eq!(state.root_hash, root_hash);
+
+        let updated_hash = match state
+            .commit(root_hash, effects, Arithmetic::Wrapping)
+            .unwrap()
+        {
+            CommitResult::Success(hash) => hash,
+            other => panic!("commit failed: {:?}", other),
+        };
+        let updated_checkout = state.checkout(updated_hash).unwrap().unwrap();
+        assert_eq!(
+            Some(Value::Int32(std::i32::MIN)),
+            updated_checkout.read(&key).unwrap()
+        );
+    }
+
     #[test]
     fn initial_state_has_the_expected_hash() {
         let expected_bytes = vec![
-            193u8, 151, 167, 126, 241, 141, 9, 163, 43, 169, 238, 19, 93, 87, 183, 131, 99, 160,
-            96, 216, 8, 39, 227, 218, 246, 90, 65, 57, 207, 242, 61, 205,
+            93u8, 252, 109, 141, 34, 183, 206, 247, 215, 204, 244, 17, 44, 84, 72, 93, 227, 49, 8,
+            165, 216, 127, 195, 101, 19, 150, 113, 46, 226, 253, 194, 130,
         ];
         let init_state = mocked_account([48u8; 32]);
         let global_state = InMemoryGlobalState::from_pairs(&init_state).unwrap();
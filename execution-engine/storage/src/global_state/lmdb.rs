@@ -6,7 +6,7 @@ use common::key::Key;
 use common::value::Value;
 use lmdb;
 use shared::newtypes::Blake2bHash;
-use shared::transform::Transform;
+use shared::transform::{Arithmetic, Transform};
 
 use error;
 use global_state::StateReader;
@@ -128,12 +128,14 @@ impl History for LmdbGlobalState {
         &mut self,
         prestate_hash: Blake2bHash,
         effects: HashMap<Key, Transform>,
+        arithmetic: Arithmetic,
     ) -> Result<CommitResult, Self::Error> {
         let commit_result = commit::<LmdbEnvironment, LmdbTrieStore, _, Self::Error>(
             &self.environment,
             &self.store,
             prestate_hash,
             effects,
+            arithmetic,
         )?;
         if let CommitResult::Success(root_hash) = commit_result {
             self.root_hash = root_hash;
@@ -253,7 +255,10 @@ mod tests {
             tmp
         };
 
-        let updated_hash = match state.commit(root_hash, effects).unwrap() {
+        let updated_hash = match state
+            .commit(root_hash, effects, Arithmetic::Wrapping)
+            .unwrap()
+        {
             CommitResult::Success(hash) => hash,
             _ => panic!("commit failed"),
         };
@@ -280,7 +285,10 @@ mod tests {
             tmp
         };
 
-        let updated_hash = match state.commit(root_hash, effects).unwrap() {
+        let updated_hash = match state
+            .commit(root_hash, effects, Arithmetic::Wrapping)
+            .unwrap()
+        {
             CommitResult::Success(hash) => hash,
             _ => panic!("commit failed"),
         };
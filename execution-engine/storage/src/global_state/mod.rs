@@ -7,7 +7,7 @@ use std::hash::BuildHasher;
 use common::key::Key;
 use common::value::Value;
 use shared::newtypes::Blake2bHash;
-use shared::transform::{self, Transform, TypeMismatch};
+use shared::transform::{self, Arithmetic, Transform, TypeMismatch};
 
 use trie::Trie;
 use trie_store::operations::{read, write, ReadResult, WriteResult};
@@ -28,6 +28,7 @@ pub enum CommitResult {
     Success(Blake2bHash),
     KeyNotFound(Key),
     TypeMismatch(TypeMismatch),
+    Overflow,
 }
 
 impl From<transform::Error> for CommitResult {
@@ -36,6 +37,7 @@ impl From<transform::Error> for CommitResult {
             transform::Error::TypeMismatch(type_mismatch) => {
                 CommitResult::TypeMismatch(type_mismatch)
             }
+            transform::Error::Overflow => CommitResult::Overflow,
         }
     }
 }
@@ -49,10 +51,12 @@ pub trait History {
 
     /// Applies changes and returns a new post state hash.
     /// block_hash is used for computing a deterministic and unique keys.
+    /// Numeric additions follow `arithmetic`.
     fn commit(
         &mut self,
         prestate_hash: Blake2bHash,
         effects: HashMap<Key, Transform>,
+        arithmetic: Arithmetic,
     ) -> Result<CommitResult, Self::Error>;
 }
 
@@ -61,6 +65,7 @@ pub fn commit<'a, R, S, H, E>(
     store: &S,
     prestate_hash: Blake2bHash,
     effects: HashMap<Key, Transform, H>,
+    arithmetic: Arithmetic,
 ) -> Result<CommitResult, E>
 where
     R: TransactionSource<'a, Handle = S::Handle>,
@@ -86,10 +91,12 @@ where
             (ReadResult::NotFound, _) => {
                 return Ok(CommitResult::KeyNotFound(key));
             }
-            (ReadResult::Found(current_value), transform) => match transform.apply(current_value) {
-                Ok(updated_value) => updated_value,
-                Err(err) => return Ok(err.into()),
-            },
+            (ReadResult::Found(current_value), transform) => {
+                match transform.apply_with(current_value, arithmetic) {
+                    Ok(updated_value) => updated_value,
+                    Err(err) => return Ok(err.into()),
+                }
+            }
             _x @ (ReadResult::RootNotFound, _) => panic!(stringify!(_x._1)),
         };
 
@@ -1,16 +1,16 @@
 extern crate parity_wasm;
 extern crate pwasm_utils;
 
+pub mod profiling;
 pub mod wasm_costs;
 
-use parity_wasm::elements::{deserialize_buffer, Error as ParityWasmError, Module};
+use parity_wasm::elements::{
+    deserialize_buffer, Error as ParityWasmError, ImportCountType, Module,
+};
 use pwasm_utils::{externalize_mem, inject_gas_counter, rules};
 use std::error::Error;
 use wasm_costs::WasmCosts;
 
-//NOTE: size of Wasm memory page is 64 KiB
-const MEM_PAGES: u32 = 64;
-
 #[derive(Debug)]
 pub enum PreprocessingError {
     InvalidImportsError(String),
@@ -29,15 +29,24 @@ pub trait Preprocessor<A> {
 
 pub struct WasmiPreprocessor {
     wasm_costs: WasmCosts,
-    // Number of memory pages.
-    mem_pages: u32,
+    // Whether modules are instrumented for gas profiling.
+    profiling: bool,
 }
 
 impl WasmiPreprocessor {
     pub fn new(wasm_costs: WasmCosts) -> WasmiPreprocessor {
         WasmiPreprocessor {
             wasm_costs,
-            mem_pages: MEM_PAGES,
+            profiling: false,
+        }
+    }
+
+    /// Creates a preprocessor which also instruments modules for gas profiling.
+    /// See `profiling::inject_profiling`.
+    pub fn with_profiling(wasm_costs: WasmCosts) -> WasmiPreprocessor {
+        WasmiPreprocessor {
+            wasm_costs,
+            profiling: true,
         }
     }
 }
@@ -46,8 +55,13 @@ impl Preprocessor<Module> for WasmiPreprocessor {
     fn preprocess(&self, module_bytes: &[u8]) -> Result<Module, PreprocessingError> {
         let from_parity_err = |err: ParityWasmError| DeserializeError(err.description().to_owned());
         let deserialized_module = deserialize_buffer(module_bytes).map_err(from_parity_err)?;
-        let ext_mod = externalize_mem(deserialized_module, None, self.mem_pages);
-        let gas_mod = inject_gas_counters(ext_mod, &self.wasm_costs)?;
+        //NOTE: size of Wasm memory page is 64 KiB
+        let ext_mod = externalize_mem(deserialized_module, None, self.wasm_costs.max_mem);
+        let imported_functions = ext_mod.import_count(ImportCountType::Function) as u32;
+        let mut gas_mod = inject_gas_counters(ext_mod, &self.wasm_costs)?;
+        if self.profiling {
+            gas_mod = profiling::inject_profiling(gas_mod, imported_functions)?;
+        }
         let module =
             pwasm_utils::stack_height::inject_limiter(gas_mod, self.wasm_costs.max_stack_height)
                 .map_err(|_| StackLimiterError)?;
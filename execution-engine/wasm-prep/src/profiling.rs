@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+
+use parity_wasm::elements::{
+    ExportEntry, GlobalEntry, GlobalSection, GlobalType, ImportCountType, InitExpr, Instruction,
+    Internal, Module, Section, ValueType,
+};
+
+use PreprocessingError;
+
+/// Name of the exported global which holds the index of the function being executed
+/// by a module instrumented with `inject_profiling`.
+pub const PROFILED_FUNCTION_GLOBAL: &str = "__profiled_function";
+
+/// Instruments `module` so that the index of the function being executed is always
+/// available in the `PROFILED_FUNCTION_GLOBAL` global, which lets the host attribute
+/// the gas charged by the injected gas counter to functions.
+///
+/// Indices are given in the function index space of the module before the gas counter
+/// was injected, so that they match the name section. `imported_functions` is the number
+/// of functions the module imported at that point.
+///
+/// The global is set when a function starts and again after every call it makes.
+/// The instrumentation should be injected after the gas counter, so it isn't metered.
+pub fn inject_profiling(
+    mut module: Module,
+    imported_functions: u32,
+) -> Result<Module, PreprocessingError> {
+    let global_index = add_profiled_function_global(&mut module)?;
+    if let Some(code_section) = module.code_section_mut() {
+        for (i, body) in code_section.bodies_mut().iter_mut().enumerate() {
+            let function_index = (imported_functions + i as u32) as i32;
+            let set_function = [
+                Instruction::I32Const(function_index),
+                Instruction::SetGlobal(global_index),
+            ];
+            let instructions = body.code_mut().elements_mut();
+            let mut profiled = Vec::with_capacity(instructions.len() + 2);
+            profiled.extend_from_slice(&set_function);
+            for instruction in instructions.drain(..) {
+                let is_call = match instruction {
+                    Instruction::Call(_) | Instruction::CallIndirect(_, _) => true,
+                    _ => false,
+                };
+                profiled.push(instruction);
+                if is_call {
+                    profiled.extend_from_slice(&set_function);
+                }
+            }
+            *instructions = profiled;
+        }
+    }
+    Ok(module)
+}
+
+fn add_profiled_function_global(module: &mut Module) -> Result<u32, PreprocessingError> {
+    if module.global_section().is_none() {
+        // The global section precedes the export, start, element, code and data sections.
+        let position = module
+            .sections()
+            .iter()
+            .position(|section| match section {
+                Section::Export(_)
+                | Section::Start(_)
+                | Section::Element(_)
+                | Section::Code(_)
+                | Section::Data(_) => true,
+                _ => false,
+            })
+            .unwrap_or_else(|| module.sections().len());
+        module.sections_mut().insert(
+            position,
+            Section::Global(GlobalSection::with_entries(Vec::new())),
+        );
+    }
+    let imported_globals = module.import_count(ImportCountType::Global) as u32;
+    let global_index = {
+        let globals = module
+            .global_section_mut()
+            .expect("Global section was inserted above")
+            .entries_mut();
+        globals.push(GlobalEntry::new(
+            GlobalType::new(ValueType::I32, true),
+            InitExpr::new(vec![Instruction::I32Const(-1), Instruction::End]),
+        ));
+        imported_globals + globals.len() as u32 - 1
+    };
+    module
+        .export_section_mut()
+        .ok_or(PreprocessingError::NoExportSection)?
+        .entries_mut()
+        .push(ExportEntry::new(
+            PROFILED_FUNCTION_GLOBAL.to_string(),
+            Internal::Global(global_index),
+        ));
+    Ok(global_index)
+}
+
+/// Reads the names of functions from the name section of `module`, if present.
+pub fn function_names(module: &Module) -> BTreeMap<u32, String> {
+    module
+        .sections()
+        .iter()
+        .filter_map(|section| match section {
+            Section::Custom(custom) if custom.name() == "name" => {
+                parse_function_names(custom.payload())
+            }
+            _ => None,
+        })
+        .next()
+        .unwrap_or_default()
+}
+
+/// Parses the function names subsection of the payload of a name section.
+fn parse_function_names(payload: &[u8]) -> Option<BTreeMap<u32, String>> {
+    const FUNCTION_NAMES_SUBSECTION: u32 = 1;
+    let mut reader = Reader(payload);
+    while !reader.0.is_empty() {
+        let id = reader.byte()?;
+        let size = reader.varuint32()? as usize;
+        let subsection = reader.bytes(size)?;
+        if u32::from(id) != FUNCTION_NAMES_SUBSECTION {
+            continue;
+        }
+        let mut reader = Reader(subsection);
+        let mut names = BTreeMap::new();
+        for _ in 0..reader.varuint32()? {
+            let index = reader.varuint32()?;
+            let length = reader.varuint32()? as usize;
+            let name = String::from_utf8(reader.bytes(length)?.to_vec()).ok()?;
+            names.insert(index, name);
+        }
+        return Some(names);
+    }
+    None
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Option<u8> {
+        let (first, rest) = self.0.split_first()?;
+        self.0 = rest;
+        Some(*first)
+    }
+
+    fn bytes(&mut self, length: usize) -> Option<&'a [u8]> {
+        if length > self.0.len() {
+            return None;
+        }
+        let (bytes, rest) = self.0.split_at(length);
+        self.0 = rest;
+        Some(bytes)
+    }
+
+    /// Reads an unsigned LEB128 encoded integer.
+    fn varuint32(&mut self) -> Option<u32> {
+        let mut result = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = self.byte()?;
+            result |= u32::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Some(result);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_function_names;
+
+    #[test]
+    fn function_names_are_parsed() {
+        let payload = vec![
+            // module name subsection, skipped
+            0, 2, 1, b'm', //
+            // function names subsection
+            1, 12, 2, // two names
+            0, 3, b'f', b'o', b'o', //
+            129, 1, 3, b'b', b'a', b'r', // index 129 takes two bytes
+        ];
+        let names = parse_function_names(&payload).unwrap();
+        assert_eq!(names.len(), 2);
+        assert_eq!(names[&0], "foo");
+        assert_eq!(names[&129], "bar");
+    }
+}
@@ -9,8 +9,10 @@ pub struct WasmCosts {
     pub mul: u32,
     /// Memory (load/store) operations multiplier.
     pub mem: u32,
-    /// Memory stipend. Amount of free memory (in 64kb pages) each contract can use for stack.
+    /// Memory stipend. Maximum amount of memory (in 64kb pages) a contract can start with.
     pub initial_mem: u32,
+    /// Maximum amount of memory (in 64kb pages) a contract can grow to.
+    pub max_mem: u32,
     /// Grow memory cost, per page (64kb)
     pub grow_mem: u32,
     /// Memory copy cost, per byte
@@ -26,12 +28,15 @@ pub struct WasmCosts {
 impl WasmCosts {
     pub fn from_version(protocol_version: u64) -> Option<WasmCosts> {
         match protocol_version {
-            1 => Some(WasmCosts {
+            // Version 2 only changes the arithmetic of the global state and the seeding
+            // of the rngs of deploys.
+            1 | 2 => Some(WasmCosts {
                 regular: 1,
                 div: 16,
                 mul: 4,
                 mem: 2,
-                initial_mem: 4096,
+                initial_mem: 64,
+                max_mem: 64,
                 grow_mem: 8192,
                 memcpy: 1,
                 max_stack_height: 64 * 1024,
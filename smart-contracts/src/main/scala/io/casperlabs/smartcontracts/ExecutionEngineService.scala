@@ -24,9 +24,14 @@ import scala.util.Either
   def exec(
       prestate: ByteString,
       deploys: Seq[Deploy],
-      protocolVersion: ProtocolVersion
+      protocolVersion: ProtocolVersion,
+      blockContext: BlockContext
   ): F[Either[Throwable, Seq[DeployResult]]]
-  def commit(prestate: ByteString, effects: Seq[TransformEntry]): F[Either[Throwable, ByteString]]
+  def commit(
+      prestate: ByteString,
+      effects: Seq[TransformEntry],
+      protocolVersion: ProtocolVersion
+  ): F[Either[Throwable, ByteString]]
   def computeBonds(hash: ByteString)(implicit log: Log[F]): F[Seq[Bond]]
   def setBonds(bonds: Map[PublicKey, Long]): F[Unit]
   def query(state: ByteString, baseKey: Key, path: Seq[String]): F[Either[Throwable, Value]]
@@ -43,8 +48,8 @@ class GrpcExecutionEngineService[F[_]: Defer: Sync: Log: TaskLift] private[smart
   private var bonds = initialBonds.map(p => Bond(ByteString.copyFrom(p._1), p._2)).toSeq
 
   override def emptyStateHash: ByteString = {
-    val arr: Array[Byte] = Array(193, 151, 167, 126, 241, 141, 9, 163, 43, 169, 238, 19, 93, 87,
-      183, 131, 99, 160, 96, 216, 8, 39, 227, 218, 246, 90, 65, 57, 207, 242, 61, 205).map(_.toByte)
+    val arr: Array[Byte] = Array(93, 252, 109, 141, 34, 183, 206, 247, 215, 204, 244, 17, 44, 84,
+      72, 93, 227, 49, 8, 165, 216, 127, 195, 101, 19, 150, 113, 46, 226, 253, 194, 130).map(_.toByte)
     ByteString.copyFrom(arr)
   }
 
@@ -64,11 +69,15 @@ class GrpcExecutionEngineService[F[_]: Defer: Sync: Log: TaskLift] private[smart
   override def exec(
       prestate: ByteString,
       deploys: Seq[Deploy],
-      protocolVersion: ProtocolVersion
+      protocolVersion: ProtocolVersion,
+      blockContext: BlockContext
   ): F[Either[Throwable, Seq[DeployResult]]] =
-    sendMessage(ExecRequest(prestate, deploys, Some(protocolVersion)), _.exec) {
+    sendMessage(
+      ExecRequest(prestate, deploys, Some(protocolVersion), Some(blockContext)),
+      _.exec
+    ) {
       _.result match {
-        case ExecResponse.Result.Success(ExecResult(deployResults)) =>
+        case ExecResponse.Result.Success(ExecResult(deployResults, _)) =>
           Right(deployResults)
         //TODO: Capture errors better than just as a string
         case ExecResponse.Result.Empty =>
@@ -77,14 +86,17 @@ class GrpcExecutionEngineService[F[_]: Defer: Sync: Log: TaskLift] private[smart
           Left(
             new SmartContractEngineError(s"Missing states: ${Base16.encode(missing.toByteArray)}")
           )
+        case ExecResponse.Result.Failure(error) =>
+          Left(new SmartContractEngineError(error))
       }
     }
 
   override def commit(
       prestate: ByteString,
-      effects: Seq[TransformEntry]
+      effects: Seq[TransformEntry],
+      protocolVersion: ProtocolVersion
   ): F[Either[Throwable, ByteString]] =
-    sendMessage(CommitRequest(prestate, effects), _.commit) {
+    sendMessage(CommitRequest(prestate, effects, Some(protocolVersion)), _.commit) {
       _.result match {
         case CommitResponse.Result.Success(CommitResult(poststateHash)) =>
           Right(poststateHash)
@@ -98,6 +110,8 @@ class GrpcExecutionEngineService[F[_]: Defer: Sync: Log: TaskLift] private[smart
           Left(SmartContractEngineError(s"Key not found in global state: $value"))
         case CommitResponse.Result.TypeMismatch(err) =>
           Left(SmartContractEngineError(err.toString))
+        case CommitResponse.Result.Overflow(_) =>
+          Left(SmartContractEngineError("Arithmetic overflow while applying effects"))
 
       }
     }